@@ -0,0 +1,270 @@
+use crate::{
+    common::webhooks::emit_webhook, config::Config, http_client::HttpClient, logger::Logger,
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    Database,
+};
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoostStatus {
+    Scheduled,
+    Active,
+    Drawing,
+    Drawn,
+    PartiallyClaimed,
+    Settled,
+    Expired,
+}
+
+impl BoostStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BoostStatus::Scheduled => "scheduled",
+            BoostStatus::Active => "active",
+            BoostStatus::Drawing => "drawing",
+            BoostStatus::Drawn => "drawn",
+            BoostStatus::PartiallyClaimed => "partially_claimed",
+            BoostStatus::Settled => "settled",
+            BoostStatus::Expired => "expired",
+        }
+    }
+}
+
+pub struct BoostProgress {
+    pub start_time: Option<i64>,
+    pub expiry: i64,
+    pub drawn_at: Option<i64>,
+    pub winners: Option<usize>,
+    pub claimed: usize,
+}
+
+// claim_window is expressed in seconds, all timestamps in milliseconds
+pub fn compute_boost_status(progress: &BoostProgress, claim_window: i64, now: i64) -> BoostStatus {
+    if let Some(start_time) = progress.start_time {
+        if now < start_time {
+            return BoostStatus::Scheduled;
+        }
+    }
+    if now < progress.expiry {
+        return BoostStatus::Active;
+    }
+    let Some(winners) = progress.winners else {
+        return BoostStatus::Drawing;
+    };
+    if progress.claimed >= winners {
+        return BoostStatus::Settled;
+    }
+    let claim_deadline = progress.drawn_at.unwrap_or(progress.expiry) + claim_window * 1000;
+    if now >= claim_deadline {
+        return BoostStatus::Expired;
+    }
+    if progress.claimed == 0 {
+        BoostStatus::Drawn
+    } else {
+        BoostStatus::PartiallyClaimed
+    }
+}
+
+// returns the winners of a boost which have a live claim in boost_claims
+pub fn get_claimed_winners(boost: &Document) -> (Vec<String>, Vec<String>) {
+    let winners: Vec<String> = boost
+        .get_array("winner")
+        .map(|winners| {
+            winners
+                .iter()
+                .filter_map(|winner| winner.as_str().map(|w| w.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let claims: Vec<&str> = boost
+        .get_array("claimed")
+        .map(|claims| claims.iter().filter_map(Bson::as_str).collect())
+        .unwrap_or_default();
+    winners
+        .into_iter()
+        .partition(|winner| claims.contains(&winner.as_str()))
+}
+
+pub fn get_boost_progress_pipeline(filter: Document) -> Vec<Document> {
+    vec![
+        doc! { "$match": filter },
+        doc! {
+            "$lookup": doc! {
+                "from": "quests",
+                "localField": "quests",
+                "foreignField": "id",
+                "as": "associated_quests"
+            }
+        },
+        doc! {
+            "$lookup": doc! {
+                "from": "boost_claims",
+                "let": doc! { "localId": "$id" },
+                "pipeline": [
+                    doc! {
+                        "$match": doc! {
+                            "$expr": doc! {
+                                "$and": [
+                                    doc! { "$eq": ["$id", "$$localId"] },
+                                    doc! { "$eq": ["$_cursor.to", null] }
+                                ]
+                            }
+                        }
+                    }
+                ],
+                "as": "claims"
+            }
+        },
+        doc! {
+            "$addFields": doc! {
+                "start_time": doc! { "$min": "$associated_quests.start_time" },
+                "claimed": "$claims.winner"
+            }
+        },
+        doc! {
+            "$project": doc! {
+                "_id": 0,
+                "associated_quests": 0,
+                "claims": 0
+            }
+        },
+    ]
+}
+
+pub async fn update_boosts_lifecycle(
+    db: &Database,
+    http: &HttpClient,
+    conf: &Config,
+    logger: &Logger,
+) {
+    let boost_collection = db.collection::<Document>("boosts");
+    let pipeline = get_boost_progress_pipeline(doc! {
+        "status": { "$nin": [BoostStatus::Settled.as_str()] }
+    });
+    let mut cursor = match boost_collection.aggregate(pipeline, None).await {
+        Ok(cursor) => cursor,
+        Err(e) => {
+            logger.warning(format!("Unable to load boosts lifecycle: {}", e));
+            return;
+        }
+    };
+
+    let now = Utc::now().timestamp_millis();
+    loop {
+        let boost = match cursor.try_next().await {
+            Ok(Some(boost)) => boost,
+            Ok(None) => break,
+            Err(e) => {
+                logger.warning(format!("Error reading boost lifecycle: {}", e));
+                break;
+            }
+        };
+        let (Ok(boost_id), Ok(expiry)) = (boost.get_i32("id"), boost.get_i64("expiry")) else {
+            logger.warning(format!("Skipping malformed boost document: {}", boost));
+            continue;
+        };
+
+        let (claimed, unclaimed) = get_claimed_winners(&boost);
+        let progress = BoostProgress {
+            start_time: boost.get("start_time").and_then(Bson::as_i64),
+            expiry,
+            drawn_at: boost.get("drawn_at").and_then(Bson::as_i64),
+            winners: boost
+                .get_array("winner")
+                .ok()
+                .map(|_| claimed.len() + unclaimed.len()),
+            claimed: claimed.len(),
+        };
+        let status = compute_boost_status(&progress, conf.quest_boost.claim_window, now);
+        let previous_status = boost.get_str("status").ok();
+        if previous_status == Some(status.as_str()) {
+            continue;
+        }
+
+        let update = doc! {
+            "$set": { "status": status.as_str() },
+            "$push": { "status_history": { "status": status.as_str(), "timestamp": now } },
+        };
+        if let Err(e) = boost_collection
+            .update_one(doc! { "id": boost_id }, update, None)
+            .await
+        {
+            logger.warning(format!(
+                "Unable to update status of boost {}: {}",
+                boost_id, e
+            ));
+            continue;
+        }
+
+        emit_webhook(
+            db,
+            http,
+            &conf.webhooks,
+            logger,
+            &format!("boost.{}", status.as_str()),
+            json!({
+                "boost_id": boost_id,
+                "previous_status": previous_status,
+                "status": status.as_str(),
+                "claimed": claimed,
+                "unclaimed": unclaimed,
+            }),
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(winners: Option<usize>, claimed: usize) -> BoostProgress {
+        BoostProgress {
+            start_time: Some(1_000),
+            expiry: 10_000,
+            drawn_at: None,
+            winners,
+            claimed,
+        }
+    }
+
+    #[test]
+    fn boost_before_expiry() {
+        assert_eq!(
+            compute_boost_status(&progress(None, 0), 10, 500),
+            BoostStatus::Scheduled
+        );
+        assert_eq!(
+            compute_boost_status(&progress(None, 0), 10, 5_000),
+            BoostStatus::Active
+        );
+    }
+
+    #[test]
+    fn boost_after_expiry() {
+        assert_eq!(
+            compute_boost_status(&progress(None, 0), 10, 11_000),
+            BoostStatus::Drawing
+        );
+        assert_eq!(
+            compute_boost_status(&progress(Some(3), 0), 10, 11_000),
+            BoostStatus::Drawn
+        );
+        assert_eq!(
+            compute_boost_status(&progress(Some(3), 1), 10, 11_000),
+            BoostStatus::PartiallyClaimed
+        );
+        assert_eq!(
+            compute_boost_status(&progress(Some(3), 3), 10, 11_000),
+            BoostStatus::Settled
+        );
+        assert_eq!(
+            compute_boost_status(&progress(Some(3), 1), 10, 20_000),
+            BoostStatus::Expired
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use crate::config::Config;
+use crate::http_client::{HttpClient, HttpError};
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    http::{HeaderName, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_bson, Bson, DateTime, Document},
+    options::{FindOneOptions, IndexOptions},
+    Database, IndexModel,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// timestamped copies of the reshaped discover/defi stats, written by the discover_stats job
+pub const DEFI_STATS_COLLECTION: &str = "defi_stats_snapshots";
+// freshness of the served snapshot, the bodies keep the shape of the upstream stats
+pub const UPDATED_AT_HEADER: HeaderName = HeaderName::from_static("x-data-updated-at");
+pub const STALE_HEADER: HeaderName = HeaderName::from_static("x-data-stale");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DefiStatsKind {
+    Pairs,
+    Lend,
+    Derivatives,
+    AltProtocols,
+}
+
+impl DefiStatsKind {
+    pub const ALL: [DefiStatsKind; 4] = [
+        DefiStatsKind::Pairs,
+        DefiStatsKind::Lend,
+        DefiStatsKind::Derivatives,
+        DefiStatsKind::AltProtocols,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DefiStatsKind::Pairs => "pairs",
+            DefiStatsKind::Lend => "lend",
+            DefiStatsKind::Derivatives => "derivatives",
+            DefiStatsKind::AltProtocols => "alt_protocols",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        DefiStatsKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+
+    fn endpoint<'a>(&self, conf: &'a Config) -> &'a str {
+        match self {
+            DefiStatsKind::Pairs => &conf.discover.pairs_api_endpoint,
+            DefiStatsKind::Lend => &conf.discover.lending_api_endpoint,
+            DefiStatsKind::Derivatives => &conf.discover.derivates_api_endpoint,
+            DefiStatsKind::AltProtocols => &conf.discover.alt_protocols_api_endpoint,
+        }
+    }
+}
+
+fn get_last_value(value: &Value) -> Option<Value> {
+    value.as_array()?.last().cloned()
+}
+
+// keeps the latest value of every series of a {key: [values]} response
+pub fn get_latest_flat(json: &Value) -> Map<String, Value> {
+    let mut stats = Map::new();
+    if let Value::Object(map) = json {
+        for (key, series) in map {
+            if let Some(last) = get_last_value(series) {
+                stats.insert(key.clone(), last);
+            }
+        }
+    }
+    stats
+}
+
+// keeps the latest value of every series of a {key: {sub_key: [values]}} response
+pub fn get_latest_nested(json: &Value) -> Map<String, Value> {
+    let mut stats = Map::new();
+    if let Value::Object(map) = json {
+        for (key, value) in map {
+            if value.is_object() {
+                stats.insert(key.clone(), Value::Object(get_latest_flat(value)));
+            }
+        }
+    }
+    stats
+}
+
+fn get_nimbora_strategy_map() -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    map.insert("angle".to_string(), "nstUSD".to_string());
+    map.insert("pendle-puffer-eth".to_string(), "nppETH".to_string());
+    map.insert("pendle-etherfi-eth".to_string(), "npeETH".to_string());
+    map.insert("spark".to_string(), "nsDAI".to_string());
+    map
+}
+
+async fn fetch_nimbora_data(http: &HttpClient) -> Result<Value, HttpError> {
+    let nimbora_endpoint = "https://stats.nimbora.io/yield-dex/strategies";
+    http.send(http.get(nimbora_endpoint)).await?.json()
+}
+
+// Replaces the APRs of the Nimbora strategies with the ones of the Nimbora API, strategies
+// unknown to it are removed
+pub fn apply_nimbora_aprs(stats: &mut Map<String, Value>, nimbora_strategies: &[Value]) {
+    let strategy_map = get_nimbora_strategy_map();
+    let Some(Value::Object(strategies)) = stats.get_mut("Nimbora") else {
+        return;
+    };
+    strategies.retain(|strategy_name, strategy| {
+        let Some(nimbora_symbol) = strategy_map.get(strategy_name) else {
+            return false;
+        };
+        let apr = nimbora_strategies
+            .iter()
+            .find(|s| s["symbol"].as_str().unwrap_or("") == nimbora_symbol)
+            .and_then(|s| s["apr"].as_str())
+            .and_then(|apr| apr.parse::<f64>().ok());
+        if let (Some(apr), Value::Object(strategy)) = (apr, strategy) {
+            strategy.insert(
+                "apr".to_string(),
+                Value::Number(
+                    serde_json::Number::from_f64(apr / 100.0)
+                        .unwrap_or(serde_json::Number::from(0)),
+                ),
+            );
+        }
+        true
+    });
+}
+
+// fetches the stats of kind from its upstream and keeps the latest value of each series
+pub async fn fetch_defi_stats(state: &AppState, kind: DefiStatsKind) -> Result<Value, String> {
+    let endpoint = kind.endpoint(&state.conf);
+    let json = state
+        .http
+        .send(state.http.get(endpoint))
+        .await
+        .and_then(|response| response.json::<Value>())
+        .map_err(|e| format!("{}", e))?;
+
+    let stats = match kind {
+        DefiStatsKind::Derivatives => get_latest_flat(&json),
+        DefiStatsKind::Pairs | DefiStatsKind::Lend => get_latest_nested(&json),
+        DefiStatsKind::AltProtocols => {
+            let mut stats = get_latest_nested(&json);
+            match fetch_nimbora_data(&state.http).await {
+                Ok(Value::Array(nimbora_strategies)) => {
+                    apply_nimbora_aprs(&mut stats, &nimbora_strategies)
+                }
+                _ => state
+                    .logger
+                    .info("Failed to fetch or parse Nimbora data".to_string()),
+            }
+            stats
+        }
+    };
+    Ok(Value::Object(stats))
+}
+
+pub async fn ensure_defi_stats_indexes(
+    db: &Database,
+    history_days: u64,
+) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(DEFI_STATS_COLLECTION);
+    let latest = IndexModel::builder()
+        .keys(doc! { "kind": 1, "timestamp": -1 })
+        .build();
+    collection.create_index(latest, None).await?;
+    let expiry = IndexModel::builder()
+        .keys(doc! { "created_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(history_days * 86_400))
+                .build(),
+        )
+        .build();
+    collection.create_index(expiry, None).await?;
+    Ok(())
+}
+
+// fetches the stats of kind and stores them as a new snapshot
+pub async fn refresh_defi_stats(state: &AppState, kind: DefiStatsKind) -> Result<Document, String> {
+    let stats = fetch_defi_stats(state, kind).await?;
+    let now = Utc::now().timestamp_millis();
+    let snapshot = doc! {
+        "kind": kind.name(),
+        "data": to_bson(&stats).map_err(|e| format!("{}", e))?,
+        "timestamp": now,
+        "created_at": DateTime::from_millis(now),
+    };
+    state
+        .db
+        .collection::<Document>(DEFI_STATS_COLLECTION)
+        .insert_one(&snapshot, None)
+        .await
+        .map_err(|e| format!("Error saving {} stats: {}", kind.name(), e))?;
+    Ok(snapshot)
+}
+
+pub async fn get_latest_snapshot(
+    db: &Database,
+    kind: DefiStatsKind,
+) -> Result<Option<Document>, mongodb::error::Error> {
+    let options = FindOneOptions::builder()
+        .sort(doc! { "timestamp": -1 })
+        .build();
+    db.collection::<Document>(DEFI_STATS_COLLECTION)
+        .find_one(doc! { "kind": kind.name() }, options)
+        .await
+}
+
+// Serves the latest snapshot of kind, its age is sent in the X-Data-Updated-At and X-Data-Stale
+// headers. Requests never call the upstreams, until the refresher stored a first snapshot the
+// stats are unavailable.
+pub async fn get_defi_stats_response(state: &AppState, kind: DefiStatsKind) -> Response {
+    let snapshot = match get_latest_snapshot(&state.db, kind).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!(
+                    "Try again later: {} stats are not available yet",
+                    kind.name()
+                ),
+            )
+                .into_response()
+        }
+        Err(e) => return get_error(format!("Error reading {} stats: {}", kind.name(), e)),
+    };
+    let updated_at = snapshot.get_i64("timestamp").unwrap_or(0);
+    let stale = Utc::now().timestamp_millis() - updated_at > state.conf.discover.stale_after * 1000;
+    (
+        StatusCode::OK,
+        [
+            (UPDATED_AT_HEADER, updated_at.to_string()),
+            (STALE_HEADER, stale.to_string()),
+        ],
+        Json(snapshot.get("data").cloned().unwrap_or(Bson::Null)),
+    )
+        .into_response()
+}
+
+// Reads the value at path in a snapshot, one segment per level: a protocol for flat kinds, a
+// protocol then a pair or strategy for nested ones
+pub fn get_path_expression(path: &[&str]) -> Bson {
+    path.iter().fold(Bson::from("$data"), |input, segment| {
+        // keys may contain dots, they can't be used in a field path
+        Bson::Document(doc! {
+            "$getField": { "field": { "$literal": *segment }, "input": input }
+        })
+    })
+}
+
+// values at path in the snapshots of kind between from and to, oldest first
+pub async fn get_defi_stats_history(
+    db: &Database,
+    kind: DefiStatsKind,
+    path: &[&str],
+    from: i64,
+    to: i64,
+    limit: i64,
+) -> Result<Vec<Document>, mongodb::error::Error> {
+    let pipeline = vec![
+        doc! { "$match": { "kind": kind.name(), "timestamp": { "$gte": from, "$lte": to } } },
+        doc! { "$sort": { "timestamp": -1 } },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "timestamp": 1,
+                "value": get_path_expression(path)
+            }
+        },
+        doc! { "$match": { "value": { "$ne": null } } },
+        doc! { "$limit": limit },
+        doc! { "$sort": { "timestamp": 1 } },
+    ];
+    db.collection::<Document>(DEFI_STATS_COLLECTION)
+        .aggregate(pipeline, None)
+        .await?
+        .try_collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_nested_paths() {
+        assert_eq!(
+            get_path_expression(&["ekubo", "ETH/USDC"]),
+            Bson::Document(doc! {
+                "$getField": {
+                    "field": { "$literal": "ETH/USDC" },
+                    "input": {
+                        "$getField": { "field": { "$literal": "ekubo" }, "input": "$data" }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_latest_values() {
+        let json = json!({
+            "ekubo": { "ETH/USDC": [{ "apr": 1 }, { "apr": 2 }], "STRK/ETH": [] },
+            "total": 3
+        });
+        assert_eq!(
+            Value::Object(get_latest_nested(&json)),
+            json!({ "ekubo": { "ETH/USDC": { "apr": 2 } } })
+        );
+        let json = json!({ "zklend": [{ "tvl": 1 }, { "tvl": 5 }], "nostra": [] });
+        assert_eq!(
+            Value::Object(get_latest_flat(&json)),
+            json!({ "zklend": { "tvl": 5 } })
+        );
+    }
+
+    #[test]
+    fn overrides_nimbora_aprs() {
+        let mut stats = get_latest_nested(&json!({
+            "Nimbora": { "spark": [{ "apr": 0.01 }], "unknown": [{ "apr": 0.5 }] },
+            "Other": { "spark": [{ "apr": 0.01 }] }
+        }));
+        apply_nimbora_aprs(&mut stats, &[json!({ "symbol": "nsDAI", "apr": "5" })]);
+        assert_eq!(
+            Value::Object(stats),
+            json!({
+                "Nimbora": { "spark": { "apr": 0.05 } },
+                "Other": { "spark": { "apr": 0.01 } }
+            })
+        );
+    }
+}
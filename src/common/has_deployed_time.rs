@@ -24,15 +24,13 @@ pub async fn execute_has_deployed_time(
         "https://api.starkscan.co/api/v0/transactions?from_block=1&limit=1&contract_address={}&order_by=asc",
         to_hex(*addr)
     );
-    let client = reqwest::Client::new();
-    match client
+    let request = state
+        .http
         .get(&url)
         .header("accept", "application/json")
-        .header("x-api-key", state.conf.starkscan.api_key.clone())
-        .send()
-        .await
-    {
-        Ok(response) => match response.json::<serde_json::Value>().await {
+        .header("x-api-key", state.conf.starkscan.api_key.clone());
+    match state.http.send(request).await {
+        Ok(response) => match response.json::<serde_json::Value>() {
             Ok(json) => {
                 if let Some(timestamp) = json["data"][0]["timestamp"].as_i64() {
                     match state
@@ -0,0 +1,145 @@
+use crate::config::Images;
+use crate::image_store::{ImageStore, THUMBNAILS_PREFIX};
+use image::{
+    imageops::FilterType, DynamicImage, GenericImageView, ImageError, ImageFormat, ImageReader,
+    Limits,
+};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+
+pub struct ProcessedImage {
+    pub name: String,
+    pub image: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+}
+
+// format of an upload read from its first bytes, the file name and content type sent by the
+// client aren't trusted
+pub fn sniff_format(data: &[u8]) -> Option<ImageFormat> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageFormat::Png)
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(ImageFormat::Jpeg)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some(ImageFormat::WebP)
+    } else {
+        None
+    }
+}
+
+// images are named after the hash of the uploaded file, uploading it again doesn't duplicate it
+pub fn get_image_name(data: &[u8]) -> String {
+    format!("{}.webp", hex::encode(&Sha256::digest(data)[..16]))
+}
+
+// whether name is an image or thumbnail name generated by get_image_name
+pub fn is_image_name(name: &str) -> bool {
+    let name = name.strip_prefix(THUMBNAILS_PREFIX).unwrap_or(name);
+    match name.strip_suffix(".webp") {
+        Some(hash) => hash.len() == 32 && hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')),
+        None => false,
+    }
+}
+
+pub fn get_thumbnail_name(name: &str) -> String {
+    format!("{}{}", THUMBNAILS_PREFIX, name)
+}
+
+fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    // the webp encoder only supports 8 bits rgb(a) images
+    let image = DynamicImage::ImageRgba8(image.to_rgba8());
+    let mut data = Cursor::new(Vec::new());
+    image
+        .write_to(&mut data, ImageFormat::WebP)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(data.into_inner())
+}
+
+// Decodes an image within the configured limits, the size of a compressed file says nothing of
+// the memory its pixels take
+pub fn decode_image(
+    data: &[u8],
+    format: ImageFormat,
+    conf: &Images,
+) -> Result<DynamicImage, String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(conf.max_image_width);
+    limits.max_image_height = Some(conf.max_image_height);
+    limits.max_alloc = Some(conf.max_alloc);
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    reader.decode().map_err(|e| match e {
+        ImageError::Limits(_) => format!(
+            "Images can't exceed {}x{} pixels",
+            conf.max_image_width, conf.max_image_height
+        ),
+        e => format!("Invalid image: {}", e),
+    })
+}
+
+// Checks an upload and converts it to webp, downscaled to the configured size, with a thumbnail.
+// Decoding is slow, it must run on a blocking thread.
+pub fn process_image(data: &[u8], conf: &Images) -> Result<ProcessedImage, String> {
+    if data.len() > conf.max_size {
+        return Err(format!("Images can't exceed {} bytes", conf.max_size));
+    }
+    let format = sniff_format(data).ok_or("Only png, jpeg, gif and webp images are allowed")?;
+    let mut image = decode_image(data, format, conf)?;
+    let (width, height) = image.dimensions();
+    if width > conf.max_dimension || height > conf.max_dimension {
+        image = image.resize(conf.max_dimension, conf.max_dimension, FilterType::Lanczos3);
+    }
+    let thumbnail = image.thumbnail(conf.thumbnail_size, conf.thumbnail_size);
+    Ok(ProcessedImage {
+        name: get_image_name(data),
+        image: encode_webp(&image)?,
+        thumbnail: encode_webp(&thumbnail)?,
+    })
+}
+
+// Quest images can be set to the name of an uploaded image, they are stored as its public url.
+// Other values are kept as is.
+pub fn get_image_url(store: &dyn ImageStore, value: &str) -> String {
+    if is_image_name(value) {
+        store.get_url(value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_formats() {
+        assert_eq!(
+            sniff_format(b"\x89PNG\r\n\x1a\n...."),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(
+            sniff_format(&[0xff, 0xd8, 0xff, 0xe0]),
+            Some(ImageFormat::Jpeg)
+        );
+        assert_eq!(
+            sniff_format(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(sniff_format(b"<svg xmlns=...>"), None);
+        assert_eq!(sniff_format(b"RIFF"), None);
+    }
+
+    #[test]
+    fn names_images_by_content() {
+        let name = get_image_name(b"image");
+        assert_eq!(name, get_image_name(b"image"));
+        assert_ne!(name, get_image_name(b"other image"));
+        assert!(is_image_name(&name));
+        assert!(is_image_name(&get_thumbnail_name(&name)));
+        assert!(!is_image_name("../config.toml"));
+        assert!(!is_image_name(&format!("../{}", name)));
+        assert!(!is_image_name("/visuals/quests/logo.webp"));
+    }
+}
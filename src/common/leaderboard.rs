@@ -0,0 +1,403 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use mongodb::{
+    bson::{doc, Document},
+    options::{IndexOptions, UpdateOptions},
+    Collection, Database, IndexModel,
+};
+
+// experience earned per address and per UTC day, used to compute windowed rankings
+pub const LEADERBOARD_BUCKETS_COLLECTION: &str = "leaderboard_daily";
+const DAY_MS: i64 = 86_400_000;
+
+#[derive(Debug, PartialEq)]
+pub enum LeaderboardWindow {
+    All,
+    // days in [start, end), both being day starts in milliseconds
+    Range { start: i64, end: i64 },
+}
+
+pub fn get_day_start(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(DAY_MS)
+}
+
+fn get_date_start(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis()
+}
+
+// Windows are resolved at day granularity: rolling windows include today, custom ranges
+// include both the start and end days.
+pub fn parse_leaderboard_window(
+    duration: &str,
+    start: Option<i64>,
+    end: Option<i64>,
+    now: DateTime<Utc>,
+) -> Result<LeaderboardWindow, String> {
+    let today = now.date_naive();
+    let tomorrow = get_date_start(today) + DAY_MS;
+    match duration {
+        "all" => Ok(LeaderboardWindow::All),
+        "week" => Ok(LeaderboardWindow::Range {
+            start: tomorrow - 7 * DAY_MS,
+            end: tomorrow,
+        }),
+        "month" => Ok(LeaderboardWindow::Range {
+            start: tomorrow - 30 * DAY_MS,
+            end: tomorrow,
+        }),
+        "calendar_week" => {
+            let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            let start = get_date_start(week_start);
+            Ok(LeaderboardWindow::Range {
+                start,
+                end: start + 7 * DAY_MS,
+            })
+        }
+        "calendar_month" => {
+            let month_start = today.with_day(1).unwrap();
+            let next_month_start = match today.month() {
+                12 => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1),
+                month => NaiveDate::from_ymd_opt(today.year(), month + 1, 1),
+            }
+            .unwrap();
+            Ok(LeaderboardWindow::Range {
+                start: get_date_start(month_start),
+                end: get_date_start(next_month_start),
+            })
+        }
+        "custom" => match (start, end) {
+            (Some(start), Some(end)) if start <= end => Ok(LeaderboardWindow::Range {
+                start: get_day_start(start),
+                end: get_day_start(end) + DAY_MS,
+            }),
+            (Some(_), Some(_)) => Err("start must be before end".to_string()),
+            _ => Err("start and end are required for custom ranges".to_string()),
+        },
+        _ => Err("Invalid duration".to_string()),
+    }
+}
+
+// Returns the collection to rank and the stages producing one document per address with its
+// "experience" and last "timestamp" for the window
+pub fn get_leaderboard_source(
+    db: &Database,
+    window: &LeaderboardWindow,
+) -> (Collection<Document>, Vec<Document>) {
+    match window {
+        LeaderboardWindow::All => (db.collection::<Document>("leaderboard_table"), vec![]),
+        LeaderboardWindow::Range { start, end } => (
+            db.collection::<Document>(LEADERBOARD_BUCKETS_COLLECTION),
+            vec![
+                doc! { "$match": { "day": { "$gte": start, "$lt": end } } },
+                doc! {
+                    "$group": {
+                        "_id": "$address",
+                        "experience": { "$sum": "$experience" },
+                        "timestamp": { "$max": "$timestamp" }
+                    }
+                },
+            ],
+        ),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RankingMetric {
+    // most experience first, ties broken by the earliest last timestamp
+    Xp,
+    // most completed quests first, ties broken by the shortest time spent completing them
+    Time,
+}
+
+impl RankingMetric {
+    pub fn parse(metric: &str) -> Result<Self, String> {
+        match metric {
+            "xp" => Ok(RankingMetric::Xp),
+            "time" => Ok(RankingMetric::Time),
+            _ => Err("Invalid sort_by, expected xp or time".to_string()),
+        }
+    }
+
+    pub fn sort(&self) -> Document {
+        match self {
+            RankingMetric::Xp => doc! {
+                "experience": -1,
+                "timestamp": 1,
+                "_id": 1
+            },
+            RankingMetric::Time => doc! {
+                "quests": -1,
+                "duration": 1,
+                "timestamp": 1,
+                "_id": 1
+            },
+        }
+    }
+}
+
+// Sorts documents produced by a leaderboard source and numbers them in a "rank" field
+pub fn get_ranking_stages(metric: RankingMetric) -> Vec<Document> {
+    vec![
+        doc! {
+            "$sort": metric.sort()
+        },
+        doc! {
+            "$addFields": doc! {
+                "tempSortField": 1
+            }
+        },
+        doc! {
+            "$setWindowFields": doc! {
+                "sortBy": doc! {
+                    "tempSortField": -1
+                },
+                "output": doc! {
+                    "rank": doc! {
+                        "$documentNumber": doc! {}
+                    }
+                }
+            }
+        },
+    ]
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LeaderboardScope {
+    Quest(u32),
+    Category(String),
+    // quests created by a partner
+    Issuer(String),
+}
+
+// Returns the stages producing one document per address with the "experience" of the quests of
+// the scope it completed, the number of completed "quests", their total completion "duration"
+// (first to last task) and the "timestamp" of its last completion
+pub fn get_scoped_leaderboard_source(
+    db: &Database,
+    scope: &LeaderboardScope,
+) -> (Collection<Document>, Vec<Document>) {
+    let filter = match scope {
+        LeaderboardScope::Quest(quest_id) => doc! { "id": quest_id },
+        LeaderboardScope::Category(category) => doc! { "category": category },
+        LeaderboardScope::Issuer(issuer) => doc! { "issuer": issuer },
+    };
+    (
+        db.collection::<Document>("quests"),
+        vec![
+            doc! { "$match": filter },
+            doc! {
+                "$lookup": {
+                    "from": "tasks",
+                    "localField": "id",
+                    "foreignField": "quest_id",
+                    "as": "tasks"
+                }
+            },
+            doc! {
+                "$project": {
+                    "_id": 0,
+                    "quest_id": "$id",
+                    "experience": 1,
+                    "task_ids": "$tasks.id",
+                    "task_count": { "$size": "$tasks" }
+                }
+            },
+            doc! { "$match": { "task_count": { "$gt": 0 } } },
+            doc! {
+                "$lookup": {
+                    "from": "completed_tasks",
+                    "localField": "task_ids",
+                    "foreignField": "task_id",
+                    "as": "completions"
+                }
+            },
+            doc! { "$unwind": "$completions" },
+            doc! {
+                "$group": {
+                    "_id": { "quest_id": "$quest_id", "address": "$completions.address" },
+                    "done": { "$addToSet": "$completions.task_id" },
+                    "task_count": { "$first": "$task_count" },
+                    "experience": { "$first": "$experience" },
+                    "first": { "$min": "$completions.timestamp" },
+                    "last": { "$max": "$completions.timestamp" }
+                }
+            },
+            doc! {
+                "$match": {
+                    "$expr": { "$eq": [{ "$size": "$done" }, "$task_count"] }
+                }
+            },
+            doc! {
+                "$group": {
+                    "_id": "$_id.address",
+                    "experience": { "$sum": "$experience" },
+                    "quests": { "$sum": 1 },
+                    "duration": { "$sum": { "$subtract": ["$last", "$first"] } },
+                    "timestamp": { "$max": "$last" }
+                }
+            },
+        ],
+    )
+}
+
+pub async fn add_experience_to_bucket(
+    db: &Database,
+    address: &str,
+    experience: i64,
+    timestamp: f64,
+) -> Result<(), mongodb::error::Error> {
+    let buckets_collection = db.collection::<Document>(LEADERBOARD_BUCKETS_COLLECTION);
+    let filter = doc! { "address": address, "day": get_day_start(timestamp as i64) };
+    let update = doc! {
+        "$inc": { "experience": experience },
+        "$max": { "timestamp": timestamp },
+    };
+    let options = UpdateOptions::builder().upsert(true).build();
+    buckets_collection
+        .update_one(filter, update, options)
+        .await?;
+    Ok(())
+}
+
+// rebuilds the daily buckets of addresses (of every address when None) from the user_exp events
+pub async fn rebuild_leaderboard_buckets(
+    db: &Database,
+    addresses: Option<&[String]>,
+) -> Result<(), mongodb::error::Error> {
+    let buckets_collection = db.collection::<Document>(LEADERBOARD_BUCKETS_COLLECTION);
+    let unique_bucket = IndexModel::builder()
+        .keys(doc! { "address": 1, "day": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    buckets_collection.create_index(unique_bucket, None).await?;
+    let day_only = IndexModel::builder().keys(doc! { "day": 1 }).build();
+    buckets_collection.create_index(day_only, None).await?;
+
+    let mut pipeline = vec![];
+    if let Some(addresses) = addresses {
+        buckets_collection
+            .delete_many(doc! { "address": { "$in": addresses } }, None)
+            .await?;
+        pipeline.push(doc! { "$match": { "address": { "$in": addresses } } });
+    }
+    pipeline.extend(vec![
+        doc! {
+            "$addFields": {
+                "day": {
+                    "$subtract": [
+                        { "$toLong": "$timestamp" },
+                        { "$mod": [{ "$toLong": "$timestamp" }, DAY_MS] }
+                    ]
+                }
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": { "address": "$address", "day": "$day" },
+                "experience": { "$sum": "$experience" },
+                "timestamp": { "$max": "$timestamp" }
+            }
+        },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "address": "$_id.address",
+                "day": "$_id.day",
+                "experience": 1,
+                "timestamp": 1
+            }
+        },
+        doc! {
+            "$merge": {
+                "into": LEADERBOARD_BUCKETS_COLLECTION,
+                "on": ["address", "day"],
+                "whenMatched": "replace",
+                "whenNotMatched": "insert"
+            }
+        },
+    ]);
+    db.collection::<Document>("user_exp")
+        .aggregate(pipeline, None)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Wednesday 2024-05-15 13:20:00 UTC
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 15, 13, 20, 0).unwrap()
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> i64 {
+        get_date_start(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    #[test]
+    fn rolling_windows_include_today() {
+        assert_eq!(
+            parse_leaderboard_window("week", None, None, now()),
+            Ok(LeaderboardWindow::Range {
+                start: day(2024, 5, 9),
+                end: day(2024, 5, 16)
+            })
+        );
+        assert_eq!(
+            parse_leaderboard_window("month", None, None, now()),
+            Ok(LeaderboardWindow::Range {
+                start: day(2024, 4, 16),
+                end: day(2024, 5, 16)
+            })
+        );
+    }
+
+    #[test]
+    fn calendar_windows() {
+        assert_eq!(
+            parse_leaderboard_window("calendar_week", None, None, now()),
+            Ok(LeaderboardWindow::Range {
+                start: day(2024, 5, 13),
+                end: day(2024, 5, 20)
+            })
+        );
+        let december = Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 0).unwrap();
+        assert_eq!(
+            parse_leaderboard_window("calendar_month", None, None, december),
+            Ok(LeaderboardWindow::Range {
+                start: day(2024, 12, 1),
+                end: day(2025, 1, 1)
+            })
+        );
+    }
+
+    #[test]
+    fn custom_range_includes_end_day() {
+        let start = day(2024, 1, 1) + 5_000;
+        let end = day(2024, 1, 31) + 5_000;
+        assert_eq!(
+            parse_leaderboard_window("custom", Some(start), Some(end), now()),
+            Ok(LeaderboardWindow::Range {
+                start: day(2024, 1, 1),
+                end: day(2024, 2, 1)
+            })
+        );
+        assert!(parse_leaderboard_window("custom", Some(end), Some(start), now()).is_err());
+        assert!(parse_leaderboard_window("custom", None, Some(end), now()).is_err());
+        assert!(parse_leaderboard_window("year", None, None, now()).is_err());
+    }
+
+    #[test]
+    fn ranking_metrics() {
+        assert_eq!(RankingMetric::parse("xp"), Ok(RankingMetric::Xp));
+        assert_eq!(RankingMetric::parse("time"), Ok(RankingMetric::Time));
+        assert!(RankingMetric::parse("speed").is_err());
+        let time_sort = RankingMetric::Time.sort();
+        let keys: Vec<&String> = time_sort.keys().collect();
+        assert_eq!(keys, ["quests", "duration", "timestamp", "_id"]);
+    }
+}
@@ -1,5 +1,21 @@
+pub mod boost_lifecycle;
+pub mod defi_stats;
 pub mod get_achievement;
 pub mod has_deployed_time;
+pub mod images;
+pub mod leaderboard;
+pub mod nft_claims;
+pub mod nft_rewards;
+pub mod quest_eligibility;
+pub mod quest_prerequisites;
+pub mod seasons;
+pub mod share_cards;
+pub mod sybil;
+pub mod typed_data;
+pub mod verification_cache;
 pub mod verify_has_nft;
 pub mod verify_has_root_domain;
 pub mod verify_quiz;
+pub mod webhooks;
+pub mod xp_adjustments;
+pub mod xp_ledger;
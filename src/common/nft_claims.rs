@@ -0,0 +1,258 @@
+use crate::utils::is_duplicate_key;
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Bson, DateTime, Document},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+use starknet::core::{crypto::pedersen_hash, types::FieldElement};
+
+// NFT claims signed for each (address, quest, level), marked as minted by the nft_mints job
+pub const NFT_CLAIMS_COLLECTION: &str = "nft_claims";
+// salts tried before giving up on allocating a token id
+const MAX_TOKEN_ID_ATTEMPTS: u32 = 8;
+
+// Token id of the NFT of a quest level for addr, derived from the three of them. A salt is mixed
+// in when the id of a lower salt already belongs to another claim.
+pub fn get_token_id(addr: &FieldElement, quest_id: u32, nft_level: u32, salt: u32) -> u64 {
+    let mut hash = pedersen_hash(
+        &pedersen_hash(addr, &FieldElement::from(quest_id)),
+        &FieldElement::from(nft_level),
+    );
+    if salt > 0 {
+        hash = pedersen_hash(&hash, &FieldElement::from(salt));
+    }
+    let seed = u64::from_be_bytes(hash.to_bytes_be()[24..].try_into().unwrap());
+    match nft_level < 100 {
+        true => nft_level as u64 + 100 * (seed % (2u64.pow(32))),
+        false => (seed / 100 / 0x2000000 * 0x2000000 + nft_level as u64) * 100 + 99,
+    }
+}
+
+// Level of the NFT with token_id, the inverse of the layout of get_token_id. Levels from 100 are
+// flagged by a 99 suffix, so level 99 can't be decoded, only read from its claim.
+pub fn get_level_from_token_id(token_id: u64) -> u32 {
+    match token_id % 100 {
+        99 => ((token_id / 100) % 0x2000000) as u32,
+        level => level as u32,
+    }
+}
+
+// level recorded by the claim of the token, decoded from the id for tokens issued before claims
+// were recorded
+pub fn get_token_level(claim: Option<&Document>, token_id: u64) -> u32 {
+    let level = claim.and_then(|claim| match claim.get("level") {
+        Some(Bson::Int32(level)) => u32::try_from(*level).ok(),
+        Some(Bson::Int64(level)) => u32::try_from(*level).ok(),
+        _ => None,
+    });
+    level.unwrap_or_else(|| get_level_from_token_id(token_id))
+}
+
+pub async fn get_claimed_level(db: &Database, token_id: u64) -> Result<u32, mongodb::error::Error> {
+    let claim = db
+        .collection::<Document>(NFT_CLAIMS_COLLECTION)
+        .find_one(doc! { "token_id": token_id.to_string() }, None)
+        .await?;
+    Ok(get_token_level(claim.as_ref(), token_id))
+}
+
+pub async fn ensure_nft_claims_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(NFT_CLAIMS_COLLECTION);
+    let key = IndexModel::builder()
+        .keys(doc! { "address": 1, "quest_id": 1, "level": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    collection.create_index(key, None).await?;
+    // a token id belongs to a single claim
+    let token = IndexModel::builder()
+        .keys(doc! { "token_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    collection.create_index(token, None).await?;
+    Ok(())
+}
+
+async fn get_claimed_token_id(
+    db: &Database,
+    addr: &FieldElement,
+    quest_id: u32,
+    nft_level: u32,
+) -> Result<Option<u64>, mongodb::error::Error> {
+    let claim = db
+        .collection::<Document>(NFT_CLAIMS_COLLECTION)
+        .find_one(
+            doc! { "address": addr.to_string(), "quest_id": quest_id, "level": nft_level },
+            None,
+        )
+        .await?;
+    Ok(claim.and_then(|claim| claim.get_str("token_id").ok()?.parse().ok()))
+}
+
+// Returns the token id of the claim of a quest level by addr, recording the claim the first time.
+// Every signature issued for a level mints the same token, which the contract accepts only once.
+pub async fn allocate_token_id(
+    db: &Database,
+    addr: &FieldElement,
+    quest_id: u32,
+    task_id: u32,
+    nft_level: u32,
+) -> Result<u64, String> {
+    let error = |e: mongodb::error::Error| format!("Error saving claim: {}", e);
+    if let Some(token_id) = get_claimed_token_id(db, addr, quest_id, nft_level)
+        .await
+        .map_err(error)?
+    {
+        return Ok(token_id);
+    }
+
+    let collection = db.collection::<Document>(NFT_CLAIMS_COLLECTION);
+    for salt in 0..MAX_TOKEN_ID_ATTEMPTS {
+        let token_id = get_token_id(addr, quest_id, nft_level, salt);
+        let claim = doc! {
+            "address": addr.to_string(),
+            "quest_id": quest_id,
+            "level": nft_level,
+            "task_id": task_id,
+            "token_id": token_id.to_string(),
+            "minted": false,
+            "created_at": DateTime::from_millis(Utc::now().timestamp_millis()),
+        };
+        match collection.insert_one(claim, None).await {
+            Ok(_) => return Ok(token_id),
+            // either a concurrent request recorded this claim or the id is taken by another one
+            Err(e) if is_duplicate_key(&e) => {
+                if let Some(token_id) = get_claimed_token_id(db, addr, quest_id, nft_level)
+                    .await
+                    .map_err(error)?
+                {
+                    return Ok(token_id);
+                }
+            }
+            Err(e) => return Err(error(e)),
+        }
+    }
+    Err(format!(
+        "No free token id for level {} of quest {}",
+        nft_level, quest_id
+    ))
+}
+
+// Recipient and token id of a Transfer event from the zero address. Cairo 1 contracts index
+// from, to and token_id in the keys, older ones emit them as data.
+pub fn parse_mint(keys: &[FieldElement], data: &[FieldElement]) -> Option<(FieldElement, u64)> {
+    let fields = if keys.len() == 5 { &keys[1..] } else { data };
+    let [from, to, low, high] = fields else {
+        return None;
+    };
+    if *from != FieldElement::ZERO || *high != FieldElement::ZERO {
+        return None;
+    }
+    let low = low.to_bytes_be();
+    if low[..24].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some((*to, u64::from_be_bytes(low[24..].try_into().unwrap())))
+}
+
+// marks the claim of the token minted by to, tokens not issued by us are ignored
+pub async fn mark_nft_minted(
+    db: &Database,
+    to: &FieldElement,
+    token_id: u64,
+    block_number: u64,
+    transaction_hash: &FieldElement,
+) -> Result<bool, mongodb::error::Error> {
+    let result = db
+        .collection::<Document>(NFT_CLAIMS_COLLECTION)
+        .update_one(
+            doc! { "address": to.to_string(), "token_id": token_id.to_string() },
+            doc! { "$set": {
+                "minted": true,
+                "block_number": block_number as i64,
+                "transaction_hash": transaction_hash.to_string(),
+            } },
+            None,
+        )
+        .await?;
+    Ok(result.matched_count > 0)
+}
+
+// quests of which addr minted at least one NFT, among quest_ids when set
+pub async fn get_claimed_quests(
+    db: &Database,
+    addr: &FieldElement,
+    quest_ids: Option<&[u32]>,
+) -> Result<Vec<u32>, mongodb::error::Error> {
+    let mut filter = doc! { "address": addr.to_string(), "minted": true };
+    if let Some(quest_ids) = quest_ids {
+        filter.insert("quest_id", doc! { "$in": quest_ids });
+    }
+    let quest_ids = db
+        .collection::<Document>(NFT_CLAIMS_COLLECTION)
+        .distinct("quest_id", filter, None)
+        .await?;
+    Ok(quest_ids
+        .iter()
+        .filter_map(|id| id.as_i64().or(id.as_i32().map(i64::from)))
+        .map(|id| id as u32)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_ids_are_deterministic() {
+        let addr = FieldElement::from(123u32);
+        let token_id = get_token_id(&addr, 4, 2, 0);
+        assert_eq!(token_id, get_token_id(&addr, 4, 2, 0));
+        assert_ne!(token_id, get_token_id(&addr, 4, 3, 0));
+        assert_ne!(token_id, get_token_id(&FieldElement::from(124u32), 4, 2, 0));
+        assert_ne!(token_id, get_token_id(&addr, 4, 2, 1));
+        // the level stays readable from the token id
+        assert_eq!(token_id % 100, 2);
+        assert_eq!(get_token_id(&addr, 4, 2, 1) % 100, 2);
+        assert_eq!(get_token_id(&addr, 4, 150, 0) % 100, 99);
+    }
+
+    #[test]
+    fn decodes_levels() {
+        let addr = FieldElement::from(123u32);
+        for level in [1, 2, 42, 98, 100, 150, 4242] {
+            assert_eq!(
+                get_level_from_token_id(get_token_id(&addr, 4, level, 0)),
+                level
+            );
+        }
+    }
+
+    #[test]
+    fn reads_level_99_from_claims() {
+        let token_id = get_token_id(&FieldElement::from(123u32), 4, 99, 0);
+        let claim = doc! { "token_id": token_id.to_string(), "level": 99 };
+        assert_eq!(get_token_level(Some(&claim), token_id), 99);
+        // tokens without a claim are decoded
+        assert_eq!(get_token_level(None, 4202), 2);
+    }
+
+    #[test]
+    fn parses_mints() {
+        let to = FieldElement::from(7u32);
+        let id = FieldElement::from(4202u32);
+        let selector = FieldElement::from(1u32);
+        let zero = FieldElement::ZERO;
+        assert_eq!(
+            parse_mint(&[selector, zero, to, id, zero], &[]),
+            Some((to, 4202))
+        );
+        assert_eq!(
+            parse_mint(&[selector], &[zero, to, id, zero]),
+            Some((to, 4202))
+        );
+        // transfers between users aren't mints
+        assert_eq!(parse_mint(&[selector], &[to, to, id, zero]), None);
+        assert_eq!(parse_mint(&[selector], &[zero, to, id]), None);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::models::{NFTUri, NftUnlock};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::Database;
+use std::collections::HashSet;
+
+// whether the completed tasks unlock a level of a quest made of quest_tasks
+pub fn is_level_unlocked(
+    unlock: Option<&NftUnlock>,
+    quest_tasks: &[u32],
+    completed: &HashSet<u32>,
+) -> bool {
+    let all_completed =
+        |tasks: &[u32]| !tasks.is_empty() && tasks.iter().all(|task| completed.contains(task));
+    let Some(unlock) = unlock.filter(|u| u.min_tasks.is_some() || u.required_tasks.is_some())
+    else {
+        return all_completed(quest_tasks);
+    };
+
+    if let Some(required_tasks) = &unlock.required_tasks {
+        if !all_completed(required_tasks) {
+            return false;
+        }
+    }
+    if let Some(min_tasks) = unlock.min_tasks {
+        let among = unlock.among.as_deref().unwrap_or(quest_tasks);
+        let done = among.iter().filter(|task| completed.contains(task)).count();
+        if done < min_tasks as usize {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn validate_unlock(unlock: &NftUnlock) -> Result<(), String> {
+    if let Some(min_tasks) = unlock.min_tasks {
+        if min_tasks == 0 {
+            return Err("min_tasks must be positive".to_string());
+        }
+        if let Some(among) = &unlock.among {
+            if among.len() < min_tasks as usize {
+                return Err(format!(
+                    "min_tasks can't exceed the {} tasks of among",
+                    among.len()
+                ));
+            }
+        }
+    }
+    if unlock.required_tasks.as_ref().is_some_and(|t| t.is_empty()) {
+        return Err("required_tasks can't be empty".to_string());
+    }
+    Ok(())
+}
+
+// Checks the claim tasks of the levels of a quest, None standing for the last task of the quest.
+// The contract accepts a single NFT per task so they must be distinct tasks of the quest.
+pub fn validate_claim_tasks(
+    quest_tasks: &[u32],
+    claim_tasks: &[Option<u32>],
+) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for claim_task in claim_tasks {
+        if let Some(task_id) = claim_task {
+            if !quest_tasks.contains(task_id) {
+                return Err(format!("Task {} doesn't belong to the quest", task_id));
+            }
+        }
+        let task_id = claim_task.or_else(|| quest_tasks.iter().max().copied());
+        if !seen.insert(task_id) {
+            return Err(match task_id {
+                Some(task_id) => format!(
+                    "Several levels of the quest are claimed with task {}",
+                    task_id
+                ),
+                None => "Several levels of the quest need a claim_task_id".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// tasks of a quest and claim tasks of its levels, except the level being updated
+pub async fn get_quest_claim_tasks(
+    db: &Database,
+    quest_id: i64,
+    except_level: Option<i64>,
+) -> Result<(Vec<u32>, Vec<Option<u32>>), mongodb::error::Error> {
+    let quest_tasks = db
+        .collection::<Document>("tasks")
+        .distinct("id", doc! { "quest_id": quest_id }, None)
+        .await?
+        .iter()
+        .filter_map(|id| match id {
+            Bson::Int32(id) => u32::try_from(*id).ok(),
+            Bson::Int64(id) => u32::try_from(*id).ok(),
+            _ => None,
+        })
+        .collect();
+    let claim_tasks = db
+        .collection::<NFTUri>("nft_uri")
+        .find(doc! { "quest_id": quest_id }, None)
+        .await?
+        .try_collect::<Vec<NFTUri>>()
+        .await?
+        .into_iter()
+        .filter(|level| Some(level.id) != except_level)
+        .map(|level| level.claim_task_id)
+        .collect();
+    Ok((quest_tasks, claim_tasks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUEST_TASKS: &[u32] = &[1, 2, 3, 4, 5, 6];
+
+    fn unlock(
+        min_tasks: Option<u32>,
+        among: Option<Vec<u32>>,
+        required: Option<Vec<u32>>,
+    ) -> NftUnlock {
+        NftUnlock {
+            min_tasks,
+            among,
+            required_tasks: required,
+        }
+    }
+
+    #[test]
+    fn unlocks_bronze_silver_gold() {
+        // 6 is a bonus task
+        let bronze = unlock(Some(3), Some(vec![1, 2, 3, 4, 5]), None);
+        let silver = unlock(None, None, Some(vec![1, 2, 3, 4, 5]));
+        let gold = unlock(None, None, Some(vec![1, 2, 3, 4, 5, 6]));
+        let levels = |completed: &[u32]| {
+            let completed: HashSet<u32> = completed.iter().copied().collect();
+            [&bronze, &silver, &gold]
+                .map(|level| is_level_unlocked(Some(level), QUEST_TASKS, &completed))
+        };
+        assert_eq!(levels(&[1, 2]), [false, false, false]);
+        assert_eq!(levels(&[1, 4, 6]), [false, false, false]);
+        assert_eq!(levels(&[2, 3, 5]), [true, false, false]);
+        assert_eq!(levels(&[1, 2, 3, 4, 5]), [true, true, false]);
+        assert_eq!(levels(&[1, 2, 3, 4, 5, 6]), [true, true, true]);
+    }
+
+    #[test]
+    fn defaults_to_every_task() {
+        let completed: HashSet<u32> = [1, 2, 3, 4, 5].into_iter().collect();
+        assert!(!is_level_unlocked(None, QUEST_TASKS, &completed));
+        assert!(is_level_unlocked(None, &[1, 2, 3], &completed));
+        assert!(!is_level_unlocked(None, &[], &completed));
+        let empty = unlock(None, Some(vec![1]), None);
+        assert!(!is_level_unlocked(Some(&empty), QUEST_TASKS, &completed));
+        // min_tasks without among counts every task of the quest
+        let any_four = unlock(Some(4), None, None);
+        assert!(is_level_unlocked(Some(&any_four), QUEST_TASKS, &completed));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(validate_unlock(&unlock(Some(0), None, None)).is_err());
+        assert!(validate_unlock(&unlock(Some(3), Some(vec![1, 2]), None)).is_err());
+        assert!(validate_unlock(&unlock(None, None, Some(vec![]))).is_err());
+        assert!(validate_unlock(&unlock(Some(2), Some(vec![1, 2]), Some(vec![3]))).is_ok());
+    }
+
+    #[test]
+    fn validates_claim_tasks() {
+        assert!(validate_claim_tasks(QUEST_TASKS, &[Some(4), Some(5), None]).is_ok());
+        // both default to the last task
+        assert!(validate_claim_tasks(QUEST_TASKS, &[Some(4), None, None]).is_err());
+        assert!(validate_claim_tasks(QUEST_TASKS, &[Some(6), None]).is_err());
+        assert!(validate_claim_tasks(QUEST_TASKS, &[Some(7)]).is_err());
+        assert!(validate_claim_tasks(&[], &[None]).is_ok());
+        assert!(validate_claim_tasks(&[], &[None, None]).is_err());
+    }
+}
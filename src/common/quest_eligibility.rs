@@ -0,0 +1,257 @@
+use crate::{
+    common::quest_prerequisites::get_missing_prerequisites,
+    models::{AppState, QuestDocument, QuestPrerequisite},
+    utils::get_error,
+};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, Bson, Document};
+use serde_json::json;
+use starknet::{
+    core::types::{BlockId, BlockTag, FieldElement, FunctionCall},
+    macros::selector,
+    providers::Provider,
+};
+
+// mandatory_domain value requiring a (non expired) root domain, any other value requires a
+// subdomain of that root domain, e.g. "braavos" for *.braavos.stark
+const ROOT_DOMAIN: &str = "root";
+
+#[derive(Debug)]
+pub enum EligibilityError {
+    QuestNotFound,
+    QuestDisabled,
+    QuestNotStarted,
+    QuestExpired,
+    MissingDomain(String),
+    MissingPrerequisites(Vec<QuestPrerequisite>),
+    Internal(String),
+}
+
+impl EligibilityError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EligibilityError::QuestNotFound => "quest_not_found",
+            EligibilityError::QuestDisabled => "quest_disabled",
+            EligibilityError::QuestNotStarted => "quest_not_started",
+            EligibilityError::QuestExpired => "quest_expired",
+            EligibilityError::MissingDomain(_) => "missing_domain",
+            EligibilityError::MissingPrerequisites(_) => "missing_prerequisites",
+            EligibilityError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl std::fmt::Display for EligibilityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EligibilityError::QuestNotFound => write!(f, "Quest not found"),
+            EligibilityError::QuestDisabled => write!(f, "Quest is disabled"),
+            EligibilityError::QuestNotStarted => write!(f, "Quest has not started yet"),
+            EligibilityError::QuestExpired => write!(f, "Quest has expired"),
+            EligibilityError::MissingDomain(domain) if domain == ROOT_DOMAIN => {
+                write!(f, "A root domain is required for this quest")
+            }
+            EligibilityError::MissingDomain(domain) => {
+                write!(f, "A {}.stark subdomain is required for this quest", domain)
+            }
+            EligibilityError::MissingPrerequisites(missing) => write!(
+                f,
+                "Quest is locked, you must first complete: {}",
+                missing
+                    .iter()
+                    .map(|prerequisite| prerequisite.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            EligibilityError::Internal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl IntoResponse for EligibilityError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            EligibilityError::Internal(e) => return get_error(e.clone()),
+            EligibilityError::QuestNotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::FORBIDDEN,
+        };
+        let mut body = json!({
+            "error_code": self.code(),
+            "error": self.to_string(),
+        });
+        if let EligibilityError::MissingPrerequisites(missing) = &self {
+            body["missing_prerequisites"] = json!(missing);
+        }
+        (status, Json(body)).into_response()
+    }
+}
+
+pub fn check_quest_schedule(
+    disabled: bool,
+    start_time: i64,
+    expiry: Option<i64>,
+    now: i64,
+) -> Result<(), EligibilityError> {
+    if disabled {
+        return Err(EligibilityError::QuestDisabled);
+    }
+    if start_time > now {
+        return Err(EligibilityError::QuestNotStarted);
+    }
+    // same rule as the "expired" field computed when listing quests
+    if matches!(expiry, Some(expiry) if expiry >= 0 && expiry < now) {
+        return Err(EligibilityError::QuestExpired);
+    }
+    Ok(())
+}
+
+async fn has_mandatory_domain(
+    state: &AppState,
+    addr: &FieldElement,
+    mandatory_domain: &str,
+) -> Result<bool, String> {
+    let domain = state
+        .provider
+        .call(
+            FunctionCall {
+                contract_address: state.conf.starknetid_contracts.naming_contract,
+                entry_point_selector: selector!("address_to_domain"),
+                calldata: vec![*addr, FieldElement::ZERO],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+        .await
+        .map_err(|e| format!("Error querying domain: {}", e))?;
+    // result is [len, ...labels] with the root domain label last
+    let labels = domain.get(1..).unwrap_or_default();
+
+    if mandatory_domain == ROOT_DOMAIN {
+        if labels.len() != 1 {
+            return Ok(false);
+        }
+        let expiry_result = state
+            .provider
+            .call(
+                FunctionCall {
+                    contract_address: state.conf.starknetid_contracts.naming_contract,
+                    entry_point_selector: selector!("domain_to_expiry"),
+                    calldata: vec![FieldElement::ONE, labels[0]],
+                },
+                BlockId::Tag(BlockTag::Latest),
+            )
+            .await
+            .map_err(|e| format!("Error querying domain expiry: {}", e))?;
+        let expiry: u64 = expiry_result
+            .first()
+            .and_then(|expiry| (*expiry).try_into().ok())
+            .ok_or_else(|| "Error reading domain expiry".to_string())?;
+        return Ok(expiry >= Utc::now().timestamp() as u64);
+    }
+
+    let encoded_root = starknet_id::encode(mandatory_domain)
+        .map_err(|_| format!("Invalid mandatory domain {}", mandatory_domain))?;
+    Ok(labels.len() > 1 && labels.last() == Some(&encoded_root))
+}
+
+// Central guard applied before completing a task or signing a quest reward
+pub async fn check_quest_eligibility(
+    state: &AppState,
+    addr: &FieldElement,
+    quest_id: u32,
+) -> Result<(), EligibilityError> {
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+    let quest = quests_collection
+        .find_one(doc! { "id": quest_id }, None)
+        .await
+        .map_err(|e| EligibilityError::Internal(format!("Error querying quest: {}", e)))?
+        .ok_or(EligibilityError::QuestNotFound)?;
+
+    check_quest_schedule(
+        quest.disabled,
+        quest.start_time,
+        quest.expiry,
+        Utc::now().timestamp_millis(),
+    )?;
+
+    let missing_prerequisites = get_missing_prerequisites(
+        &state.db,
+        addr,
+        quest.prerequisites.as_deref().unwrap_or_default(),
+    )
+    .await
+    .map_err(|e| EligibilityError::Internal(format!("Error checking prerequisites: {}", e)))?;
+    if !missing_prerequisites.is_empty() {
+        return Err(EligibilityError::MissingPrerequisites(
+            missing_prerequisites,
+        ));
+    }
+
+    if let Some(mandatory_domain) = quest
+        .mandatory_domain
+        .as_deref()
+        .filter(|domain| !domain.is_empty())
+    {
+        if !has_mandatory_domain(state, addr, mandatory_domain)
+            .await
+            .map_err(EligibilityError::Internal)?
+        {
+            return Err(EligibilityError::MissingDomain(
+                mandatory_domain.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn check_task_eligibility(
+    state: &AppState,
+    addr: &FieldElement,
+    task_id: u32,
+) -> Result<(), EligibilityError> {
+    let tasks_collection = state.db.collection::<Document>("tasks");
+    let task = tasks_collection
+        .find_one(doc! { "id": task_id }, None)
+        .await
+        .map_err(|e| EligibilityError::Internal(format!("Error querying task: {}", e)))?
+        .ok_or_else(|| EligibilityError::Internal(format!("Task {} not found", task_id)))?;
+    let quest_id = match task.get("quest_id") {
+        Some(Bson::Int32(id)) => *id as u32,
+        Some(Bson::Int64(id)) => *id as u32,
+        _ => return Err(EligibilityError::QuestNotFound),
+    };
+    check_quest_eligibility(state, addr, quest_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn active_quest_is_eligible() {
+        assert!(check_quest_schedule(false, NOW - 1000, Some(NOW + 1000), NOW).is_ok());
+        assert!(check_quest_schedule(false, NOW - 1000, None, NOW).is_ok());
+        // negative expiries mean the quest never expires
+        assert!(check_quest_schedule(false, NOW - 1000, Some(-1), NOW).is_ok());
+    }
+
+    #[test]
+    fn schedule_failures_have_codes() {
+        let codes = [
+            check_quest_schedule(true, NOW - 1000, None, NOW),
+            check_quest_schedule(false, NOW + 1000, None, NOW),
+            check_quest_schedule(false, NOW - 1000, Some(NOW - 1), NOW),
+        ]
+        .map(|result| result.unwrap_err().code());
+        assert_eq!(
+            codes,
+            ["quest_disabled", "quest_not_started", "quest_expired"]
+        );
+    }
+}
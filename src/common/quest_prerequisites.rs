@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::QuestPrerequisite;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_bson, Bson, Document},
+    options::FindOptions,
+    Database,
+};
+use starknet::core::types::FieldElement;
+
+fn as_u32(value: &Bson) -> Option<u32> {
+    match value {
+        Bson::Int32(v) => u32::try_from(*v).ok(),
+        Bson::Int64(v) => u32::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+// returns the ids among quest_ids for which the user has completed every task
+async fn get_completed_quests(
+    db: &Database,
+    addr: &FieldElement,
+    quest_ids: &[u32],
+) -> Result<HashSet<u32>, mongodb::error::Error> {
+    let pipeline = vec![
+        doc! { "$match": { "address": addr.to_string() } },
+        doc! {
+            "$lookup": {
+                "from": "tasks",
+                "localField": "task_id",
+                "foreignField": "id",
+                "as": "task"
+            }
+        },
+        doc! { "$unwind": "$task" },
+        doc! { "$match": { "task.quest_id": { "$in": quest_ids.to_vec() } } },
+        doc! {
+            "$group": {
+                "_id": "$task.quest_id",
+                "done": { "$addToSet": "$task_id" }
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "tasks",
+                "localField": "_id",
+                "foreignField": "quest_id",
+                "as": "tasks"
+            }
+        },
+        doc! {
+            "$match": {
+                "$expr": { "$eq": [{ "$size": "$done" }, { "$size": "$tasks" }] }
+            }
+        },
+        doc! { "$project": { "_id": 1 } },
+    ];
+
+    let completed_tasks_collection = db.collection::<Document>("completed_tasks");
+    let mut cursor = completed_tasks_collection.aggregate(pipeline, None).await?;
+    let mut completed = HashSet::new();
+    while let Some(doc) = cursor.try_next().await? {
+        if let Some(quest_id) = doc.get("_id").and_then(as_u32) {
+            completed.insert(quest_id);
+        }
+    }
+    Ok(completed)
+}
+
+async fn get_completed_achievements(
+    db: &Database,
+    addr: &FieldElement,
+    achievement_ids: &[u32],
+) -> Result<HashSet<u32>, mongodb::error::Error> {
+    let achieved_collection = db.collection::<Document>("achieved");
+    let filter = doc! {
+        "addr": addr.to_string(),
+        "achievement_id": { "$in": achievement_ids.to_vec() },
+    };
+    let mut cursor = achieved_collection.find(filter, None).await?;
+    let mut completed = HashSet::new();
+    while let Some(doc) = cursor.try_next().await? {
+        if let Some(achievement_id) = doc.get("achievement_id").and_then(as_u32) {
+            completed.insert(achievement_id);
+        }
+    }
+    Ok(completed)
+}
+
+pub async fn get_missing_prerequisites(
+    db: &Database,
+    addr: &FieldElement,
+    prerequisites: &[QuestPrerequisite],
+) -> Result<Vec<QuestPrerequisite>, mongodb::error::Error> {
+    if prerequisites.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut quest_ids = Vec::new();
+    let mut achievement_ids = Vec::new();
+    for prerequisite in prerequisites {
+        match prerequisite {
+            QuestPrerequisite::Quest { id } => quest_ids.push(*id),
+            QuestPrerequisite::Achievement { id } => achievement_ids.push(*id),
+        }
+    }
+
+    let completed_quests = match quest_ids.is_empty() {
+        true => HashSet::new(),
+        false => get_completed_quests(db, addr, &quest_ids).await?,
+    };
+    let completed_achievements = match achievement_ids.is_empty() {
+        true => HashSet::new(),
+        false => get_completed_achievements(db, addr, &achievement_ids).await?,
+    };
+
+    Ok(prerequisites
+        .iter()
+        .filter(|prerequisite| match prerequisite {
+            QuestPrerequisite::Quest { id } => !completed_quests.contains(id),
+            QuestPrerequisite::Achievement { id } => !completed_achievements.contains(id),
+        })
+        .cloned()
+        .collect())
+}
+
+fn parse_prerequisites(quest: &Document) -> Vec<QuestPrerequisite> {
+    quest
+        .get("prerequisites")
+        .and_then(|prerequisites| from_bson(prerequisites.clone()).ok())
+        .unwrap_or_default()
+}
+
+pub async fn get_quest_missing_prerequisites(
+    db: &Database,
+    addr: &FieldElement,
+    quest_id: u32,
+) -> Result<Vec<QuestPrerequisite>, mongodb::error::Error> {
+    let quests_collection = db.collection::<Document>("quests");
+    match quests_collection
+        .find_one(doc! { "id": quest_id }, None)
+        .await?
+    {
+        Some(quest) => get_missing_prerequisites(db, addr, &parse_prerequisites(&quest)).await,
+        None => Ok(Vec::new()),
+    }
+}
+
+// Returns the path leading back to quest_id if one of its (transitive) quest prerequisites
+// requires quest_id itself
+pub fn find_prerequisites_cycle(
+    quest_id: u32,
+    dependencies: &HashMap<u32, Vec<u32>>,
+) -> Option<Vec<u32>> {
+    let mut visited = HashSet::new();
+    let mut stack = vec![vec![quest_id]];
+    while let Some(path) = stack.pop() {
+        let current = *path.last().unwrap();
+        for next in dependencies.get(&current).into_iter().flatten() {
+            if *next == quest_id {
+                let mut cycle = path.clone();
+                cycle.push(*next);
+                return Some(cycle);
+            }
+            if visited.insert(*next) {
+                let mut next_path = path.clone();
+                next_path.push(*next);
+                stack.push(next_path);
+            }
+        }
+    }
+    None
+}
+
+// Checks that prerequisites reference existing quests and achievements and that setting them on
+// quest_id doesn't create a cycle
+pub async fn validate_prerequisites(
+    db: &Database,
+    quest_id: u32,
+    prerequisites: &[QuestPrerequisite],
+) -> Result<(), String> {
+    let mut dependencies: HashMap<u32, Vec<u32>> = HashMap::new();
+    let quests_collection = db.collection::<Document>("quests");
+    let options = FindOptions::builder()
+        .projection(doc! { "id": 1, "prerequisites": 1 })
+        .build();
+    let mut cursor = quests_collection
+        .find(doc! {}, options)
+        .await
+        .map_err(|e| format!("Error querying quests: {}", e))?;
+    while let Some(quest) = cursor
+        .try_next()
+        .await
+        .map_err(|e| format!("Error reading quests: {}", e))?
+    {
+        let Some(id) = quest.get("id").and_then(as_u32) else {
+            continue;
+        };
+        let quest_dependencies = parse_prerequisites(&quest)
+            .into_iter()
+            .filter_map(|prerequisite| match prerequisite {
+                QuestPrerequisite::Quest { id } => Some(id),
+                QuestPrerequisite::Achievement { .. } => None,
+            })
+            .collect();
+        dependencies.insert(id, quest_dependencies);
+    }
+
+    let mut achievement_ids = Vec::new();
+    let mut quest_dependencies = Vec::new();
+    for prerequisite in prerequisites {
+        match prerequisite {
+            QuestPrerequisite::Quest { id } => {
+                if !dependencies.contains_key(id) {
+                    return Err(format!("Prerequisite quest {} does not exist", id));
+                }
+                quest_dependencies.push(*id);
+            }
+            QuestPrerequisite::Achievement { id } => achievement_ids.push(*id),
+        }
+    }
+
+    if !achievement_ids.is_empty() {
+        let achievements_collection = db.collection::<Document>("achievements");
+        let found = achievements_collection
+            .count_documents(doc! { "id": { "$in": achievement_ids.clone() } }, None)
+            .await
+            .map_err(|e| format!("Error querying achievements: {}", e))?;
+        if found < achievement_ids.len() as u64 {
+            return Err("Prerequisite achievement does not exist".to_string());
+        }
+    }
+
+    dependencies.insert(quest_id, quest_dependencies);
+    match find_prerequisites_cycle(quest_id, &dependencies) {
+        Some(cycle) => Err(format!(
+            "Prerequisites would create a cycle: {}",
+            cycle
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(" -> ")
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_cycle_in_chain() {
+        let dependencies = HashMap::from([(3, vec![2]), (2, vec![1]), (1, vec![])]);
+        assert_eq!(find_prerequisites_cycle(3, &dependencies), None);
+    }
+
+    #[test]
+    fn self_prerequisite_is_a_cycle() {
+        let dependencies = HashMap::from([(1, vec![1])]);
+        assert_eq!(find_prerequisites_cycle(1, &dependencies), Some(vec![1, 1]));
+    }
+
+    #[test]
+    fn detects_indirect_cycle() {
+        let dependencies = HashMap::from([(1, vec![3]), (2, vec![1]), (3, vec![2, 4])]);
+        assert_eq!(
+            find_prerequisites_cycle(1, &dependencies),
+            Some(vec![1, 3, 2, 1])
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use crate::common::leaderboard::{get_ranking_stages, RankingMetric};
+use crate::common::xp_ledger::XP_LEDGER_COLLECTION;
+use crate::logger::Logger;
+use crate::models::SeasonDocument;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+
+pub const SEASONS_COLLECTION: &str = "seasons";
+// experience earned by each address during a season
+pub const SEASON_EXP_COLLECTION: &str = "season_exp";
+// final standings, written once when the season ends
+pub const SEASON_STANDINGS_COLLECTION: &str = "season_standings";
+
+// ledger entries counting for seasons, quest ones are filtered by the season categories
+const SEASON_QUEST_SOURCES: [&str; 2] = ["quest", "quest_revocation"];
+const SEASON_OTHER_SOURCES: [&str; 2] = ["achievement", "achievement_revocation"];
+
+// Adds experience earned at timestamp to the ledger of every running season accepting category.
+// Experience without a category (achievements) only counts for seasons open to every category.
+pub async fn add_season_experience(
+    db: &Database,
+    address: &str,
+    category: Option<&str>,
+    experience: i64,
+    timestamp: f64,
+) -> Result<(), mongodb::error::Error> {
+    let seasons_collection = db.collection::<SeasonDocument>(SEASONS_COLLECTION);
+    let mut filter = doc! {
+        "start_time": { "$lte": timestamp },
+        "end_time": { "$gt": timestamp },
+        "frozen_at": null,
+    };
+    match category {
+        Some(category) => filter.insert(
+            "$or",
+            vec![
+                doc! { "categories": { "$size": 0 } },
+                doc! { "categories": category },
+            ],
+        ),
+        None => filter.insert("categories", doc! { "$size": 0 }),
+    };
+    let mut cursor = seasons_collection.find(filter, None).await?;
+    let season_exp_collection = db.collection::<Document>(SEASON_EXP_COLLECTION);
+    while let Some(season) = cursor.try_next().await? {
+        let filter = doc! { "season_id": season.id, "address": address };
+        let update = doc! {
+            "$inc": { "experience": experience },
+            "$max": { "timestamp": timestamp },
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        season_exp_collection
+            .update_one(filter, update, options)
+            .await?;
+    }
+    Ok(())
+}
+
+// Same as add_season_experience for callers that already recorded the grant in the ledger: a
+// failure is logged instead of failing the grant, the next recompute of the season repairs it.
+pub async fn credit_season_experience(
+    db: &Database,
+    logger: &Logger,
+    address: &str,
+    category: Option<&str>,
+    experience: i64,
+    timestamp: f64,
+) {
+    if let Err(e) = add_season_experience(db, address, category, experience, timestamp).await {
+        logger.warning(format!(
+            "Error adding {} season experience to {}: {}",
+            experience, address, e
+        ));
+    }
+}
+
+// Rebuilds the ledger of a season from the experience ledger, used when a season is created with
+// a start in the past or when its dates or categories change
+pub async fn recompute_season_experience(
+    db: &Database,
+    season: &SeasonDocument,
+) -> Result<usize, mongodb::error::Error> {
+    let window = doc! {
+        "$gte": season.start_time as f64,
+        "$lt": season.end_time as f64,
+    };
+    let mut pipeline = if season.categories.is_empty() {
+        let sources: Vec<&str> = SEASON_QUEST_SOURCES
+            .iter()
+            .chain(SEASON_OTHER_SOURCES.iter())
+            .copied()
+            .collect();
+        vec![doc! { "$match": { "timestamp": window, "source_kind": { "$in": sources } } }]
+    } else {
+        vec![
+            doc! {
+                "$match": {
+                    "timestamp": window,
+                    "source_kind": { "$in": SEASON_QUEST_SOURCES.to_vec() }
+                }
+            },
+            doc! {
+                "$lookup": {
+                    "from": "quests",
+                    "let": { "quest_id": { "$toInt": "$source_id" } },
+                    "pipeline": [
+                        { "$match": { "$expr": { "$eq": ["$id", "$$quest_id"] } } },
+                        { "$project": { "_id": 0, "category": 1 } }
+                    ],
+                    "as": "quest"
+                }
+            },
+            doc! { "$match": { "quest.category": { "$in": &season.categories } } },
+        ]
+    };
+    pipeline.extend(vec![
+        doc! {
+            "$group": {
+                "_id": "$address",
+                "experience": { "$sum": "$experience" },
+                "timestamp": { "$max": "$timestamp" }
+            }
+        },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "season_id": { "$literal": season.id },
+                "address": "$_id",
+                "experience": 1,
+                "timestamp": 1
+            }
+        },
+    ]);
+    let entries: Vec<Document> = db
+        .collection::<Document>(XP_LEDGER_COLLECTION)
+        .aggregate(pipeline, None)
+        .await?
+        .try_collect()
+        .await?;
+
+    let season_exp_collection = db.collection::<Document>(SEASON_EXP_COLLECTION);
+    season_exp_collection
+        .delete_many(doc! { "season_id": season.id }, None)
+        .await?;
+    if !entries.is_empty() {
+        season_exp_collection.insert_many(&entries, None).await?;
+    }
+    Ok(entries.len())
+}
+
+// ranks the live ledger of a season, one document per address with its "rank"
+pub fn get_season_ranking_pipeline(season_id: u32) -> Vec<Document> {
+    let mut pipeline = vec![
+        doc! { "$match": { "season_id": season_id } },
+        doc! {
+            "$project": {
+                "_id": "$address",
+                "experience": 1,
+                "timestamp": 1
+            }
+        },
+    ];
+    pipeline.extend(get_ranking_stages(RankingMetric::Xp));
+    pipeline
+}
+
+// Writes the final standings of a season. Existing standings are never replaced so that a
+// snapshot stays immutable even if the freeze is retried.
+pub async fn freeze_season(db: &Database, season_id: u32) -> Result<i64, mongodb::error::Error> {
+    let standings_collection = db.collection::<Document>(SEASON_STANDINGS_COLLECTION);
+    let unique_standing = IndexModel::builder()
+        .keys(doc! { "season_id": 1, "address": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    standings_collection
+        .create_index(unique_standing, None)
+        .await?;
+    let rank_index = IndexModel::builder()
+        .keys(doc! { "season_id": 1, "rank": 1 })
+        .build();
+    standings_collection.create_index(rank_index, None).await?;
+
+    let mut pipeline = get_season_ranking_pipeline(season_id);
+    pipeline.extend(vec![
+        doc! {
+            "$project": {
+                "_id": 0,
+                "season_id": { "$literal": season_id },
+                "address": "$_id",
+                "experience": 1,
+                "rank": 1
+            }
+        },
+        doc! {
+            "$merge": {
+                "into": SEASON_STANDINGS_COLLECTION,
+                "on": ["season_id", "address"],
+                "whenMatched": "keepExisting",
+                "whenNotMatched": "insert"
+            }
+        },
+    ]);
+    db.collection::<Document>(SEASON_EXP_COLLECTION)
+        .aggregate(pipeline, None)
+        .await?;
+
+    let total_users = standings_collection
+        .count_documents(doc! { "season_id": season_id }, None)
+        .await? as i64;
+    db.collection::<Document>(SEASONS_COLLECTION)
+        .update_one(
+            doc! { "id": season_id, "frozen_at": null },
+            doc! { "$set": {
+                "frozen_at": Utc::now().timestamp_millis(),
+                "total_users": total_users,
+            } },
+            None,
+        )
+        .await?;
+    Ok(total_users)
+}
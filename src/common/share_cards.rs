@@ -0,0 +1,447 @@
+use crate::common::images::{decode_image, sniff_format};
+use crate::config::{Images, ShareCardText, ShareCards};
+use crate::models::AppState;
+use crate::utils::{get_app_url, get_error, to_hex};
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{TimeZone, Utc};
+use image::{imageops, imageops::FilterType, RgbaImage};
+use mongodb::{
+    bson::{doc, spec::BinarySubtype, Binary, Bson, Document},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+use sha2::{Digest, Sha256};
+use starknet::{
+    core::types::{BlockId, BlockTag, FieldElement, FunctionCall},
+    macros::selector,
+    providers::Provider,
+};
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+// rendered cards, keyed by the hash of the stable inputs of the card. The name and rank of the
+// user are refreshed when the cached card expires.
+pub const SHARE_CARDS_COLLECTION: &str = "share_cards";
+const BANNER_TIMEOUT: Duration = Duration::from_secs(5);
+
+// what a card is drawn from: a banner and the values of the template placeholders
+pub struct ShareCard {
+    pub banner: Option<String>,
+    pub addr: FieldElement,
+    pub values: BTreeMap<&'static str, String>,
+}
+
+impl ShareCard {
+    pub fn new(banner: &str, title: &str, addr: FieldElement, completed_at: i64) -> Self {
+        let values = BTreeMap::from([
+            ("title", title.to_string()),
+            ("date", format_date(completed_at)),
+        ]);
+        ShareCard {
+            banner: (!banner.is_empty()).then(|| banner.to_string()),
+            addr,
+            values,
+        }
+    }
+
+    // values which change over time, only read when the card is rendered
+    pub fn with_user(mut self, name: String, xp: i64, rank: Option<u64>) -> Self {
+        self.values.insert("name", name);
+        self.values.insert("xp", xp.to_string());
+        self.values.insert(
+            "rank",
+            rank.map_or("-".to_string(), |rank| rank.to_string()),
+        );
+        self
+    }
+}
+
+pub async fn ensure_share_cards_indexes(
+    db: &Database,
+    cache_ttl: u64,
+) -> Result<(), mongodb::error::Error> {
+    let expiry = IndexModel::builder()
+        .keys(doc! { "created_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(cache_ttl))
+                .build(),
+        )
+        .build();
+    db.collection::<Document>(SHARE_CARDS_COLLECTION)
+        .create_index(expiry, None)
+        .await?;
+    Ok(())
+}
+
+fn format_date(timestamp: i64) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(date) => date.format("%B %-d, %Y").to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn fill_template(text: &str, values: &BTreeMap<&'static str, String>) -> String {
+    values.iter().fold(text.to_string(), |text, (key, value)| {
+        text.replace(&format!("{{{}}}", key), value)
+    })
+}
+
+// #rrggbb or #rrggbbaa
+pub fn parse_color(color: &str) -> Option<[u8; 4]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 && hex.len() != 8 {
+        return None;
+    }
+    let mut rgba = [255; 4];
+    for (i, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(rgba)
+}
+
+// 0x0123…cdef, for addresses without a domain
+pub fn get_short_address(addr: &str) -> String {
+    if addr.len() <= 12 {
+        return addr.to_string();
+    }
+    format!("{}…{}", &addr[..6], &addr[addr.len() - 4..])
+}
+
+// the template is part of the hash, editing it renders the cards again
+pub fn get_card_hash(conf: &ShareCards, card: &ShareCard) -> String {
+    let content = format!("{:?}{:?}{}{:?}", conf, card.banner, card.addr, card.values);
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+// main domain of addr, e.g. "fricoben.stark"
+pub async fn get_stark_name(state: &AppState, addr: &FieldElement) -> Option<String> {
+    let result = state
+        .provider
+        .call(
+            FunctionCall {
+                contract_address: state.conf.starknetid_contracts.naming_contract,
+                entry_point_selector: selector!("address_to_domain"),
+                calldata: vec![*addr, FieldElement::ZERO],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+        .await
+        .ok()?;
+    let len: u64 = (*result.first()?).try_into().ok()?;
+    let len = len as usize;
+    if len == 0 || result.len() <= len {
+        return None;
+    }
+    let labels: Vec<String> = result[1..=len]
+        .iter()
+        .map(|label| starknet_id::decode(*label))
+        .collect();
+    Some(format!("{}.stark", labels.join(".")))
+}
+
+pub async fn get_display_name(state: &AppState, addr: &FieldElement) -> String {
+    match get_stark_name(state, addr).await {
+        Some(name) => name,
+        None => get_short_address(&to_hex(*addr)),
+    }
+}
+
+fn get_number(document: &Document, key: &str) -> Option<f64> {
+    match document.get(key)? {
+        Bson::Int32(value) => Some(*value as f64),
+        Bson::Int64(value) => Some(*value as f64),
+        Bson::Double(value) => Some(*value),
+        _ => None,
+    }
+}
+
+// total experience of address and its rank in the all time leaderboard
+pub async fn get_experience_and_rank(db: &Database, address: &str) -> (i64, Option<u64>) {
+    let collection = db.collection::<Document>("leaderboard_table");
+    let Ok(Some(entry)) = collection.find_one(doc! { "_id": address }, None).await else {
+        return (0, None);
+    };
+    let (Some(experience), Some(timestamp)) = (
+        get_number(&entry, "experience"),
+        get_number(&entry, "timestamp"),
+    ) else {
+        return (0, None);
+    };
+    // same order as the leaderboard: experience, then whoever reached it first
+    let ahead = collection
+        .count_documents(
+            doc! {
+                "$or": [
+                    { "experience": { "$gt": experience } },
+                    { "experience": experience, "timestamp": { "$lt": timestamp } },
+                ]
+            },
+            None,
+        )
+        .await
+        .ok();
+    (experience as i64, ahead.map(|ahead| ahead + 1))
+}
+
+// longest prefix of text fitting in max_width, cut with an ellipsis
+fn fit_text<F: Font>(font: &F, scale: PxScale, text: &str, max_width: Option<u32>) -> String {
+    let Some(max_width) = max_width else {
+        return text.to_string();
+    };
+    let font = font.as_scaled(scale);
+    let width = |text: &str| -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    };
+    if width(text) <= max_width as f32 {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let cut = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if width(&cut) <= max_width as f32 {
+            return cut;
+        }
+    }
+    String::new()
+}
+
+fn draw_text<F: Font>(canvas: &mut RgbaImage, font: &F, conf: &ShareCardText, text: &str) {
+    let scale = PxScale::from(conf.size);
+    let text = fit_text(font, scale, text, conf.max_width);
+    let color = parse_color(&conf.color).unwrap_or([255; 4]);
+    let scaled = font.as_scaled(scale);
+    let mut x = conf.x as f32;
+    let mut previous = None;
+    for c in text.chars() {
+        let mut glyph = scaled.scaled_glyph(c);
+        if let Some(previous) = previous {
+            x += scaled.kern(previous, glyph.id);
+        }
+        glyph.position = point(x, conf.y as f32);
+        x += scaled.h_advance(glyph.id);
+        previous = Some(glyph.id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= canvas.width() as i64 || py >= canvas.height() as i64 {
+                return;
+            }
+            let alpha = coverage * color[3] as f32 / 255.0;
+            let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+            for (channel, value) in pixel.0.iter_mut().zip(color).take(3) {
+                *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha) as u8;
+            }
+        });
+    }
+}
+
+// reads the font of the cards, the server doesn't start without a valid one
+pub fn load_font(path: &str) -> Result<Arc<Vec<u8>>, String> {
+    let font_data = std::fs::read(path)
+        .map_err(|e| format!("Unable to read share cards font {}: {}", path, e))?;
+    FontRef::try_from_slice(&font_data)
+        .map_err(|e| format!("Invalid share cards font {}: {}", path, e))?;
+    Ok(Arc::new(font_data))
+}
+
+// Draws the card as a png. Decoding the banner and rasterizing text is slow, it must run on a
+// blocking thread.
+pub fn render_share_card(
+    conf: &ShareCards,
+    images: &Images,
+    font_data: &[u8],
+    banner: Option<&[u8]>,
+    values: &BTreeMap<&'static str, String>,
+) -> Result<Vec<u8>, String> {
+    let font = FontRef::try_from_slice(font_data).map_err(|e| format!("Invalid font: {}", e))?;
+    let background = parse_color(&conf.background).unwrap_or([0, 0, 0, 255]);
+    let mut canvas = RgbaImage::from_pixel(conf.width, conf.height, image::Rgba(background));
+    // a banner that can't be decoded within the image limits is left out rather than failing
+    // the card
+    let banner = banner.and_then(|data| decode_image(data, sniff_format(data)?, images).ok());
+    if let Some(banner) = banner {
+        let banner = banner
+            .resize_to_fill(conf.width, conf.banner_height, FilterType::Triangle)
+            .to_rgba8();
+        imageops::overlay(&mut canvas, &banner, 0, 0);
+    }
+    for text in &conf.texts {
+        draw_text(&mut canvas, &font, text, &fill_template(&text.text, values));
+    }
+    let mut data = Cursor::new(Vec::new());
+    canvas
+        .write_to(&mut data, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode card: {}", e))?;
+    Ok(data.into_inner())
+}
+
+// banners are capped like uploads, a larger one is left out of the card
+async fn fetch_banner(state: &AppState, url: &str) -> Option<Vec<u8>> {
+    let url = get_app_url(&state.conf.variables.app_link, url);
+    let request = state.http.get(&url).timeout(BANNER_TIMEOUT);
+    let response = state
+        .http
+        .send_capped(request, state.conf.images.max_size)
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    Some(response.bytes().to_vec())
+}
+
+// a cached card and when it was rendered
+async fn get_cached_card(state: &AppState, hash: &str) -> Option<(Vec<u8>, i64)> {
+    let cached = state
+        .db
+        .collection::<Document>(SHARE_CARDS_COLLECTION)
+        .find_one(doc! { "_id": hash }, None)
+        .await
+        .ok()??;
+    let data = cached.get_binary_generic("data").ok()?.clone();
+    let created_at = cached.get_datetime("created_at").ok()?.timestamp_millis();
+    Some((data, created_at))
+}
+
+async fn render_card(
+    state: &AppState,
+    hash: &str,
+    card: ShareCard,
+) -> Result<(Vec<u8>, i64), String> {
+    let (xp, rank) = get_experience_and_rank(&state.db, &card.addr.to_string()).await;
+    let name = get_display_name(state, &card.addr).await;
+    let card = card.with_user(name, xp, rank);
+    let conf = state.conf.share_cards.clone();
+    let images = state.conf.images.clone();
+    let font_data = state.share_card_font.clone();
+    let banner = match &card.banner {
+        Some(url) => fetch_banner(state, url).await,
+        None => None,
+    };
+    let values = card.values.clone();
+    let data = tokio::task::spawn_blocking(move || {
+        render_share_card(&conf, &images, &font_data, banner.as_deref(), &values)
+    })
+    .await
+    .map_err(|e| format!("Failed to render card: {}", e))??;
+
+    // a concurrent request may have cached the same card first, this one is served uncached
+    let created_at = mongodb::bson::DateTime::now();
+    let _ = state
+        .db
+        .collection::<Document>(SHARE_CARDS_COLLECTION)
+        .insert_one(
+            doc! {
+                "_id": hash,
+                "data": Binary { subtype: BinarySubtype::Generic, bytes: data.clone() },
+                "created_at": created_at,
+            },
+            None,
+        )
+        .await;
+    Ok((data, created_at.timestamp_millis()))
+}
+
+// The card as a png, revalidated through its hash and render time. The name and rank are only
+// looked up when the card isn't cached.
+pub async fn get_share_card_response(
+    state: &AppState,
+    card: ShareCard,
+    if_none_match: Option<&str>,
+) -> Response {
+    let hash = get_card_hash(&state.conf.share_cards, &card);
+    let card = match get_cached_card(state, &hash).await {
+        Some(cached) => Ok(cached),
+        None => render_card(state, &hash, card).await,
+    };
+    match card {
+        Ok((data, created_at)) => {
+            let etag = format!("\"{}-{}\"", hash, created_at);
+            if if_none_match == Some(etag.as_str()) {
+                return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+            }
+            (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "image/png".to_string()),
+                    (header::CACHE_CONTROL, "public, max-age=3600".to_string()),
+                    (header::ETAG, etag),
+                ],
+                data,
+            )
+                .into_response()
+        }
+        Err(e) => get_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_templates() {
+        let card = ShareCard::new(
+            "/visuals/quests/card.webp",
+            "Starknet ID",
+            FieldElement::ONE,
+            1_700_000_000,
+        )
+        .with_user("ben.stark".to_string(), 1250, Some(42));
+        assert_eq!(
+            fill_template("{name} completed {title} on {date}", &card.values),
+            "ben.stark completed Starknet ID on November 14, 2023"
+        );
+        assert_eq!(
+            fill_template("{xp} XP, rank #{rank} {unknown}", &card.values),
+            "1250 XP, rank #42 {unknown}"
+        );
+        let unranked = ShareCard::new("", "Quest", FieldElement::ONE, 0).with_user(
+            "ben.stark".to_string(),
+            0,
+            None,
+        );
+        assert_eq!(unranked.banner, None);
+        assert_eq!(fill_template("#{rank}", &unranked.values), "#-");
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#6affaf"), Some([0x6a, 0xff, 0xaf, 255]));
+        assert_eq!(parse_color("#ffffff80"), Some([255, 255, 255, 0x80]));
+        assert_eq!(parse_color("ffffff"), None);
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("#gggggg"), None);
+    }
+
+    #[test]
+    fn shortens_addresses() {
+        assert_eq!(
+            get_short_address("0x061b6c0a78f9edf13cea17b50719f3344533fadd470b8cb29c2b4318014f52d3"),
+            "0x061b…52d3"
+        );
+        assert_eq!(get_short_address("0x1"), "0x1");
+    }
+}
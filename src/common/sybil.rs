@@ -0,0 +1,277 @@
+use crate::common::has_deployed_time::execute_has_deployed_time;
+use crate::middleware::address_ip::ADDRESS_IPS_COLLECTION;
+use crate::models::AppState;
+use crate::utils::to_hex;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOptions, UpdateOptions},
+    Database,
+};
+use serde::Serialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+pub const SYBIL_SCORES_COLLECTION: &str = "sybil_scores";
+// first address that sent funds to each wallet, fetched from Starkscan
+pub const FUNDING_SOURCES_COLLECTION: &str = "funding_sources";
+
+// every signal weighs the same in the score
+const SIGNAL_WEIGHT: f64 = 0.25;
+const DAY_SECONDS: f64 = 86_400.0;
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct SybilSignals {
+    // None when the wallet is not deployed or its age is unknown
+    pub wallet_age_days: Option<f64>,
+    // median time between two consecutive task completions, None with less than 2 completions
+    pub completion_interval_ms: Option<i64>,
+    // other addresses seen with one of the ips of this address
+    pub shared_ip_addresses: u64,
+    // other addresses funded by the same address
+    pub funding_cluster_size: u64,
+}
+
+fn wallet_age_risk(wallet_age_days: Option<f64>) -> f64 {
+    match wallet_age_days {
+        None => 0.5,
+        Some(days) if days < 1.0 => 1.0,
+        Some(days) if days < 7.0 => 0.7,
+        Some(days) if days < 30.0 => 0.3,
+        Some(_) => 0.0,
+    }
+}
+
+fn completion_speed_risk(completion_interval_ms: Option<i64>) -> f64 {
+    match completion_interval_ms {
+        Some(interval) if interval < 5_000 => 1.0,
+        Some(interval) if interval < 30_000 => 0.6,
+        Some(interval) if interval < 120_000 => 0.3,
+        _ => 0.0,
+    }
+}
+
+fn cluster_risk(cluster_size: u64, thresholds: [u64; 3]) -> f64 {
+    match cluster_size {
+        size if size >= thresholds[2] => 1.0,
+        size if size >= thresholds[1] => 0.7,
+        size if size >= thresholds[0] => 0.3,
+        _ => 0.0,
+    }
+}
+
+// Combines the signals into a score between 0 (organic) and 1 (very likely part of a farm)
+pub fn compute_risk_score(signals: &SybilSignals) -> f64 {
+    let risks = [
+        wallet_age_risk(signals.wallet_age_days),
+        completion_speed_risk(signals.completion_interval_ms),
+        cluster_risk(signals.shared_ip_addresses, [1, 3, 10]),
+        cluster_risk(signals.funding_cluster_size, [2, 5, 20]),
+    ];
+    let score: f64 = risks.iter().map(|risk| risk * SIGNAL_WEIGHT).sum();
+    (score * 100.0).round() / 100.0
+}
+
+pub fn get_median_interval(mut timestamps: Vec<i64>) -> Option<i64> {
+    if timestamps.len() < 2 {
+        return None;
+    }
+    timestamps.sort_unstable();
+    let mut intervals: Vec<i64> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+    intervals.sort_unstable();
+    Some(intervals[intervals.len() / 2])
+}
+
+async fn get_completion_interval(
+    db: &Database,
+    address: &str,
+) -> Result<Option<i64>, mongodb::error::Error> {
+    let options = FindOptions::builder()
+        .projection(doc! { "timestamp": 1 })
+        .build();
+    let timestamps: Vec<i64> = db
+        .collection::<Document>("completed_tasks")
+        .find(doc! { "address": address }, options)
+        .await?
+        .try_collect::<Vec<Document>>()
+        .await?
+        .iter()
+        .filter_map(|task| task.get_i64("timestamp").ok())
+        .collect();
+    Ok(get_median_interval(timestamps))
+}
+
+async fn get_shared_ip_addresses(
+    db: &Database,
+    address: &str,
+) -> Result<u64, mongodb::error::Error> {
+    let collection = db.collection::<Document>(ADDRESS_IPS_COLLECTION);
+    let ips = collection
+        .distinct("ip", doc! { "address": address }, None)
+        .await?;
+    if ips.is_empty() {
+        return Ok(0);
+    }
+    let addresses = collection
+        .distinct(
+            "address",
+            doc! { "ip": { "$in": ips }, "address": { "$ne": address } },
+            None,
+        )
+        .await?;
+    Ok(addresses.len() as u64)
+}
+
+// returns the first address which sent funds to addr, cached in funding_sources
+async fn get_funding_source(
+    state: &AppState,
+    addr: &FieldElement,
+) -> Result<Option<String>, String> {
+    let collection = state.db.collection::<Document>(FUNDING_SOURCES_COLLECTION);
+    if let Ok(Some(document)) = collection
+        .find_one(doc! { "address": addr.to_string() }, None)
+        .await
+    {
+        return Ok(document.get_str("funder").ok().map(|f| f.to_string()));
+    }
+
+    let url = format!(
+        "https://api.starkscan.co/api/v0/transfers?transfer_to={}&limit=1&order_by=asc",
+        to_hex(*addr)
+    );
+    let request = state
+        .http
+        .get(&url)
+        .header("accept", "application/json")
+        .header("x-api-key", state.conf.starkscan.api_key.clone());
+    let json = state
+        .http
+        .send(request)
+        .await
+        .map_err(|e| format!("Failed to fetch transfers from API: {}", e))?
+        .json::<serde_json::Value>()
+        .map_err(|e| {
+            format!(
+                "Failed to get JSON response while fetching transfers: {}",
+                e
+            )
+        })?;
+    let Some(funder) = json["data"][0]["transfer_from"]
+        .as_str()
+        .and_then(|funder| FieldElement::from_hex_be(funder).ok())
+    else {
+        return Ok(None);
+    };
+
+    let options = UpdateOptions::builder().upsert(true).build();
+    collection
+        .update_one(
+            doc! { "address": addr.to_string() },
+            doc! { "$set": { "funder": funder.to_string() } },
+            options,
+        )
+        .await
+        .map_err(|e| format!("{}", e))?;
+    Ok(Some(funder.to_string()))
+}
+
+pub async fn compute_sybil_signals(
+    state: &Arc<AppState>,
+    addr: &FieldElement,
+) -> Result<SybilSignals, String> {
+    let address = addr.to_string();
+    let now = Utc::now().timestamp() as f64;
+    let wallet_age_days = execute_has_deployed_time(state.clone(), addr)
+        .await
+        .ok()
+        .map(|deployed_at| ((now - deployed_at as f64) / DAY_SECONDS).max(0.0));
+    let completion_interval_ms = get_completion_interval(&state.db, &address)
+        .await
+        .map_err(|e| format!("Error reading completions: {}", e))?;
+    let shared_ip_addresses = get_shared_ip_addresses(&state.db, &address)
+        .await
+        .map_err(|e| format!("Error reading ips: {}", e))?;
+    let funding_cluster_size = match get_funding_source(state, addr).await? {
+        Some(funder) => state
+            .db
+            .collection::<Document>(FUNDING_SOURCES_COLLECTION)
+            .count_documents(
+                doc! { "funder": funder, "address": { "$ne": &address } },
+                None,
+            )
+            .await
+            .map_err(|e| format!("Error reading funding sources: {}", e))?,
+        None => 0,
+    };
+
+    Ok(SybilSignals {
+        wallet_age_days,
+        completion_interval_ms,
+        shared_ip_addresses,
+        funding_cluster_size,
+    })
+}
+
+// computes the score of addr and stores it with its signals
+pub async fn update_sybil_score(state: &Arc<AppState>, addr: &FieldElement) -> Result<f64, String> {
+    let signals = compute_sybil_signals(state, addr).await?;
+    let score = compute_risk_score(&signals);
+    let update = doc! {
+        "$set": {
+            "score": score,
+            "wallet_age_days": signals.wallet_age_days,
+            "completion_interval_ms": signals.completion_interval_ms,
+            "shared_ip_addresses": signals.shared_ip_addresses as i64,
+            "funding_cluster_size": signals.funding_cluster_size as i64,
+            "updated_at": Utc::now().timestamp_millis(),
+        }
+    };
+    let options = UpdateOptions::builder().upsert(true).build();
+    state
+        .db
+        .collection::<Document>(SYBIL_SCORES_COLLECTION)
+        .update_one(doc! { "address": addr.to_string() }, update, options)
+        .await
+        .map_err(|e| format!("Error saving sybil score: {}", e))?;
+    Ok(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn organic_wallet_has_no_risk() {
+        let signals = SybilSignals {
+            wallet_age_days: Some(400.0),
+            completion_interval_ms: Some(3_600_000),
+            shared_ip_addresses: 0,
+            funding_cluster_size: 1,
+        };
+        assert_eq!(compute_risk_score(&signals), 0.0);
+    }
+
+    #[test]
+    fn farmed_wallet_has_max_risk() {
+        let signals = SybilSignals {
+            wallet_age_days: Some(0.2),
+            completion_interval_ms: Some(1_000),
+            shared_ip_addresses: 25,
+            funding_cluster_size: 40,
+        };
+        assert_eq!(compute_risk_score(&signals), 1.0);
+    }
+
+    #[test]
+    fn unknown_wallet_age_is_partial_risk() {
+        assert_eq!(compute_risk_score(&SybilSignals::default()), 0.13);
+    }
+
+    #[test]
+    fn median_interval() {
+        assert_eq!(get_median_interval(vec![]), None);
+        assert_eq!(get_median_interval(vec![10]), None);
+        assert_eq!(get_median_interval(vec![100, 0, 10, 20]), Some(10));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::config::{ClaimSignature, Signing};
+use chrono::Utc;
+use starknet::core::{
+    crypto::compute_hash_on_elements,
+    types::FieldElement,
+    utils::{cairo_short_string_to_felt, starknet_keccak},
+};
+use std::collections::BTreeMap;
+
+// SNIP-12 (revision 0) typed data, hashed like starknet.js and the account contracts do
+
+pub enum TypedValue {
+    Felt(FieldElement),
+    Struct(TypedStruct),
+}
+
+pub struct TypedStruct {
+    name: &'static str,
+    fields: Vec<(&'static str, TypedValue)>,
+}
+
+impl TypedStruct {
+    pub fn new(name: &'static str) -> Self {
+        TypedStruct {
+            name,
+            fields: vec![],
+        }
+    }
+
+    pub fn felt(mut self, name: &'static str, value: FieldElement) -> Self {
+        self.fields.push((name, TypedValue::Felt(value)));
+        self
+    }
+
+    pub fn with_struct(mut self, name: &'static str, value: TypedStruct) -> Self {
+        self.fields.push((name, TypedValue::Struct(value)));
+        self
+    }
+
+    fn collect_types(&self, types: &mut BTreeMap<&'static str, String>) {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| match value {
+                TypedValue::Felt(_) => format!("{}:felt", name),
+                TypedValue::Struct(s) => format!("{}:{}", name, s.name),
+            })
+            .collect();
+        types.insert(self.name, format!("{}({})", self.name, fields.join(",")));
+        for (_, value) in &self.fields {
+            if let TypedValue::Struct(s) = value {
+                s.collect_types(types);
+            }
+        }
+    }
+
+    // the type of the struct followed by the types it depends on, sorted by name
+    pub fn encode_type(&self) -> String {
+        let mut types = BTreeMap::new();
+        self.collect_types(&mut types);
+        let primary = types.remove(self.name).unwrap_or_default();
+        types.into_values().fold(primary, |encoded, t| encoded + &t)
+    }
+
+    pub fn type_hash(&self) -> FieldElement {
+        starknet_keccak(self.encode_type().as_bytes())
+    }
+
+    pub fn hash(&self) -> FieldElement {
+        let mut elements = vec![self.type_hash()];
+        elements.extend(self.fields.iter().map(|(_, value)| match value {
+            TypedValue::Felt(felt) => *felt,
+            TypedValue::Struct(s) => s.hash(),
+        }));
+        compute_hash_on_elements(&elements)
+    }
+}
+
+// encodes a felt given as a string the way starknet.js does: numbers as is, text as a short string
+pub fn encode_felt(value: &str) -> Result<FieldElement, String> {
+    if let Ok(felt) = FieldElement::from_dec_str(value) {
+        return Ok(felt);
+    }
+    if let Some(Ok(felt)) = value.strip_prefix("0x").map(FieldElement::from_hex_be) {
+        return Ok(felt);
+    }
+    cairo_short_string_to_felt(value).map_err(|e| format!("Invalid short string {}: {}", value, e))
+}
+
+// splits a felt into the low and high 128 bits of a u256
+pub fn u256(value: FieldElement) -> TypedStruct {
+    let bytes = value.to_bytes_be();
+    TypedStruct::new("u256")
+        .felt(
+            "low",
+            FieldElement::from_byte_slice_be(&bytes[16..]).unwrap(),
+        )
+        .felt(
+            "high",
+            FieldElement::from_byte_slice_be(&bytes[..16]).unwrap(),
+        )
+}
+
+// domain of the messages, bound to the contract verifying them when there is one
+pub fn get_domain(
+    name: &str,
+    version: &str,
+    chain_id: &str,
+    verifying_contract: Option<FieldElement>,
+) -> Result<TypedStruct, String> {
+    let domain = TypedStruct::new("StarkNetDomain")
+        .felt("name", encode_felt(name)?)
+        .felt("version", encode_felt(version)?)
+        .felt("chainId", encode_felt(chain_id)?);
+    Ok(match verifying_contract {
+        Some(contract) => domain.felt("verifyingContract", contract),
+        None => domain,
+    })
+}
+
+// hash of message signed by account under domain
+pub fn get_message_hash(
+    domain: &TypedStruct,
+    account: FieldElement,
+    message: &TypedStruct,
+) -> FieldElement {
+    compute_hash_on_elements(&[
+        FieldElement::from_byte_slice_be(b"StarkNet Message").unwrap(),
+        domain.hash(),
+        account,
+        message.hash(),
+    ])
+}
+
+pub fn get_nft_claim(
+    token_id: u64,
+    quest_id: u32,
+    task_id: u32,
+    deadline: i64,
+    nonce: FieldElement,
+) -> TypedStruct {
+    TypedStruct::new("NftClaim")
+        .with_struct("token_id", u256(FieldElement::from(token_id)))
+        .felt("quest_id", FieldElement::from(quest_id))
+        .felt("task_id", FieldElement::from(task_id))
+        .felt("deadline", FieldElement::from(deadline as u64))
+        .felt("nonce", nonce)
+}
+
+pub fn get_boost_claim(
+    boost_id: u32,
+    amount: u128,
+    token: FieldElement,
+    deadline: i64,
+    nonce: FieldElement,
+) -> TypedStruct {
+    TypedStruct::new("BoostClaim")
+        .felt("boost_id", FieldElement::from(boost_id))
+        .with_struct("amount", u256(FieldElement::from(amount)))
+        .felt("token", token)
+        .felt("deadline", FieldElement::from(deadline as u64))
+        .felt("nonce", nonce)
+}
+
+// deadline and nonce of a new SNIP-12 claim, the contract rejects it after the deadline or
+// once the nonce was used
+pub fn get_claim_expiry(signing: &Signing) -> (i64, FieldElement) {
+    let deadline = Utc::now().timestamp() + signing.validity;
+    (deadline, FieldElement::from(rand::random::<u128>()))
+}
+
+// the signing section is checked at startup for every SNIP-12 contract
+pub fn get_signing(signing: &Option<Signing>) -> Result<&Signing, String> {
+    signing
+        .as_ref()
+        .ok_or_else(|| "SNIP-12 claims need a [signing] section".to_string())
+}
+
+// hash of a claim message for the contract configured with settings
+pub fn get_claim_hash(
+    signing: &Signing,
+    settings: &ClaimSignature,
+    account: FieldElement,
+    message: &TypedStruct,
+) -> Result<FieldElement, String> {
+    let verifying_contract = settings
+        .verifying_contract
+        .ok_or_else(|| format!("{} claims need a verifying_contract", settings.name))?;
+    let domain = get_domain(
+        &settings.name,
+        &settings.version,
+        &signing.chain_id,
+        Some(verifying_contract),
+    )?;
+    Ok(get_message_hash(&domain, account, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn felt(value: &str) -> FieldElement {
+        FieldElement::from_hex_be(value).unwrap()
+    }
+
+    #[test]
+    fn hashes_domain_type() {
+        let domain = get_domain("StarkNet Mail", "1", "1", None).unwrap();
+        assert_eq!(
+            domain.encode_type(),
+            "StarkNetDomain(name:felt,version:felt,chainId:felt)"
+        );
+        assert_eq!(
+            domain.type_hash(),
+            felt("0x1bfc207425a47a5dfa1a50a4f5241203f50624ca5fdf5e18755765416b8e288")
+        );
+    }
+
+    #[test]
+    fn binds_domain_to_contract() {
+        let domain = get_domain("StarknetQuest", "1", "SN_MAIN", Some(FieldElement::ONE)).unwrap();
+        assert_eq!(
+            domain.encode_type(),
+            "StarkNetDomain(name:felt,version:felt,chainId:felt,verifyingContract:felt)"
+        );
+        let other = get_domain(
+            "StarknetQuest",
+            "1",
+            "SN_MAIN",
+            Some(FieldElement::from(2_u32)),
+        )
+        .unwrap();
+        assert_ne!(domain.hash(), other.hash());
+    }
+
+    #[test]
+    fn hashes_nested_message() {
+        let person = |name: &str, wallet: &str| {
+            TypedStruct::new("Person")
+                .felt("name", encode_felt(name).unwrap())
+                .felt("wallet", felt(wallet))
+        };
+        let mail = TypedStruct::new("Mail")
+            .with_struct(
+                "from",
+                person("Cow", "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"),
+            )
+            .with_struct(
+                "to",
+                person("Bob", "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"),
+            )
+            .felt("contents", encode_felt("Hello, Bob!").unwrap());
+        assert_eq!(
+            mail.encode_type(),
+            "Mail(from:Person,to:Person,contents:felt)Person(name:felt,wallet:felt)"
+        );
+        let domain = get_domain("StarkNet Mail", "1", "1", None).unwrap();
+        assert_eq!(
+            get_message_hash(
+                &domain,
+                felt("0xcd2a3d9f938e13cd947ec05abc7fe734df8dd826"),
+                &mail
+            ),
+            felt("0x6fcff244f63e38b9d88b9e3378d44757710d1b244282b435cb472053c8d78d0")
+        );
+    }
+
+    #[test]
+    fn splits_u256() {
+        let value = FieldElement::from_str("340282366920938463463374607431768211457").unwrap();
+        let split = u256(value);
+        assert_eq!(split.encode_type(), "u256(low:felt,high:felt)");
+        assert!(matches!(split.fields[0].1, TypedValue::Felt(low) if low == FieldElement::ONE));
+        assert!(matches!(split.fields[1].1, TypedValue::Felt(high) if high == FieldElement::ONE));
+    }
+
+    #[test]
+    fn encodes_claims() {
+        let claim = get_nft_claim(1, 2, 3, 4, FieldElement::from(5u32));
+        assert_eq!(
+            claim.encode_type(),
+            "NftClaim(token_id:u256,quest_id:felt,task_id:felt,deadline:felt,nonce:felt)u256(low:felt,high:felt)"
+        );
+        let claim = get_boost_claim(1, 2, FieldElement::ONE, 4, FieldElement::from(5u32));
+        assert_eq!(
+            claim.encode_type(),
+            "BoostClaim(boost_id:felt,amount:u256,token:felt,deadline:felt,nonce:felt)u256(low:felt,high:felt)"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::models::AppState;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, DateTime, Document},
+    options::{IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+use serde_json::json;
+use starknet::core::types::FieldElement;
+use std::time::Duration;
+
+// last failed on-chain verification of each (address, task), until its cooldown ends
+pub const VERIFICATION_FAILURES_COLLECTION: &str = "verification_failures";
+
+pub async fn ensure_verification_cache_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(VERIFICATION_FAILURES_COLLECTION);
+    let key = IndexModel::builder()
+        .keys(doc! { "address": 1, "task_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    collection.create_index(key, None).await?;
+    let expiry = IndexModel::builder()
+        .keys(doc! { "expires_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build(),
+        )
+        .build();
+    collection.create_index(expiry, None).await?;
+    Ok(())
+}
+
+// seconds left before a failure expiring at expires_at can be verified again, rounded up
+pub fn remaining_cooldown(expires_at: i64, now: i64) -> i64 {
+    ((expires_at - now).max(0) + 999) / 1000
+}
+
+pub fn failure_response(reason: &str, cooldown: i64) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": reason, "cooldown": cooldown })),
+    )
+        .into_response()
+}
+
+// Returns the cached failure of the task while its cooldown runs, the verification must not call
+// the provider again then
+pub async fn get_cached_failure(
+    state: &AppState,
+    addr: &FieldElement,
+    task_id: u32,
+) -> Option<Response> {
+    let now = Utc::now().timestamp_millis();
+    // expired documents may still be there until the TTL monitor removes them
+    let failure = state
+        .db
+        .collection::<Document>(VERIFICATION_FAILURES_COLLECTION)
+        .find_one(
+            doc! {
+                "address": addr.to_string(),
+                "task_id": task_id,
+                "expires_at": { "$gt": DateTime::from_millis(now) },
+            },
+            None,
+        )
+        .await
+        .ok()??;
+    let expires_at = failure.get_datetime("expires_at").ok()?.timestamp_millis();
+    let reason = failure.get_str("reason").unwrap_or_default();
+    Some(failure_response(
+        reason,
+        remaining_cooldown(expires_at, now),
+    ))
+}
+
+// Caches a negative verification result for the configured cooldown and returns it. Provider
+// errors must not be cached, they say nothing about the address.
+pub async fn cache_failure(
+    state: &AppState,
+    addr: &FieldElement,
+    task_id: u32,
+    reason: &str,
+) -> Response {
+    let cooldown_ms = state.conf.verification.failure_cooldown * 1000;
+    if cooldown_ms <= 0 {
+        return failure_response(reason, 0);
+    }
+    let expires_at = Utc::now().timestamp_millis() + cooldown_ms;
+    let options = UpdateOptions::builder().upsert(true).build();
+    if let Err(e) = state
+        .db
+        .collection::<Document>(VERIFICATION_FAILURES_COLLECTION)
+        .update_one(
+            doc! { "address": addr.to_string(), "task_id": task_id },
+            doc! { "$set": {
+                "reason": reason,
+                "expires_at": DateTime::from_millis(expires_at),
+            } },
+            options,
+        )
+        .await
+    {
+        state.logger.warning(format!(
+            "Unable to cache verification failure of task {}: {}",
+            task_id, e
+        ));
+    }
+    failure_response(reason, state.conf.verification.failure_cooldown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cooldown_is_rounded_up() {
+        assert_eq!(remaining_cooldown(10_000, 0), 10);
+        assert_eq!(remaining_cooldown(10_000, 8_500), 2);
+        assert_eq!(remaining_cooldown(10_000, 10_000), 0);
+        assert_eq!(remaining_cooldown(10_000, 12_000), 0);
+    }
+}
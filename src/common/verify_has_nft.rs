@@ -1,12 +1,11 @@
 use crate::{
-    config::Config,
-    models::{Nft, StarkscanQuery},
+    models::{AppState, Nft, StarkscanQuery},
     utils::to_hex,
 };
 use starknet::core::types::FieldElement;
 
 pub async fn execute_has_nft(
-    config: &Config,
+    state: &AppState,
     addr: FieldElement,
     contract: FieldElement,
     limit: u32,
@@ -17,41 +16,30 @@ pub async fn execute_has_nft(
         to_hex(contract),
         to_hex(addr)
     );
-    let client = reqwest::Client::new();
-    match client
+    let request = state
+        .http
         .get(&url)
         .header("accept", "application/json")
-        .header("x-api-key", config.starkscan.api_key.clone())
-        .send()
-        .await
-    {
-        Ok(response) => {
-            match response.text().await {
-                Ok(text) => {
-                    match serde_json::from_str::<StarkscanQuery>(&text) {
-                        Ok(res) => {
-                            // Remove duplicates & check is whitelisted
-                            let nft_data = res.data;
-                            let mut unique_nfts: Vec<String> = Vec::new();
-                            for nft in nft_data {
-                                if nft.name.is_some() {
-                                    is_whitelisted(&nft, &mut unique_nfts)
-                                }
-                            }
-                            Ok(unique_nfts.len() >= limit as usize)
-                        }
-                        Err(e) => Err(format!(
-                            "Failed to deserialize result from Starkscan API: {} for response: {}",
-                            e, text
-                        )),
+        .header("x-api-key", state.conf.starkscan.api_key.clone());
+    match state.http.send(request).await {
+        Ok(response) => match response.json::<StarkscanQuery>() {
+            Ok(res) => {
+                // Remove duplicates & check is whitelisted
+                let nft_data = res.data;
+                let mut unique_nfts: Vec<String> = Vec::new();
+                for nft in nft_data {
+                    if nft.name.is_some() {
+                        is_whitelisted(&nft, &mut unique_nfts)
                     }
                 }
-                Err(e) => Err(format!(
-                    "Failed to get JSON response while fetching user NFT data: {}",
-                    e
-                )),
+                Ok(unique_nfts.len() >= limit as usize)
             }
-        }
+            Err(e) => Err(format!(
+                "Failed to deserialize result from Starkscan API: {} for response: {}",
+                e,
+                response.text()
+            )),
+        },
         Err(e) => Err(format!("Failed to fetch user NFTs from API: {}", e)),
     }
 }
@@ -66,7 +66,7 @@ pub async fn execute_has_root_domain(
 
                 match state.upsert_completed_task(*addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             } else {
                 get_error("Invalid domain: subdomains are not eligible".to_string())
@@ -0,0 +1,70 @@
+use crate::{config::Webhooks, http_client::HttpClient, logger::Logger};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, to_bson, Document},
+    Database,
+};
+use serde_json::{json, Value};
+
+// Every delivery attempt is stored in this collection so failed ones can be retried later
+pub const WEBHOOK_DELIVERIES_COLLECTION: &str = "webhook_deliveries";
+
+// sent with the timeout and retries of the receiver host, a hung receiver can't stall the jobs
+// emitting webhooks
+pub async fn deliver_webhook(
+    http: &HttpClient,
+    conf: &Webhooks,
+    endpoint: &str,
+    body: &Value,
+) -> Result<(), String> {
+    let request = http
+        .post(endpoint)
+        .header("x-webhook-token", conf.token.clone())
+        .json(body);
+    match http.send(request).await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("Webhook endpoint returned {}", response.status())),
+        Err(e) => Err(format!("Failed to deliver webhook: {}", e)),
+    }
+}
+
+pub async fn emit_webhook(
+    db: &Database,
+    http: &HttpClient,
+    conf: &Webhooks,
+    logger: &Logger,
+    event: &str,
+    payload: Value,
+) {
+    let deliveries_collection = db.collection::<Document>(WEBHOOK_DELIVERIES_COLLECTION);
+    let timestamp = Utc::now().timestamp_millis();
+    let body = json!({
+        "event": event,
+        "timestamp": timestamp,
+        "data": payload,
+    });
+    let Ok(bson_body) = to_bson(&body) else {
+        logger.warning(format!("Unable to serialize webhook payload for {}", event));
+        return;
+    };
+
+    for endpoint in &conf.endpoints {
+        let result = deliver_webhook(http, conf, endpoint, &body).await;
+        if let Err(e) = &result {
+            logger.warning(format!("Webhook {} to {} failed: {}", event, endpoint, e));
+        }
+        let delivery = doc! {
+            "event": event,
+            "endpoint": endpoint,
+            "body": bson_body.clone(),
+            "status": if result.is_ok() { "delivered" } else { "failed" },
+            "attempts": 1,
+            "last_error": result.err(),
+            "created_at": timestamp,
+            "updated_at": timestamp,
+        };
+        if let Err(e) = deliveries_collection.insert_one(delivery, None).await {
+            logger.warning(format!("Unable to save webhook delivery: {}", e));
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::common::quest_prerequisites::get_missing_prerequisites;
+use crate::common::seasons::credit_season_experience;
+use crate::common::xp_ledger::{get_ledger_entry, grant_experience, record_experience, XpSource};
+use crate::logger::Logger;
+use crate::models::{AchievementDocument, QuestPrerequisite};
+use crate::utils::{get_error, to_hex};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::Database;
+use serde_json::json;
+use starknet::core::types::FieldElement;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+// every adjustment made by an admin with its reason
+pub const XP_AUDIT_COLLECTION: &str = "xp_audit";
+const MAX_BULK_ADDRESSES: usize = 5000;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum XpAdjustment {
+    RevokeTask(u32),
+    RevokeQuest(u32),
+    RevokeAchievement(u32),
+    Bonus { key: String, experience: i64 },
+}
+
+impl XpAdjustment {
+    pub fn action(&self) -> &'static str {
+        match self {
+            XpAdjustment::RevokeTask(_) => "revoke_task",
+            XpAdjustment::RevokeQuest(_) => "revoke_quest",
+            XpAdjustment::RevokeAchievement(_) => "revoke_achievement",
+            XpAdjustment::Bonus { .. } => "bonus",
+        }
+    }
+
+    pub fn target(&self) -> String {
+        match self {
+            XpAdjustment::RevokeTask(id)
+            | XpAdjustment::RevokeQuest(id)
+            | XpAdjustment::RevokeAchievement(id) => id.to_string(),
+            XpAdjustment::Bonus { key, .. } => key.clone(),
+        }
+    }
+}
+
+// Reads addresses (hex or decimal) from a JSON list and/or a CSV whose first column holds the
+// address. A header line is allowed, duplicates are dropped.
+pub fn parse_address_list(
+    addresses: Option<Vec<String>>,
+    csv: Option<String>,
+) -> Result<Vec<FieldElement>, String> {
+    let mut parsed = Vec::new();
+    for address in addresses.unwrap_or_default() {
+        let addr = FieldElement::from_str(address.trim())
+            .map_err(|_| format!("Invalid address {}", address))?;
+        parsed.push(addr);
+    }
+    if let Some(csv) = csv {
+        for (index, line) in csv.lines().enumerate() {
+            let cell = line
+                .split([',', ';'])
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .trim_matches('"');
+            if cell.is_empty() {
+                continue;
+            }
+            match FieldElement::from_str(cell) {
+                Ok(addr) => parsed.push(addr),
+                Err(_) if index == 0 => continue,
+                Err(_) => return Err(format!("Invalid address {} on line {}", cell, index + 1)),
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    parsed.retain(|addr| seen.insert(*addr));
+    if parsed.is_empty() {
+        return Err("No address provided".to_string());
+    }
+    if parsed.len() > MAX_BULK_ADDRESSES {
+        return Err(format!(
+            "Too many addresses, at most {} per request",
+            MAX_BULK_ADDRESSES
+        ));
+    }
+    Ok(parsed)
+}
+
+fn as_u32(value: Option<&Bson>) -> Option<u32> {
+    match value {
+        Some(Bson::Int32(v)) => u32::try_from(*v).ok(),
+        Some(Bson::Int64(v)) => u32::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+fn as_i64(value: Option<&Bson>) -> i64 {
+    match value {
+        Some(Bson::Int32(v)) => *v as i64,
+        Some(Bson::Int64(v)) => *v,
+        Some(Bson::Double(v)) => *v as i64,
+        _ => 0,
+    }
+}
+
+// Writes the compensating entry of the experience granted by a quest. Grants made before the
+// ledger had sources are compensated with the quest experience when the quest was completed.
+async fn revoke_quest_experience(
+    db: &Database,
+    logger: &Logger,
+    address: &str,
+    quest_id: u32,
+    was_completed: bool,
+) -> Result<i64, mongodb::error::Error> {
+    let quest = db
+        .collection::<Document>("quests")
+        .find_one(doc! { "id": quest_id }, None)
+        .await?;
+    let grant = get_ledger_entry(db, address, &XpSource::Quest(quest_id)).await?;
+    let (experience, timestamp) = match (&grant, &quest) {
+        (Some(grant), _) => (
+            as_i64(grant.get("experience")),
+            grant.get_f64("timestamp").ok(),
+        ),
+        (None, Some(quest)) if was_completed => (as_i64(quest.get("experience")), None),
+        _ => return Ok(0),
+    };
+    if experience == 0 {
+        return Ok(0);
+    }
+
+    // the revocation counts for the day of the grant so that windowed rankings lose it too
+    let revoked_at = timestamp.unwrap_or_else(|| Utc::now().timestamp_millis() as f64);
+    if !record_experience(
+        db,
+        address,
+        &XpSource::QuestRevocation(quest_id),
+        -experience,
+        revoked_at,
+    )
+    .await?
+    {
+        return Ok(0);
+    }
+    if let (Some(timestamp), Some(category)) = (
+        timestamp,
+        quest
+            .as_ref()
+            .and_then(|quest| quest.get_str("category").ok()),
+    ) {
+        credit_season_experience(db, logger, address, Some(category), -experience, timestamp).await;
+    }
+    Ok(-experience)
+}
+
+async fn is_quest_completed(
+    db: &Database,
+    addr: &FieldElement,
+    quest_id: u32,
+) -> Result<bool, mongodb::error::Error> {
+    let missing =
+        get_missing_prerequisites(db, addr, &[QuestPrerequisite::Quest { id: quest_id }]).await?;
+    Ok(missing.is_empty())
+}
+
+// Applies an adjustment to one address. Returns the experience added (negative for revocations),
+// None when there was nothing to change.
+pub async fn apply_adjustment(
+    db: &Database,
+    logger: &Logger,
+    addr: &FieldElement,
+    adjustment: &XpAdjustment,
+) -> Result<Option<i64>, mongodb::error::Error> {
+    let address = addr.to_string();
+    let completed_tasks_collection = db.collection::<Document>("completed_tasks");
+    match adjustment {
+        XpAdjustment::RevokeTask(task_id) => {
+            let task = db
+                .collection::<Document>("tasks")
+                .find_one(doc! { "id": task_id }, None)
+                .await?;
+            let quest_id = task.as_ref().and_then(|task| as_u32(task.get("quest_id")));
+            let was_completed = match quest_id {
+                Some(quest_id) => is_quest_completed(db, addr, quest_id).await?,
+                None => false,
+            };
+            let deleted = completed_tasks_collection
+                .delete_one(doc! { "address": &address, "task_id": task_id }, None)
+                .await?;
+            if deleted.deleted_count == 0 {
+                return Ok(None);
+            }
+            // the quest is not completed anymore
+            match quest_id {
+                Some(quest_id) => Ok(Some(
+                    revoke_quest_experience(db, logger, &address, quest_id, was_completed).await?,
+                )),
+                None => Ok(Some(0)),
+            }
+        }
+        XpAdjustment::RevokeQuest(quest_id) => {
+            let was_completed = is_quest_completed(db, addr, *quest_id).await?;
+            let task_ids: Vec<Bson> = db
+                .collection::<Document>("tasks")
+                .find(doc! { "quest_id": quest_id }, None)
+                .await?
+                .try_collect::<Vec<Document>>()
+                .await?
+                .into_iter()
+                .filter_map(|task| task.get("id").cloned())
+                .collect();
+            let deleted = completed_tasks_collection
+                .delete_many(
+                    doc! { "address": &address, "task_id": { "$in": task_ids } },
+                    None,
+                )
+                .await?;
+            let experience =
+                revoke_quest_experience(db, logger, &address, *quest_id, was_completed).await?;
+            if deleted.deleted_count == 0 && experience == 0 {
+                return Ok(None);
+            }
+            Ok(Some(experience))
+        }
+        XpAdjustment::RevokeAchievement(achievement_id) => {
+            let deleted = db
+                .collection::<Document>("achieved")
+                .delete_one(
+                    doc! { "addr": &address, "achievement_id": achievement_id },
+                    None,
+                )
+                .await?;
+            let grant =
+                get_ledger_entry(db, &address, &XpSource::Achievement(*achievement_id)).await?;
+            let (experience, timestamp) = match grant {
+                Some(grant) => (
+                    as_i64(grant.get("experience")),
+                    grant.get_f64("timestamp").ok(),
+                ),
+                None if deleted.deleted_count > 0 => {
+                    let achievement = db
+                        .collection::<AchievementDocument>("achievements")
+                        .find_one(doc! { "id": achievement_id }, None)
+                        .await?;
+                    (achievement.map(|a| a.experience).unwrap_or(0), None)
+                }
+                None => (0, None),
+            };
+            let revoked = experience != 0
+                && record_experience(
+                    db,
+                    &address,
+                    &XpSource::AchievementRevocation(*achievement_id),
+                    -experience,
+                    timestamp.unwrap_or_else(|| Utc::now().timestamp_millis() as f64),
+                )
+                .await?;
+            if let (true, Some(timestamp)) = (revoked, timestamp) {
+                credit_season_experience(db, logger, &address, None, -experience, timestamp).await;
+            }
+            match (revoked, deleted.deleted_count) {
+                (true, _) => Ok(Some(-experience)),
+                (false, 0) => Ok(None),
+                (false, _) => Ok(Some(0)),
+            }
+        }
+        XpAdjustment::Bonus { key, experience } => {
+            // bonuses are off-platform, they don't count for seasons
+            match grant_experience(db, &address, &XpSource::Bonus(key.clone()), *experience).await?
+            {
+                Some(_) => Ok(Some(*experience)),
+                None => Ok(None),
+            }
+        }
+    }
+}
+
+// Applies an adjustment to every address and records each outcome in the audit trail
+pub async fn apply_bulk_adjustment(
+    db: &Database,
+    logger: &Logger,
+    addresses: Vec<FieldElement>,
+    adjustment: XpAdjustment,
+    reason: &str,
+    admin: &str,
+) -> Response {
+    if reason.trim().is_empty() {
+        return get_error("A reason is required".to_string());
+    }
+
+    let audit_collection = db.collection::<Document>(XP_AUDIT_COLLECTION);
+    let mut results = Vec::new();
+    let (mut applied, mut skipped, mut failed) = (0, 0, 0);
+    for addr in addresses {
+        let outcome = apply_adjustment(db, logger, &addr, &adjustment).await;
+        let (status, experience, error) = match &outcome {
+            Ok(Some(experience)) => {
+                applied += 1;
+                ("applied", *experience, None)
+            }
+            Ok(None) => {
+                skipped += 1;
+                ("skipped", 0, None)
+            }
+            Err(e) => {
+                failed += 1;
+                ("failed", 0, Some(e.to_string()))
+            }
+        };
+
+        let entry = doc! {
+            "address": addr.to_string(),
+            "action": adjustment.action(),
+            "target": adjustment.target(),
+            "experience": experience,
+            "status": status,
+            "error": &error,
+            "reason": reason,
+            "admin": admin,
+            "timestamp": Utc::now().timestamp_millis(),
+        };
+        if let Err(e) = audit_collection.insert_one(entry, None).await {
+            return get_error(format!("Error saving audit trail: {}", e));
+        }
+        results.push(json!({
+            "address": to_hex(addr),
+            "status": status,
+            "experience": experience,
+            "error": error,
+        }));
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "applied": applied,
+            "skipped": skipped,
+            "failed": failed,
+            "results": results,
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_and_csv_addresses() {
+        let csv = "address,comment\n0x1,spam\n\n\"0x2\";bot\n2\n".to_string();
+        let addresses = parse_address_list(Some(vec!["0x3".to_string()]), Some(csv)).unwrap();
+        assert_eq!(
+            addresses,
+            [
+                FieldElement::from(3_u32),
+                FieldElement::ONE,
+                FieldElement::from(2_u32)
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        assert!(parse_address_list(Some(vec!["hello".to_string()]), None).is_err());
+        assert!(parse_address_list(None, Some("0x1\nhello".to_string())).is_err());
+        assert!(parse_address_list(None, Some("address\n".to_string())).is_err());
+        assert!(parse_address_list(None, None).is_err());
+    }
+}
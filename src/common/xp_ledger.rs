@@ -0,0 +1,276 @@
+use crate::common::leaderboard::{
+    add_experience_to_bucket, rebuild_leaderboard_buckets, LEADERBOARD_BUCKETS_COLLECTION,
+};
+use crate::utils::is_duplicate_key;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+
+// append-only ledger, one entry per experience grant
+pub const XP_LEDGER_COLLECTION: &str = "user_exp";
+// total experience per address, materialized from the ledger
+pub const LEADERBOARD_TABLE_COLLECTION: &str = "leaderboard_table";
+
+// What granted experience. Together with the address it is the idempotency key of a ledger
+// entry: a source can grant experience to an address only once.
+#[derive(Debug, PartialEq, Clone)]
+pub enum XpSource {
+    Quest(u32),
+    Achievement(u32),
+    // compensating entries written by admins, a revoked quest or achievement never grants
+    // experience again to the same address
+    QuestRevocation(u32),
+    AchievementRevocation(u32),
+    // off-platform event identified by a key chosen by admins, e.g. "ama-2024-05"
+    Bonus(String),
+}
+
+impl XpSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            XpSource::Quest(_) => "quest",
+            XpSource::Achievement(_) => "achievement",
+            XpSource::QuestRevocation(_) => "quest_revocation",
+            XpSource::AchievementRevocation(_) => "achievement_revocation",
+            XpSource::Bonus(_) => "bonus",
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            XpSource::Quest(id)
+            | XpSource::Achievement(id)
+            | XpSource::QuestRevocation(id)
+            | XpSource::AchievementRevocation(id) => id.to_string(),
+            XpSource::Bonus(key) => key.clone(),
+        }
+    }
+}
+
+pub async fn ensure_xp_ledger_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
+    // entries written before the ledger had sources are not deduplicated
+    let idempotency_key = IndexModel::builder()
+        .keys(doc! { "address": 1, "source_kind": 1, "source_id": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "source_kind": { "$exists": true } })
+                .build(),
+        )
+        .build();
+    db.collection::<Document>(XP_LEDGER_COLLECTION)
+        .create_index(idempotency_key, None)
+        .await?;
+
+    let view_collection = db.collection::<Document>(LEADERBOARD_TABLE_COLLECTION);
+    let timestamp_only = IndexModel::builder().keys(doc! { "timestamp": 1 }).build();
+    view_collection.create_index(timestamp_only, None).await?;
+    let compound_index = IndexModel::builder()
+        .keys(doc! { "experience": -1, "timestamp": 1, "_id": 1 })
+        .build();
+    view_collection.create_index(compound_index, None).await?;
+    Ok(())
+}
+
+// Records the experience granted by source and adds it to the materialized totals. Returns the
+// timestamp of the entry, None without changing anything when the source already granted
+// experience to this address. Seasons are credited by the caller from that timestamp.
+pub async fn grant_experience(
+    db: &Database,
+    address: &str,
+    source: &XpSource,
+    experience: i64,
+) -> Result<Option<f64>, mongodb::error::Error> {
+    let timestamp = Utc::now().timestamp_millis() as f64;
+    match record_experience(db, address, source, experience, timestamp).await? {
+        true => Ok(Some(timestamp)),
+        false => Ok(None),
+    }
+}
+
+// Same as grant_experience with the timestamp setting the day the entry counts for in windowed
+// rankings
+pub async fn record_experience(
+    db: &Database,
+    address: &str,
+    source: &XpSource,
+    experience: i64,
+    timestamp: f64,
+) -> Result<bool, mongodb::error::Error> {
+    let entry = doc! {
+        "address": address,
+        "experience": experience,
+        "timestamp": timestamp,
+        "source_kind": source.kind(),
+        "source_id": source.id(),
+    };
+    match db
+        .collection::<Document>(XP_LEDGER_COLLECTION)
+        .insert_one(entry, None)
+        .await
+    {
+        Ok(_) => {}
+        Err(e) if is_duplicate_key(&e) => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    apply_experience(db, address, experience, timestamp).await?;
+    Ok(true)
+}
+
+// returns the ledger entry written when source granted experience to address
+pub async fn get_ledger_entry(
+    db: &Database,
+    address: &str,
+    source: &XpSource,
+) -> Result<Option<Document>, mongodb::error::Error> {
+    db.collection::<Document>(XP_LEDGER_COLLECTION)
+        .find_one(
+            doc! {
+                "address": address,
+                "source_kind": source.kind(),
+                "source_id": source.id(),
+            },
+            None,
+        )
+        .await
+}
+
+// adds a ledger entry to the total of the address and to its daily bucket
+pub async fn apply_experience(
+    db: &Database,
+    address: &str,
+    experience: i64,
+    timestamp: f64,
+) -> Result<(), mongodb::error::Error> {
+    let options = UpdateOptions::builder().upsert(true).build();
+    db.collection::<Document>(LEADERBOARD_TABLE_COLLECTION)
+        .update_one(
+            doc! { "_id": address },
+            doc! {
+                "$inc": { "experience": experience },
+                "$max": { "timestamp": timestamp },
+            },
+            options,
+        )
+        .await?;
+    add_experience_to_bucket(db, address, experience, timestamp).await
+}
+
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub repaired: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+// Compares the materialized totals and daily buckets with the ledger and rewrites the totals and
+// buckets of every address that drifted. Totals of addresses without ledger entries are removed.
+pub async fn reconcile_leaderboard(
+    db: &Database,
+) -> Result<ReconcileReport, mongodb::error::Error> {
+    let mut report = ReconcileReport::default();
+    let view_collection = db.collection::<Document>(LEADERBOARD_TABLE_COLLECTION);
+
+    let pipeline = vec![
+        doc! {
+            "$group": {
+                "_id": "$address",
+                "experience": { "$sum": "$experience" },
+                "timestamp": { "$max": "$timestamp" }
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": LEADERBOARD_TABLE_COLLECTION,
+                "localField": "_id",
+                "foreignField": "_id",
+                "as": "current"
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": LEADERBOARD_BUCKETS_COLLECTION,
+                "localField": "_id",
+                "foreignField": "address",
+                "as": "buckets"
+            }
+        },
+        doc! {
+            "$project": {
+                "experience": 1,
+                "timestamp": 1,
+                "current": { "$arrayElemAt": ["$current.experience", 0] },
+                "buckets": { "$sum": "$buckets.experience" }
+            }
+        },
+        doc! {
+            "$match": {
+                "$expr": {
+                    "$or": [
+                        { "$ne": ["$current", "$experience"] },
+                        { "$ne": ["$buckets", "$experience"] }
+                    ]
+                }
+            }
+        },
+    ];
+    let mut cursor = db
+        .collection::<Document>(XP_LEDGER_COLLECTION)
+        .aggregate(pipeline, None)
+        .await?;
+    let options = UpdateOptions::builder().upsert(true).build();
+    while let Some(drift) = cursor.try_next().await? {
+        let Ok(address) = drift.get_str("_id") else {
+            continue;
+        };
+        view_collection
+            .update_one(
+                doc! { "_id": address },
+                doc! { "$set": {
+                    "experience": drift.get("experience"),
+                    "timestamp": drift.get("timestamp"),
+                } },
+                options.clone(),
+            )
+            .await?;
+        report.repaired.push(address.to_string());
+    }
+
+    let orphans_pipeline = vec![
+        doc! {
+            "$lookup": {
+                "from": XP_LEDGER_COLLECTION,
+                "localField": "_id",
+                "foreignField": "address",
+                "as": "entries"
+            }
+        },
+        doc! { "$match": { "entries.0": { "$exists": false } } },
+        doc! { "$project": { "_id": 1 } },
+    ];
+    let mut cursor = view_collection.aggregate(orphans_pipeline, None).await?;
+    while let Some(orphan) = cursor.try_next().await? {
+        if let Ok(address) = orphan.get_str("_id") {
+            report.removed.push(address.to_string());
+        }
+    }
+    if !report.removed.is_empty() {
+        view_collection
+            .delete_many(doc! { "_id": { "$in": &report.removed } }, None)
+            .await?;
+    }
+
+    let drifted: Vec<String> = report
+        .repaired
+        .iter()
+        .chain(report.removed.iter())
+        .cloned()
+        .collect();
+    if !drifted.is_empty() {
+        rebuild_leaderboard_buckets(db, Some(&drifted)).await?;
+    }
+    Ok(report)
+}
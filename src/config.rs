@@ -1,5 +1,7 @@
+use axum_client_ip::SecureClientIpSource;
 use serde::{self, Deserialize, Deserializer};
 use starknet::core::types::FieldElement;
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 
@@ -17,17 +19,33 @@ pub_struct!(Clone, Deserialize; WatchtowerTypes {
     severe: String,
 });
 
-pub_struct!(Clone, Deserialize; Server { port: u16 });
+#[derive(Clone, Deserialize)]
+pub struct Server {
+    pub port: u16,
+    // where the client ip is read from: "ConnectInfo" when clients connect directly, the header
+    // set by the proxy (e.g. "RightmostXForwardedFor") when the api runs behind one
+    #[serde(default = "default_ip_source")]
+    pub ip_source: SecureClientIpSource,
+}
+
+fn default_ip_source() -> SecureClientIpSource {
+    SecureClientIpSource::ConnectInfo
+}
 
 pub_struct!(Clone, Deserialize; Database {
     name: String,
     connection_string: String,
 });
 
-pub_struct!(Clone, Deserialize; NftContract {
-    address: String,
-    private_key: FieldElement,
-});
+#[derive(Clone, Deserialize)]
+pub struct NftContract {
+    pub address: String,
+    // first block indexed by the nft_mints job
+    pub deploy_block: u64,
+    pub signer: SignerConfig,
+    #[serde(default)]
+    pub signature: ClaimSignature,
+}
 
 pub_struct!(Clone, Deserialize;  Variables {
     app_link: String,
@@ -101,9 +119,218 @@ pub_struct!(Clone, Deserialize;  Twitter {
     oauth2_secret: String,
 });
 
-pub_struct!(Clone, Deserialize;  QuestBoost{
-    private_key: FieldElement,
-    update_interval: u64,
+#[derive(Clone, Deserialize)]
+pub struct QuestBoost {
+    pub signer: SignerConfig,
+    pub claim_window: i64,
+    #[serde(default)]
+    pub signature: ClaimSignature,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SignatureScheme {
+    // pedersen chain of the claim fields, for contracts deployed before SNIP-12 claims
+    #[default]
+    Legacy,
+    Snip12,
+}
+
+impl<'de> Deserialize<'de> for SignatureScheme {
+    fn deserialize<D>(deserializer: D) -> Result<SignatureScheme, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        match s.to_lowercase().as_str() {
+            "legacy" => Ok(SignatureScheme::Legacy),
+            "snip12" => Ok(SignatureScheme::Snip12),
+            _ => Err(serde::de::Error::custom("Unexpected signature scheme")),
+        }
+    }
+}
+
+// key signing the claims of a contract, secrets are read from the named environment variables
+#[derive(Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SignerConfig {
+    Local {
+        private_key: FieldElement,
+    },
+    // key file encrypted with a passphrase
+    Keystore {
+        path: String,
+        passphrase_env: String,
+    },
+    Remote {
+        url: String,
+        key_id: String,
+        token_env: Option<String>,
+        timeout_ms: u64,
+    },
+}
+
+// where admin uploads are stored, credentials are read from the named environment variables
+#[derive(Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImageStoreConfig {
+    Local {
+        folder: String,
+        public_url: String,
+    },
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key_env: String,
+        secret_key_env: String,
+        public_url: String,
+        // requests to the bucket fail after this delay
+        #[serde(default = "default_store_timeout_ms")]
+        timeout_ms: u64,
+    },
+}
+
+fn default_store_timeout_ms() -> u64 {
+    30_000
+}
+
+pub_struct!(Clone, Deserialize;  Images {
+    // bytes of an upload, requests are also capped by the 2MB default body limit of axum
+    max_size: usize,
+    // larger images are downscaled to fit this size
+    max_dimension: u32,
+    thumbnail_size: u32,
+    // larger images are rejected before being decoded, max_alloc caps the decoder memory in bytes
+    max_image_width: u32,
+    max_image_height: u32,
+    max_alloc: u64,
+    store: ImageStoreConfig,
+});
+
+// a line of text of the share cards, {title}, {name}, {date}, {xp} and {rank} are replaced by
+// the values of the card
+pub_struct!(Clone, Deserialize, Debug;  ShareCardText {
+    text: String,
+    // position of the baseline start, in pixels
+    x: u32,
+    y: u32,
+    size: f32,
+    // hex color, #rrggbb or #rrggbbaa
+    color: String,
+    // longer texts are cut with an ellipsis
+    max_width: Option<u32>,
+});
+
+pub_struct!(Clone, Deserialize, Debug;  ShareCards {
+    // ttf or otf font used for all the texts
+    font: String,
+    width: u32,
+    height: u32,
+    background: String,
+    // the quest or achievement image is scaled to fill the top of the card
+    banner_height: u32,
+    texts: Vec<ShareCardText>,
+    // seconds before a rendered card is evicted from the cache
+    cache_ttl: u64,
+});
+
+// How the claims of a contract are signed, legacy unless the section opts in to SNIP-12. Name,
+// version and verifying_contract form the SNIP-12 domain.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ClaimSignature {
+    pub scheme: SignatureScheme,
+    pub name: String,
+    pub version: String,
+    // contract checking the claims, the nft contract address when omitted for nft claims
+    pub verifying_contract: Option<FieldElement>,
+}
+
+impl ClaimSignature {
+    fn check(&self, section: &str, signing: &Option<Signing>) -> Result<(), String> {
+        if self.scheme == SignatureScheme::Legacy {
+            return Ok(());
+        }
+        if self.name.is_empty() || self.version.is_empty() {
+            return Err(format!("{} needs a name and a version", section));
+        }
+        if self.verifying_contract.is_none() {
+            return Err(format!("{} needs a verifying_contract", section));
+        }
+        if signing.is_none() {
+            return Err(format!("{} needs a [signing] section", section));
+        }
+        Ok(())
+    }
+}
+
+pub_struct!(Clone, Deserialize;  Signing {
+    // SN_MAIN or SN_SEPOLIA
+    chain_id: String,
+    // seconds before a SNIP-12 claim expires
+    validity: i64,
+});
+
+pub_struct!(Clone, Deserialize;  JobConfig {
+    enabled: bool,
+    schedule: String,
+});
+
+pub_struct!(Clone, Deserialize;  Sybil {
+    // addresses with a risk score above it are excluded from boost raffles, boosts can override it
+    boost_threshold: f64,
+});
+
+pub_struct!(Clone, Deserialize;  ClaimableAlias {
+    // legacy per-quest claimable url, e.g. /quests/ekubo/claimable
+    path: String,
+    quest_id: u32,
+});
+
+pub_struct!(Clone, Deserialize;  HttpHost {
+    host: String,
+    timeout_ms: Option<u64>,
+    retries: Option<u32>,
+    cache_ttl: Option<u64>,
+});
+
+pub_struct!(Clone, Deserialize;  Http {
+    timeout_ms: u64,
+    retries: u32,
+    retry_delay_ms: u64,
+    // consecutive failures after which a host isn't called for breaker_cooldown seconds
+    breaker_failures: u32,
+    breaker_cooldown: u64,
+    // seconds responses are cached for unless Cache-Control says otherwise, 0 disables the cache
+    cache_ttl: u64,
+    hosts: Vec<HttpHost>,
+});
+
+pub_struct!(Clone, Deserialize;  Verification {
+    // seconds during which a failed on-chain verification is answered from the cache, 0 disables it
+    failure_cooldown: i64,
+});
+
+pub_struct!(Clone, Deserialize;  RateLimitGroup {
+    name: String,
+    // patterns of the paths of the group, * matches any characters
+    paths: Vec<String>,
+    // a capacity of 0 disables the bucket
+    ip_capacity: u32,
+    ip_refill_per_minute: u32,
+    address_capacity: u32,
+    address_refill_per_minute: u32,
+});
+
+pub_struct!(Clone, Deserialize;  RateLimit {
+    // "memory" or "mongo" to share the limits between instances
+    store: String,
+    groups: Vec<RateLimitGroup>,
+});
+
+pub_struct!(Clone, Deserialize;  Webhooks {
+    endpoints: Vec<String>,
+    token: String,
 });
 
 pub_struct!(Clone, Deserialize;  Discord {
@@ -186,13 +413,32 @@ pub_struct!(Clone, Deserialize;  ProtocolStats {
     lending_api_endpoint: String,
     derivates_api_endpoint: String,
     alt_protocols_api_endpoint: String,
+    // seconds after which the latest snapshot is served as stale
+    stale_after: i64,
+    history_days: u64,
 });
 
-pub_struct!(Clone, Deserialize;  Rewards {
-    nimbora: Contract,
-    vesu: Contract,
+pub_struct!(Clone, Deserialize;  RewardProviderConfig {
+    enabled: bool,
+    timeout_ms: u64,
 });
 
+#[derive(Clone, Deserialize)]
+pub struct Rewards {
+    pub nimbora: Contract,
+    pub vesu: Contract,
+    // timeout of the providers missing from providers
+    pub timeout_ms: u64,
+    pub providers: HashMap<String, RewardProviderConfig>,
+    // seconds the last rewards fetched from a provider are served while it fails
+    #[serde(default = "default_cache_max_age")]
+    pub cache_max_age: u64,
+}
+
+fn default_cache_max_age() -> u64 {
+    7 * 86_400
+}
+
 pub_struct!(Clone, Deserialize;  Token {
     contract: FieldElement,
     symbol: String,
@@ -207,6 +453,8 @@ pub_struct!(Clone, Deserialize;  Config {
     server: Server,
     database: Database,
     nft_contract: NftContract,
+    // only used by SNIP-12 claims
+    signing: Option<Signing>,
     discover:ProtocolStats,
     variables: Variables,
     starknetid_contracts: StarknetIdContracts,
@@ -222,7 +470,16 @@ pub_struct!(Clone, Deserialize;  Config {
     pyramid: ApiEndpoint,
     auth:AuthSetup,
     rewards: Rewards,
-    tokens: Tokens
+    tokens: Tokens,
+    webhooks: Webhooks,
+    sybil: Sybil,
+    rate_limit: RateLimit,
+    verification: Verification,
+    http: Http,
+    images: Images,
+    share_cards: ShareCards,
+    claimable_aliases: Vec<ClaimableAlias>,
+    jobs: HashMap<String, JobConfig>,
 });
 
 pub fn load() -> Config {
@@ -237,10 +494,24 @@ pub fn load() -> Config {
         panic!("error: unable to read file with path \"{}\"", config_path);
     }
 
-    match toml::from_str(file_contents.unwrap().as_str()) {
+    let mut conf: Config = match toml::from_str(file_contents.unwrap().as_str()) {
         Ok(loaded) => loaded,
         Err(err) => {
             panic!("error: unable to deserialize config. {}", err);
         }
+    };
+    if conf.nft_contract.signature.verifying_contract.is_none() {
+        conf.nft_contract.signature.verifying_contract =
+            FieldElement::from_hex_be(&conf.nft_contract.address).ok();
+    }
+    let checks = [
+        (&conf.nft_contract.signature, "[nft_contract.signature]"),
+        (&conf.quest_boost.signature, "[quest_boost.signature]"),
+    ];
+    for (signature, section) in checks {
+        if let Err(err) = signature.check(section, &conf.signing) {
+            panic!("error: invalid config. {}", err);
+        }
     }
+    conf
 }
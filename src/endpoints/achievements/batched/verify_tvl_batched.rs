@@ -29,9 +29,8 @@ pub async fn handler(
         "https://public.starkendefi.xyz/public/aggregates/{}",
         to_hex(addr)
     );
-    let client = reqwest::Client::new();
-    match client.get(url).send().await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
+    match state.http.send(state.http.get(&url)).await {
+        Ok(response) => match response.json::<serde_json::Value>() {
             Ok(json) => {
                 if let Some(total_tvl_dollars) = json["total_tvl_dollars"].as_f64() {
                     if total_tvl_dollars < 100.0 {
@@ -2,6 +2,7 @@ pub mod batched;
 pub mod claim;
 pub mod fetch;
 pub mod fetch_buildings;
+pub mod share_card;
 pub mod verify_achieved_quests;
 pub mod verify_avnu;
 pub mod verify_briq;
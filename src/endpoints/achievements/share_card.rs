@@ -0,0 +1,59 @@
+use crate::common::share_cards::{get_share_card_response, ShareCard};
+use crate::models::{AchievedDocument, AchievementDocument, AppState};
+use crate::utils::get_error;
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ShareCardQuery {
+    id: u32,
+    addr: FieldElement,
+}
+
+// png preview of an achievement unlocked by addr
+#[route(get, "/achievements/share_card")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ShareCardQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let achievement = match state
+        .db
+        .collection::<AchievementDocument>("achievements")
+        .find_one(doc! { "id": query.id }, None)
+        .await
+    {
+        Ok(Some(achievement)) => achievement,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Achievement not found").into_response(),
+        Err(_) => return get_error("Error querying achievement".to_string()),
+    };
+    let address = query.addr.to_string();
+    let achieved = match state
+        .db
+        .collection::<AchievedDocument>("achieved")
+        .find_one(doc! { "addr": &address, "achievement_id": query.id }, None)
+        .await
+    {
+        Ok(Some(achieved)) => achieved,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Achievement not unlocked").into_response(),
+        Err(_) => return get_error("Error querying achievement".to_string()),
+    };
+    let card = ShareCard::new(
+        &achievement.img_url,
+        &achievement.name,
+        query.addr,
+        achieved.timestamp / 1000,
+    );
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    get_share_card_response(&state, card, if_none_match).await
+}
@@ -33,9 +33,8 @@ pub async fn handler(
         "{}/get_completed_quests?addr={}",
         state.conf.variables.api_link, addr
     );
-    let client = reqwest::Client::new();
-    match client.get(&url).send().await {
-        Ok(response) => match response.json::<Vec<u32>>().await {
+    match state.http.send(state.http.get(&url)).await {
+        Ok(response) => match response.json::<Vec<u32>>() {
             Ok(quests) => {
                 if quests.is_empty() {
                     return get_error("You have not completed any quests.".to_string());
@@ -30,9 +30,8 @@ pub async fn handler(
     }
 
     let url = format!("https://starknet.api.avnu.fi/v1/takers/{}", to_hex(addr));
-    let client = reqwest::Client::new();
-    match client.get(url).send().await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
+    match state.http.send(state.http.get(&url)).await {
+        Ok(response) => match response.json::<serde_json::Value>() {
             Ok(json) => {
                 if let Some(volume) = json["volumeInUSD"].as_f64() {
                     if (achievement_id == 17 && volume >= 500.0)
@@ -45,7 +45,7 @@ pub async fn handler(
                 "https://api.briq.construction/v1/user/data/starknet-mainnet-dojo/{}",
                 to_hex(addr)
             );
-            match fetch_json_from_url(url).await {
+            match fetch_json_from_url(&state.http, url).await {
                 Ok(response) => {
                     if let Some(sets) = response.get("sets") {
                         match sets {
@@ -56,7 +56,7 @@ pub async fn handler(
                                             "https://api.briq.construction/v1/metadata/starknet-mainnet-dojo/{}",
                                             set_str
                                         );
-                                        match fetch_json_from_url(url).await {
+                                        match fetch_json_from_url(&state.http, url).await {
                                             Ok(metadata_response) => {
                                                 if let Some(properties) =
                                                     metadata_response.get("properties")
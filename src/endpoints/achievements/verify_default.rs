@@ -61,7 +61,7 @@ pub async fn handler(
         Ok(Some(_)) => (StatusCode::OK, Json(json!({"achieved": true}))).into_response(),
         Ok(None) => match get_args(state.conf.clone(), achievement_id) {
             Ok((contract, limit, is_whitelisted)) => {
-                match execute_has_nft(&state.conf, addr, contract, limit, is_whitelisted).await {
+                match execute_has_nft(&state, addr, contract, limit, is_whitelisted).await {
                     Ok(is_achieved) => {
                         if is_achieved {
                             match state
@@ -33,9 +33,8 @@ pub async fn handler(
         "https://stack.starkendefi.xyz/public/aggregates/{}",
         to_hex(addr)
     );
-    let client = reqwest::Client::new();
-    match client.get(url).send().await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
+    match state.http.send(state.http.get(&url)).await {
+        Ok(response) => match response.json::<serde_json::Value>() {
             Ok(json) => {
                 if let Some(total_tvl_dollars) = json["total_tvl_dollars"].as_f64() {
                     if (achievement_id == 11 && total_tvl_dollars >= 100.0)
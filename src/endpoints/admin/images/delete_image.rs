@@ -0,0 +1,44 @@
+use crate::common::images::{get_thumbnail_name, is_image_name};
+use crate::image_store::THUMBNAILS_PREFIX;
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; DeleteImageQuery {
+    name: String,
+});
+
+// images can be shared by the quests of several admins, only super users delete them
+#[route(post, "/admin/images/delete", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Json(body): Json<DeleteImageQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+    if !is_image_name(&body.name) || body.name.starts_with(THUMBNAILS_PREFIX) {
+        return (StatusCode::BAD_REQUEST, "Invalid image name").into_response();
+    }
+
+    for name in [body.name.clone(), get_thumbnail_name(&body.name)] {
+        if let Err(e) = state.images.delete(&name).await {
+            return get_error(e);
+        }
+    }
+    (
+        StatusCode::OK,
+        Json(json!({"message": "deleted successfully"})),
+    )
+        .into_response()
+}
@@ -0,0 +1,36 @@
+use crate::common::images::{get_thumbnail_name, is_image_name};
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde_json::json;
+use std::sync::Arc;
+
+#[route(get, "/admin/images/list", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(_sub): Extension<String>,
+) -> impl IntoResponse {
+    match state.images.list().await {
+        Ok(names) => {
+            let images: Vec<_> = names
+                .iter()
+                .filter(|name| is_image_name(name))
+                .map(|name| {
+                    json!({
+                        "name": name,
+                        "url": state.images.get_url(name),
+                        "thumbnail_url": state.images.get_url(&get_thumbnail_name(name)),
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(images)).into_response()
+        }
+        Err(e) => get_error(e),
+    }
+}
@@ -0,0 +1,3 @@
+pub mod delete_image;
+pub mod get_images;
+pub mod upload_image;
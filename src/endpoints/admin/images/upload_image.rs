@@ -0,0 +1,51 @@
+use crate::common::images::{get_thumbnail_name, process_image};
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, Multipart, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde_json::json;
+use std::sync::Arc;
+
+#[route(post, "/admin/images/upload", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(_sub): Extension<String>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let data = match multipart.next_field().await {
+        Ok(Some(field)) => match field.bytes().await {
+            Ok(data) => data,
+            Err(e) => return get_error(format!("Failed to read file data: {}", e)),
+        },
+        _ => return get_error("No valid file provided".to_string()),
+    };
+
+    let conf = state.conf.images.clone();
+    let image = match tokio::task::spawn_blocking(move || process_image(&data, &conf)).await {
+        Ok(Ok(image)) => image,
+        Ok(Err(e)) => return (StatusCode::BAD_REQUEST, e).into_response(),
+        Err(e) => return get_error(format!("Failed to process image: {}", e)),
+    };
+    let thumbnail_name = get_thumbnail_name(&image.name);
+    if let Err(e) = state.images.put(&thumbnail_name, image.thumbnail).await {
+        return get_error(e);
+    }
+    if let Err(e) = state.images.put(&image.name, image.image).await {
+        return get_error(e);
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "name": image.name,
+            "url": state.images.get_url(&image.name),
+            "thumbnail_url": state.images.get_url(&thumbnail_name),
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,48 @@
+use crate::jobs::JOB_RUNS_COLLECTION;
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct GetRunsQuery {
+    name: String,
+    limit: Option<i64>,
+}
+
+#[route(get, "/admin/jobs/get_runs", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Query(query): Query<GetRunsQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    let collection = state.db.collection::<Document>(JOB_RUNS_COLLECTION);
+    let options = FindOptions::builder()
+        .sort(doc! { "started_at": -1 })
+        .limit(query.limit.unwrap_or(50))
+        .projection(doc! { "_id": 0 })
+        .build();
+
+    match collection.find(doc! { "job": &query.name }, options).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(runs) => (StatusCode::OK, Json(runs)).into_response(),
+            Err(e) => get_error(format!("Error reading job runs: {}", e)),
+        },
+        Err(e) => get_error(format!("Error querying job runs: {}", e)),
+    }
+}
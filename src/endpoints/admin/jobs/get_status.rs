@@ -0,0 +1,74 @@
+use crate::jobs::{lease::JOB_LEASES_COLLECTION, JOBS_STATUS_COLLECTION};
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use std::sync::Arc;
+
+#[route(get, "/admin/jobs/get_status", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    let collection = state.db.collection::<Document>(JOBS_STATUS_COLLECTION);
+    let pipeline = vec![
+        doc! {
+            "$lookup": doc! {
+                "from": JOB_LEASES_COLLECTION,
+                "localField": "_id",
+                "foreignField": "_id",
+                "as": "lease"
+            }
+        },
+        doc! {
+            "$project": doc! {
+                "_id": 0,
+                "name": "$_id",
+                "paused": 1,
+                "trigger_requested": 1,
+                "last_run": 1,
+                "lease": doc! { "$arrayElemAt": ["$lease", 0] }
+            }
+        },
+    ];
+
+    let mut jobs = match collection.aggregate(pipeline, None).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(jobs) => jobs,
+            Err(e) => return get_error(format!("Error reading jobs status: {}", e)),
+        },
+        Err(e) => return get_error(format!("Error querying jobs status: {}", e)),
+    };
+
+    // add the configured schedule, including jobs which never ran yet
+    for (name, job_conf) in &state.conf.jobs {
+        match jobs
+            .iter_mut()
+            .find(|job| job.get_str("name").ok() == Some(name.as_str()))
+        {
+            Some(job) => {
+                job.insert("enabled", job_conf.enabled);
+                job.insert("schedule", job_conf.schedule.clone());
+            }
+            None => jobs.push(doc! {
+                "name": name,
+                "enabled": job_conf.enabled,
+                "schedule": &job_conf.schedule,
+            }),
+        }
+    }
+
+    (StatusCode::OK, Json(jobs)).into_response()
+}
@@ -0,0 +1,4 @@
+pub mod get_runs;
+pub mod get_status;
+pub mod pause_job;
+pub mod trigger_job;
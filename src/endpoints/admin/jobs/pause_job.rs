@@ -0,0 +1,50 @@
+use crate::jobs::JOBS_STATUS_COLLECTION;
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, Document};
+use mongodb::options::UpdateOptions;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; PauseJobQuery {
+    name: String,
+    paused: bool,
+});
+
+#[route(post, "/admin/jobs/pause", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Json(body): Json<PauseJobQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+    if !state.conf.jobs.contains_key(&body.name) {
+        return get_error(format!("Unknown job {}", body.name));
+    }
+
+    let collection = state.db.collection::<Document>(JOBS_STATUS_COLLECTION);
+    let update = doc! { "$set": { "paused": body.paused } };
+    let options = UpdateOptions::builder().upsert(true).build();
+    match collection
+        .update_one(doc! { "_id": &body.name }, update, options)
+        .await
+    {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(json!({"message": "updated successfully"})),
+        )
+            .into_response(),
+        Err(e) => get_error(format!("Error updating job: {}", e)),
+    }
+}
@@ -0,0 +1,74 @@
+use crate::common::leaderboard::{get_scoped_leaderboard_source, LeaderboardScope, RankingMetric};
+use crate::endpoints::leaderboard::get_ranking::get_ranking_page;
+use crate::middleware::auth::auth_middleware;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Extension, Query, State},
+    response::IntoResponse,
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetPartnerRankingQuery {
+    // restricts the ranking to one quest of the partner
+    quest_id: Option<u32>,
+    // only used by super_user, partners always see their own quests
+    issuer: Option<String>,
+    // address to center the page on, the page starts at the top when missing
+    addr: Option<String>,
+    page_size: i64,
+    shift: i64,
+    // "xp" (default) or "time"
+    sort_by: Option<String>,
+});
+
+#[route(get, "/admin/leaderboard/get_partner_ranking", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Query(query): Query<GetPartnerRankingQuery>,
+) -> impl IntoResponse {
+    let issuer = match (sub == "super_user", &query.issuer) {
+        (true, Some(issuer)) => issuer.clone(),
+        (true, None) if query.quest_id.is_some() => String::new(),
+        (true, None) => return get_error("issuer or quest_id is required".to_string()),
+        (false, _) => sub,
+    };
+    let metric = match RankingMetric::parse(query.sort_by.as_deref().unwrap_or("xp")) {
+        Ok(metric) => metric,
+        Err(e) => return get_error(e),
+    };
+
+    let scope = match query.quest_id {
+        Some(quest_id) => {
+            let mut filter = doc! { "id": quest_id };
+            if !issuer.is_empty() {
+                filter.insert("issuer", &issuer);
+            }
+            match state
+                .db
+                .collection::<Document>("quests")
+                .find_one(filter, None)
+                .await
+            {
+                Ok(Some(_)) => LeaderboardScope::Quest(quest_id),
+                Ok(None) => return get_error("quest does not exist".to_string()),
+                Err(e) => return get_error(format!("Error querying quest: {}", e)),
+            }
+        }
+        None => LeaderboardScope::Issuer(issuer),
+    };
+
+    let (collection, source_pipeline) = get_scoped_leaderboard_source(&state.db, &scope);
+    get_ranking_page(
+        &collection,
+        source_pipeline,
+        metric,
+        query.addr.as_deref().unwrap_or_default(),
+        query.page_size,
+        query.shift,
+    )
+    .await
+}
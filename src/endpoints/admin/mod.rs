@@ -5,11 +5,17 @@ pub mod custom_api;
 pub mod delete_task;
 pub mod discord;
 pub mod domain;
+pub mod images;
+pub mod jobs;
+pub mod leaderboard;
 pub mod login;
 pub mod nft_uri;
 pub mod quest;
 pub mod quest_boost;
 pub mod quiz;
+pub mod season;
+pub mod sybil;
 pub mod twitter;
-pub mod upload_image;
+pub mod upstreams;
 pub mod user;
+pub mod xp;
@@ -1,5 +1,6 @@
+use crate::common::nft_rewards::{get_quest_claim_tasks, validate_claim_tasks, validate_unlock};
 use crate::middleware::auth::auth_middleware;
-use crate::models::{NFTUri, QuestDocument, QuestTaskDocument};
+use crate::models::{NFTUri, NftUnlock, QuestDocument, QuestTaskDocument};
 use crate::utils::get_next_task_id;
 use crate::utils::verify_quest_auth;
 use crate::{models::AppState, utils::get_error};
@@ -19,6 +20,9 @@ pub_struct!(Deserialize; CreateCustom {
     name: String,
     desc: String,
     image: String,
+    animation_url: Option<String>,
+    unlock: Option<NftUnlock>,
+    claim_task_id: Option<u32>,
 });
 
 #[route(post, "/admin/nft_uri/create", auth_middleware)]
@@ -36,6 +40,19 @@ pub async fn handler(
         return get_error("Error creating task".to_string());
     };
 
+    if let Some(Err(e)) = body.unlock.as_ref().map(validate_unlock) {
+        return get_error(e);
+    }
+    match get_quest_claim_tasks(&state.db, body.quest_id, None).await {
+        Ok((quest_tasks, mut claim_tasks)) => {
+            claim_tasks.push(body.claim_task_id);
+            if let Err(e) = validate_claim_tasks(&quest_tasks, &claim_tasks) {
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+        }
+        Err(e) => return get_error(format!("Error querying quest levels: {}", e)),
+    }
+
     let state_last_id = state.last_task_id.lock().await;
 
     let next_id = get_next_task_id(&insert_collection, state_last_id.clone()).await;
@@ -44,9 +61,12 @@ pub async fn handler(
         name: body.name.clone(),
         description: body.desc.clone(),
         image: body.image.clone(),
+        animation_url: body.animation_url.clone(),
         quest_id: body.quest_id.clone() as i64,
         id: next_id.into(),
         attributes: None,
+        unlock: body.unlock.clone(),
+        claim_task_id: body.claim_task_id,
     };
 
     // insert document to boost collection
@@ -1,5 +1,6 @@
+use crate::common::nft_rewards::{get_quest_claim_tasks, validate_claim_tasks, validate_unlock};
 use crate::middleware::auth::auth_middleware;
-use crate::models::NFTUri;
+use crate::models::{NFTUri, NftUnlock};
 use crate::{models::AppState, utils::get_error};
 use axum::{
     extract::State,
@@ -7,7 +8,7 @@ use axum::{
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, to_bson};
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -17,6 +18,9 @@ pub_struct!(Deserialize; CreateCustom {
     name: Option<String>,
     desc: Option<String>,
     image: Option<String>,
+    animation_url: Option<String>,
+    unlock: Option<NftUnlock>,
+    claim_task_id: Option<u32>,
 });
 
 #[route(post, "/admin/nft_uri/update", auth_middleware)]
@@ -42,6 +46,35 @@ pub async fn handler(
     if let Some(image) = &body.image {
         update_doc.insert("image", image);
     }
+    if let Some(animation_url) = &body.animation_url {
+        update_doc.insert("animation_url", animation_url);
+    }
+    if let Some(unlock) = &body.unlock {
+        if let Err(e) = validate_unlock(unlock) {
+            return get_error(e);
+        }
+        match to_bson(unlock) {
+            Ok(unlock) => update_doc.insert("unlock", unlock),
+            Err(e) => return get_error(format!("Invalid unlock rules: {}", e)),
+        };
+    }
+    if let Some(claim_task_id) = &body.claim_task_id {
+        let level = match collection.find_one(doc! { "id": &body.id }, None).await {
+            Ok(Some(level)) => level,
+            Ok(None) => return get_error(format!("NFT uri {} not found", body.id)),
+            Err(e) => return get_error(format!("Error querying NFT uri: {}", e)),
+        };
+        match get_quest_claim_tasks(&state.db, level.quest_id, Some(body.id)).await {
+            Ok((quest_tasks, mut claim_tasks)) => {
+                claim_tasks.push(Some(*claim_task_id));
+                if let Err(e) = validate_claim_tasks(&quest_tasks, &claim_tasks) {
+                    return (StatusCode::BAD_REQUEST, e).into_response();
+                }
+            }
+            Err(e) => return get_error(format!("Error querying quest levels: {}", e)),
+        }
+        update_doc.insert("claim_task_id", claim_task_id);
+    }
 
     // update quest query
     let update = doc! {
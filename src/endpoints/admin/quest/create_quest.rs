@@ -1,5 +1,8 @@
+use crate::common::images::get_image_url;
+use crate::common::quest_prerequisites::validate_prerequisites;
+use crate::jobs::quests_expiry::is_quest_expired;
 use crate::middleware::auth::auth_middleware;
-use crate::models::{QuestInsertDocument, QuestTaskDocument};
+use crate::models::{QuestInsertDocument, QuestPrerequisite, QuestTaskDocument};
 use crate::utils::get_next_task_id;
 use crate::{models::AppState, utils::get_error};
 use axum::{
@@ -8,7 +11,8 @@ use axum::{
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use mongodb::bson::{doc, from_document};
+use chrono::Utc;
+use mongodb::bson::{doc, from_document, to_bson};
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -27,6 +31,7 @@ pub_struct!(Deserialize; CreateQuestQuery {
     title_card: String,
     issuer: Option<String>,
     mandatory_domain: Option<String>,
+    prerequisites: Option<Vec<QuestPrerequisite>>,
 });
 
 #[route(post, "/admin/quest/create", auth_middleware)]
@@ -42,8 +47,18 @@ pub async fn handler(
 
     let next_id = get_next_task_id(&insert_collection, state_last_id.clone()).await;
 
+    if let Some(prerequisites) = &body.prerequisites {
+        if let Err(e) = validate_prerequisites(&state.db, next_id as u32, prerequisites).await {
+            return get_error(e);
+        }
+    }
+
+    let logo = get_image_url(state.images.as_ref(), &body.logo);
+    let rewards_img = get_image_url(state.images.as_ref(), &body.rewards_img);
+    let img_card = get_image_url(state.images.as_ref(), &body.img_card);
+
     let nft_reward = doc! {
-        "img": body.img_card.clone().to_string(),
+        "img": &img_card,
         "level": 1,
     };
 
@@ -65,18 +80,23 @@ pub async fn handler(
         "issuer": &issuer,
         "rewards_endpoint":"/quests/claimable",
         "rewards_title": &body.rewards_title,
-        "rewards_img": &body.rewards_img,
+        "rewards_img": &rewards_img,
         "rewards_nfts": vec![nft_reward],
-        "logo": &body.logo,
-        "img_card": &body.img_card,
+        "logo": &logo,
+        "img_card": &img_card,
         "title_card": &body.title_card,
         "mandatory_domain": &body.mandatory_domain,
+        "prerequisites": to_bson(&body.prerequisites).unwrap(),
     };
 
     match &body.expiry {
         Some(expiry) => new_document.insert("expiry", expiry),
         None => new_document.insert("expiry", None::<String>),
     };
+    new_document.insert(
+        "expired",
+        is_quest_expired(body.expiry, Utc::now().timestamp_millis()),
+    );
 
     match issuer == "Starknet ID" {
         true => new_document.insert("experience", 50),
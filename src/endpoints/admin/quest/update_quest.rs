@@ -1,5 +1,8 @@
+use crate::common::images::get_image_url;
+use crate::common::quest_prerequisites::validate_prerequisites;
+use crate::jobs::quests_expiry::is_quest_expired;
 use crate::middleware::auth::auth_middleware;
-use crate::models::{AppState, QuestDocument, Banner};
+use crate::models::{AppState, QuestDocument, Banner, QuestPrerequisite};
 use crate::utils::get_error;
 use axum::{
     extract::{Extension, State},
@@ -7,6 +10,7 @@ use axum::{
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
+use chrono::Utc;
 
 use mongodb::options::FindOneAndUpdateOptions;
 
@@ -31,6 +35,7 @@ pub_struct!(Deserialize; UpdateQuestQuery {
     title_card: Option<String>,
     issuer: Option<String>,
     banner: Option<Banner>,    
+    prerequisites: Option<Vec<QuestPrerequisite>>,
 });
 
 #[route(post, "/admin/quest/update", auth_middleware)]
@@ -56,6 +61,19 @@ pub async fn handler(
         return get_error("quest does not exist".to_string());
     }
 
+    let logo = body
+        .logo
+        .as_deref()
+        .map(|logo| get_image_url(state.images.as_ref(), logo));
+    let rewards_img = body
+        .rewards_img
+        .as_deref()
+        .map(|rewards_img| get_image_url(state.images.as_ref(), rewards_img));
+    let img_card = body
+        .img_card
+        .as_deref()
+        .map(|img_card| get_image_url(state.images.as_ref(), img_card));
+
     let mut update_doc = Document::new();
     if let Some(name) = &body.name {
         update_doc.insert("name", name);
@@ -65,6 +83,10 @@ pub async fn handler(
     }
     if let Some(expiry) = &body.expiry {
         update_doc.insert("expiry", expiry);
+        update_doc.insert(
+            "expired",
+            is_quest_expired(Some(*expiry), Utc::now().timestamp_millis()),
+        );
     }
     if let Some(start_time) = &body.start_time {
         update_doc.insert("start_time", start_time);
@@ -82,16 +104,16 @@ pub async fn handler(
     if let Some(category) = &body.category {
         update_doc.insert("category", category);
     }
-    if let Some(logo) = &body.logo {
+    if let Some(logo) = &logo {
         update_doc.insert("logo", logo);
     }
     if let Some(logo) = &body.issuer {
         update_doc.insert("issuer", logo);
     }
-    if let Some(rewards_img) = &body.rewards_img {
+    if let Some(rewards_img) = &rewards_img {
         update_doc.insert("rewards_img", rewards_img);
         let nft_reward = doc! {
-            "img": &body.rewards_img.clone(),
+            "img": rewards_img,
             "level": 1,
         };
         update_doc.insert("rewards_nfts", vec![nft_reward]);
@@ -99,7 +121,7 @@ pub async fn handler(
     if let Some(rewards_title) = &body.rewards_title {
         update_doc.insert("rewards_title", rewards_title);
     }
-    if let Some(img_card) = &body.img_card {
+    if let Some(img_card) = &img_card {
         update_doc.insert("img_card", img_card);
     }
     if let Some(title_card) = &body.title_card {
@@ -110,6 +132,13 @@ pub async fn handler(
         update_doc.insert("banner", to_bson(&banner).unwrap());
     }    
 
+    if let Some(prerequisites) = &body.prerequisites {
+        if let Err(e) = validate_prerequisites(&state.db, body.id as u32, prerequisites).await {
+            return get_error(e);
+        }
+        update_doc.insert("prerequisites", to_bson(prerequisites).unwrap());
+    }
+
     // update quest query
     let update = doc! {
         "$set": update_doc.clone()
@@ -124,7 +153,7 @@ pub async fn handler(
     let nft_uri_filter = doc! { "id": &body.id };
 
     let mut nft_update_doc = Document::new();
-    if let Some(rewards_img) = &body.rewards_img {
+    if let Some(rewards_img) = &rewards_img {
         nft_update_doc.insert("image", rewards_img);
     }
     if let Some(rewards_title) = &body.rewards_title {
@@ -25,6 +25,7 @@ pub struct CreateBoostQuery {
     hidden: bool,
     expiry: i64,
     img_url: String,
+    max_risk_score: Option<f64>,
 }
 
 #[route(post, "/admin/quest_boost/create_boost", auth_middleware)]
@@ -58,6 +59,8 @@ pub async fn handler(
         hidden: body.hidden.clone(),
         img_url: body.img_url.clone(),
         winner: None,
+        status: None,
+        max_risk_score: body.max_risk_score,
     };
 
     // insert document to boost collection
@@ -0,0 +1,104 @@
+use crate::common::boost_lifecycle::{get_boost_progress_pipeline, get_claimed_winners};
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct GetUnclaimedParams {
+    boost_id: Option<i32>,
+}
+
+#[route(get, "/admin/boosts/get_unclaimed", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Query(params): Query<GetUnclaimedParams>,
+) -> impl IntoResponse {
+    let mut filter = doc! { "winner": { "$ne": null } };
+    if let Some(boost_id) = params.boost_id {
+        filter.insert("id", boost_id);
+    }
+
+    let mut pipeline = get_boost_progress_pipeline(filter);
+    // only super_user can see the boosts of every issuer
+    if sub != "super_user" {
+        pipeline.insert(
+            1,
+            doc! {
+                "$lookup": doc! {
+                    "from": "quests",
+                    "localField": "quests",
+                    "foreignField": "id",
+                    "as": "issuer_quests"
+                }
+            },
+        );
+        pipeline.insert(2, doc! { "$match": { "issuer_quests.issuer": &sub } });
+    }
+
+    let collection = state.db.collection::<Document>("boosts");
+    let mut cursor = match collection.aggregate(pipeline, None).await {
+        Ok(cursor) => cursor,
+        Err(e) => return get_error(format!("Error querying boosts: {}", e)),
+    };
+
+    let mut boosts = Vec::new();
+    let mut totals: HashMap<String, u128> = HashMap::new();
+    while let Ok(Some(boost)) = cursor.try_next().await {
+        let (Ok(boost_id), Ok(token), Ok(amount), Ok(decimals), Ok(num_of_winners)) = (
+            boost.get_i32("id"),
+            boost.get_str("token"),
+            boost.get_i32("amount"),
+            boost.get_i32("token_decimals"),
+            boost.get_i32("num_of_winners"),
+        ) else {
+            continue;
+        };
+        if num_of_winners <= 0 {
+            continue;
+        }
+
+        let (claimed, unclaimed) = get_claimed_winners(&boost);
+        let amount_per_winner =
+            amount as u128 * 10u128.pow(decimals as u32) / num_of_winners as u128;
+        let unclaimed_amount = amount_per_winner * unclaimed.len() as u128;
+        *totals.entry(token.to_string()).or_insert(0) += unclaimed_amount;
+
+        boosts.push(json!({
+            "boost_id": boost_id,
+            "name": boost.get_str("name").unwrap_or_default(),
+            "status": boost.get_str("status").ok(),
+            "token": token,
+            "token_decimals": decimals,
+            "amount_per_winner": amount_per_winner.to_string(),
+            "claimed": claimed,
+            "unclaimed": unclaimed,
+            "unclaimed_amount": unclaimed_amount.to_string(),
+            "extra_winners_left": boost.get_array("extra_winners").map(|w| w.len()).unwrap_or(0),
+        }));
+    }
+
+    let totals: HashMap<String, String> = totals
+        .into_iter()
+        .map(|(token, amount)| (token, amount.to_string()))
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!({ "boosts": boosts, "totals": totals })),
+    )
+        .into_response()
+}
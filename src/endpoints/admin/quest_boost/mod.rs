@@ -1,3 +1,5 @@
 pub mod create_boost;
 pub mod get_boost_winners;
+pub mod get_unclaimed;
+pub mod redraw_boost;
 pub mod update_boost;
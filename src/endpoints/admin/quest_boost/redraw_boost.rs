@@ -0,0 +1,138 @@
+use crate::common::boost_lifecycle::{get_boost_progress_pipeline, get_claimed_winners};
+use crate::common::webhooks::emit_webhook;
+use crate::config::SignatureScheme;
+use crate::middleware::auth::auth_middleware;
+use crate::models::{AppState, QuestDocument};
+use crate::utils::{get_error, verify_quest_auth};
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document};
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; RedrawBoostQuery {
+    boost_id: i32,
+});
+
+#[route(post, "/admin/quest_boost/redraw", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Json(body): Json<RedrawBoostQuery>,
+) -> impl IntoResponse {
+    let collection = state.db.collection::<Document>("boosts");
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+
+    let pipeline = get_boost_progress_pipeline(doc! { "id": body.boost_id });
+    let boost = match collection.aggregate(pipeline, None).await {
+        Ok(mut cursor) => match cursor.try_next().await {
+            Ok(Some(boost)) => boost,
+            _ => return get_error(format!("Boost with id {} not found", body.boost_id)),
+        },
+        Err(e) => return get_error(format!("Error querying boost: {}", e)),
+    };
+
+    let quest_id = match boost.get_array("quests").ok().and_then(|q| q.first()) {
+        Some(Bson::Int32(id)) => *id as i64,
+        Some(Bson::Int64(id)) => *id,
+        _ => return get_error("boost has no quest".to_string()),
+    };
+    if !verify_quest_auth(sub, &quests_collection, &quest_id).await {
+        return get_error("Error updating boost".to_string());
+    }
+
+    // replacements can only be drawn once the claim window of current winners is over
+    if boost.get_str("status").ok() != Some("expired") {
+        return get_error("Boost claim window is not over yet".to_string());
+    }
+    // legacy signatures never expire, a replaced winner could still claim with one
+    if state.conf.quest_boost.signature.scheme != SignatureScheme::Snip12 {
+        return get_error(
+            "Winners can only be redrawn for boosts claimed with SNIP-12".to_string(),
+        );
+    }
+    let claim_deadline = boost.get("claim_deadline").cloned().unwrap_or(Bson::Null);
+    let last_deadline = match claim_deadline {
+        Bson::Int32(deadline) => deadline as i64,
+        Bson::Int64(deadline) => deadline,
+        _ => 0,
+    };
+    if last_deadline >= Utc::now().timestamp() {
+        return get_error("Claim signatures issued to the winners are still valid".to_string());
+    }
+
+    let (claimed, unclaimed) = get_claimed_winners(&boost);
+    let mut pool: Vec<String> = boost
+        .get_array("extra_winners")
+        .map(|extra| {
+            extra
+                .iter()
+                .filter_map(|winner| winner.as_str().map(|w| w.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if pool.is_empty() {
+        return get_error("No replacement winners left for this boost".to_string());
+    }
+
+    pool.shuffle(&mut rand::thread_rng());
+    let replaced_count = std::cmp::min(unclaimed.len(), pool.len());
+    let replacements: Vec<String> = pool.drain(..replaced_count).collect();
+    let replaced: Vec<String> = unclaimed[..replaced_count].to_vec();
+
+    let mut winners = claimed;
+    winners.extend(replacements.iter().cloned());
+    winners.extend(unclaimed[replaced_count..].iter().cloned());
+
+    // the previous draw is part of the filter so that concurrent redraws can't share a pool
+    let filter = doc! {
+        "id": body.boost_id,
+        "winner": boost.get("winner").cloned().unwrap_or(Bson::Null),
+        "extra_winners": boost.get("extra_winners").cloned().unwrap_or(Bson::Null),
+        "claim_deadline": claim_deadline,
+    };
+    let now = Utc::now().timestamp_millis();
+    let update = doc! {
+        "$set": {
+            "winner": winners.clone(),
+            "extra_winners": pool,
+            "drawn_at": now,
+        },
+        "$push": { "replaced_winners": { "$each": replaced.clone() } },
+    };
+    match collection.update_one(filter, update, None).await {
+        Ok(result) if result.matched_count == 0 => {
+            return get_error("Boost was updated meanwhile, retry the redraw".to_string())
+        }
+        Ok(_) => {}
+        Err(e) => return get_error(format!("Error updating boost: {}", e)),
+    }
+
+    emit_webhook(
+        &state.db,
+        &state.http,
+        &state.conf.webhooks,
+        &state.logger,
+        "boost.redrawn",
+        json!({
+            "boost_id": body.boost_id,
+            "replaced": replaced,
+            "replacements": replacements,
+        }),
+    )
+    .await;
+
+    (
+        StatusCode::OK,
+        Json(json!({ "winners": winners, "replaced": replaced, "replacements": replacements })),
+    )
+        .into_response()
+}
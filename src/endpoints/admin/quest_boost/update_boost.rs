@@ -24,6 +24,7 @@ pub_struct!(Deserialize; UpdateBoostQuery {
     name: Option<String>,
     img_url: Option<String>,
     hidden: Option<bool>,
+    max_risk_score: Option<f64>,
 });
 
 #[route(post, "/admin/quest_boost/update_boost", auth_middleware)]
@@ -81,6 +82,9 @@ pub async fn handler(
     if let Some(hidden) = &body.hidden {
         update_doc.insert("hidden", hidden);
     }
+    if let Some(max_risk_score) = &body.max_risk_score {
+        update_doc.insert("max_risk_score", max_risk_score);
+    }
 
     // update boost
     let update = doc! {
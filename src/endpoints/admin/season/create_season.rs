@@ -0,0 +1,66 @@
+use crate::common::seasons::{recompute_season_experience, SEASONS_COLLECTION};
+use crate::middleware::auth::auth_middleware;
+use crate::models::{AppState, SeasonDocument};
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use mongodb::options::FindOneOptions;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; CreateSeasonQuery {
+    name: String,
+    start_time: i64,
+    end_time: i64,
+    categories: Option<Vec<String>>,
+});
+
+#[route(post, "/admin/season/create", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Json(body): Json<CreateSeasonQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+    if body.start_time >= body.end_time {
+        return get_error("start_time must be before end_time".to_string());
+    }
+
+    let collection = state.db.collection::<SeasonDocument>(SEASONS_COLLECTION);
+    let options = FindOneOptions::builder().sort(doc! { "id": -1 }).build();
+    let next_id = match collection.find_one(None, options).await {
+        Ok(Some(season)) => season.id + 1,
+        Ok(None) => 1,
+        Err(e) => return get_error(format!("Error querying seasons: {}", e)),
+    };
+
+    let season = SeasonDocument {
+        id: next_id,
+        name: body.name,
+        start_time: body.start_time,
+        end_time: body.end_time,
+        categories: body.categories.unwrap_or_default(),
+        frozen_at: None,
+        total_users: None,
+    };
+    if let Err(e) = collection.insert_one(&season, None).await {
+        return get_error(format!("Error creating season: {}", e));
+    }
+    // a season starting in the past counts the experience already earned
+    match recompute_season_experience(&state.db, &season).await {
+        Ok(users) => (
+            StatusCode::OK,
+            Json(json!({"message": "Season created successfully", "id": next_id, "users": users})),
+        )
+            .into_response(),
+        Err(e) => get_error(format!("Error computing season experience: {}", e)),
+    }
+}
@@ -0,0 +1,2 @@
+pub mod create_season;
+pub mod update_season;
@@ -0,0 +1,93 @@
+use crate::common::seasons::{recompute_season_experience, SEASONS_COLLECTION};
+use crate::middleware::auth::auth_middleware;
+use crate::models::{AppState, SeasonDocument};
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; UpdateSeasonQuery {
+    id: u32,
+    name: Option<String>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    categories: Option<Vec<String>>,
+});
+
+#[route(post, "/admin/season/update", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Json(body): Json<UpdateSeasonQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    let collection = state.db.collection::<SeasonDocument>(SEASONS_COLLECTION);
+    let season = match collection.find_one(doc! { "id": body.id }, None).await {
+        Ok(Some(season)) => season,
+        Ok(None) => return get_error("Season does not exist".to_string()),
+        Err(e) => return get_error(format!("Error querying season: {}", e)),
+    };
+    // final standings were computed with the season settings, they can't change anymore
+    if season.frozen_at.is_some() {
+        return get_error("Season is frozen and can't be updated".to_string());
+    }
+
+    let start_time = body.start_time.unwrap_or(season.start_time);
+    let end_time = body.end_time.unwrap_or(season.end_time);
+    if start_time >= end_time {
+        return get_error("start_time must be before end_time".to_string());
+    }
+
+    let categories = body.categories.clone().unwrap_or(season.categories);
+    let mut update = doc! {
+        "start_time": start_time,
+        "end_time": end_time,
+    };
+    if let Some(name) = &body.name {
+        update.insert("name", name);
+    }
+    if body.categories.is_some() {
+        update.insert("categories", &categories);
+    }
+
+    match collection
+        .update_one(
+            doc! { "id": body.id, "frozen_at": null },
+            doc! { "$set": update },
+            None,
+        )
+        .await
+    {
+        Ok(result) if result.matched_count == 0 => {
+            return get_error("Season is frozen and can't be updated".to_string())
+        }
+        Ok(_) => {}
+        Err(e) => return get_error(format!("Error updating season: {}", e)),
+    }
+
+    // the dates or categories may have changed, the season ledger is rebuilt from the xp ledger
+    let season = SeasonDocument {
+        start_time,
+        end_time,
+        categories,
+        ..season
+    };
+    match recompute_season_experience(&state.db, &season).await {
+        Ok(users) => (
+            StatusCode::OK,
+            Json(json!({"message": "updated successfully", "users": users})),
+        )
+            .into_response(),
+        Err(e) => get_error(format!("Error computing season experience: {}", e)),
+    }
+}
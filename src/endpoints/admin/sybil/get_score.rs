@@ -0,0 +1,55 @@
+use crate::common::sybil::{update_sybil_score, SYBIL_SCORES_COLLECTION};
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOneOptions;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetScoreQuery {
+    addr: FieldElement,
+    // computes the score again instead of returning the stored one
+    refresh: Option<bool>,
+});
+
+#[route(get, "/admin/sybil/get_score", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Query(query): Query<GetScoreQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    let collection = state.db.collection::<Document>(SYBIL_SCORES_COLLECTION);
+    let filter = doc! { "address": query.addr.to_string() };
+    let options = FindOneOptions::builder()
+        .projection(doc! { "_id": 0 })
+        .build();
+    let stored = match collection.find_one(filter.clone(), options.clone()).await {
+        Ok(stored) => stored,
+        Err(e) => return get_error(format!("Error querying score: {}", e)),
+    };
+    if stored.is_none() || query.refresh.unwrap_or(false) {
+        if let Err(e) = update_sybil_score(&state, &query.addr).await {
+            return get_error(e);
+        }
+    } else if let Some(score) = stored {
+        return (StatusCode::OK, Json(score)).into_response();
+    }
+
+    match collection.find_one(filter, options).await {
+        Ok(Some(score)) => (StatusCode::OK, Json(score)).into_response(),
+        Ok(None) => get_error("Score not found".to_string()),
+        Err(e) => get_error(format!("Error querying score: {}", e)),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::common::sybil::SYBIL_SCORES_COLLECTION;
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetScoresQuery {
+    min_score: Option<f64>,
+    limit: Option<i64>,
+});
+
+// riskiest addresses first
+#[route(get, "/admin/sybil/get_scores", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Query(query): Query<GetScoresQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    let filter = doc! { "score": { "$gte": query.min_score.unwrap_or(0.0) } };
+    let options = FindOptions::builder()
+        .sort(doc! { "score": -1, "updated_at": -1 })
+        .projection(doc! { "_id": 0 })
+        .limit(query.limit.unwrap_or(100).clamp(1, 1000))
+        .build();
+    let collection = state.db.collection::<Document>(SYBIL_SCORES_COLLECTION);
+    match collection.find(filter, options).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(scores) => (StatusCode::OK, Json(scores)).into_response(),
+            Err(e) => get_error(format!("Error reading scores: {}", e)),
+        },
+        Err(e) => get_error(format!("Error querying scores: {}", e)),
+    }
+}
@@ -0,0 +1,24 @@
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use std::sync::Arc;
+
+// latency, errors, retries, cache hits and breaker state of each partner API host since startup
+#[route(get, "/admin/upstreams/get_metrics", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    (StatusCode::OK, Json(state.http.get_metrics())).into_response()
+}
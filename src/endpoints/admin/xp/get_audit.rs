@@ -0,0 +1,50 @@
+use crate::common::xp_adjustments::XP_AUDIT_COLLECTION;
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetAuditQuery {
+    addr: Option<FieldElement>,
+    limit: Option<i64>,
+});
+
+#[route(get, "/admin/xp/get_audit", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Query(query): Query<GetAuditQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+
+    let filter = match query.addr {
+        Some(addr) => doc! { "address": addr.to_string() },
+        None => doc! {},
+    };
+    let options = FindOptions::builder()
+        .sort(doc! { "timestamp": -1 })
+        .projection(doc! { "_id": 0 })
+        .limit(query.limit.unwrap_or(100).clamp(1, 1000))
+        .build();
+    let collection = state.db.collection::<Document>(XP_AUDIT_COLLECTION);
+    match collection.find(filter, options).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(entries) => (StatusCode::OK, Json(entries)).into_response(),
+            Err(e) => get_error(format!("Error reading audit trail: {}", e)),
+        },
+        Err(e) => get_error(format!("Error querying audit trail: {}", e)),
+    }
+}
@@ -0,0 +1,5 @@
+pub mod get_audit;
+pub mod grant_bonus;
+pub mod revoke_achievement;
+pub mod revoke_quest;
+pub mod revoke_task;
@@ -0,0 +1,44 @@
+use crate::common::xp_adjustments::{apply_bulk_adjustment, parse_address_list, XpAdjustment};
+use crate::middleware::auth::auth_middleware;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Extension, State},
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; RevokeQuestQuery {
+    quest_id: u32,
+    // addresses as a JSON list and/or a CSV with the address in the first column
+    addresses: Option<Vec<String>>,
+    csv: Option<String>,
+    reason: String,
+});
+
+// Deletes every task completion of the quest and revokes its experience
+#[route(post, "/admin/xp/revoke_quest", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(sub): Extension<String>,
+    Json(body): Json<RevokeQuestQuery>,
+) -> impl IntoResponse {
+    if sub != "super_user" {
+        return get_error("Unauthorized".to_string());
+    }
+    let addresses = match parse_address_list(body.addresses, body.csv) {
+        Ok(addresses) => addresses,
+        Err(e) => return get_error(e),
+    };
+    apply_bulk_adjustment(
+        &state.db,
+        &state.logger,
+        addresses,
+        XpAdjustment::RevokeQuest(body.quest_id),
+        &body.reason,
+        &sub,
+    )
+    .await
+}
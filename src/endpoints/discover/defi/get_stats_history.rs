@@ -0,0 +1,60 @@
+use crate::{
+    common::defi_stats::{get_defi_stats_history, DefiStatsKind},
+    models::AppState,
+    utils::get_error,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+const MAX_POINTS: i64 = 5000;
+
+#[derive(Deserialize)]
+pub struct GetStatsHistoryQuery {
+    // pairs, lend, derivatives or alt_protocols
+    kind: String,
+    // protocol, as returned by the stats endpoint of kind
+    key: String,
+    // pair or strategy of the protocol, for pairs, lend and alt_protocols
+    sub_key: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[route(get, "/discover/defi/get_stats_history")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GetStatsHistoryQuery>,
+) -> impl IntoResponse {
+    let Some(kind) = DefiStatsKind::parse(&query.kind) else {
+        return get_error(format!("Unknown stats kind {}", query.kind));
+    };
+    let to = query.to.unwrap_or_else(|| Utc::now().timestamp_millis());
+    let from = query.from.unwrap_or(0);
+    let limit = query.limit.unwrap_or(500).clamp(1, MAX_POINTS);
+
+    let mut path = vec![query.key.as_str()];
+    path.extend(query.sub_key.as_deref());
+
+    match get_defi_stats_history(&state.db, kind, &path, from, to, limit).await {
+        Ok(series) => (
+            StatusCode::OK,
+            Json(json!({
+                "kind": kind.name(),
+                "key": query.key,
+                "sub_key": query.sub_key,
+                "series": series
+            })),
+        )
+            .into_response(),
+        Err(e) => get_error(format!("Error reading stats history: {}", e)),
+    }
+}
@@ -2,3 +2,4 @@ pub mod get_alt_protocol_stats;
 pub mod get_derivatives_stats;
 pub mod get_lend_stats;
 pub mod get_pair_stats;
+pub mod get_stats_history;
@@ -1,4 +1,4 @@
-use crate::{models::AppState, utils::get_error};
+use crate::{common::nft_claims::get_claimed_quests, models::AppState, utils::get_error};
 use axum::{
     extract::{Query, State},
     response::IntoResponse,
@@ -17,6 +17,15 @@ use std::sync::Arc;
 
 pub struct GetCompletedQuestsQuery {
     addr: FieldElement,
+    // objects with a claimed flag instead of the quest ids
+    with_claims: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct CompletedQuest {
+    pub quest_id: u32,
+    // whether an NFT of the quest was minted by the address
+    pub claimed: bool,
 }
 
 #[route(get, "/get_completed_quests")]
@@ -84,6 +93,20 @@ pub async fn handler(
             while let Some(result) = cursor.try_next().await.unwrap() {
                 quests.push(result.get("quest_id").unwrap().as_i64().unwrap() as u32);
             }
+            if !query.with_claims.unwrap_or(false) {
+                return (StatusCode::OK, Json(quests)).into_response();
+            }
+            let claimed = match get_claimed_quests(&state.db, &query.addr, Some(&quests)).await {
+                Ok(claimed) => claimed,
+                Err(_) => return get_error("Error querying claims".to_string()),
+            };
+            let quests: Vec<CompletedQuest> = quests
+                .into_iter()
+                .map(|quest_id| CompletedQuest {
+                    quest_id,
+                    claimed: claimed.contains(&quest_id),
+                })
+                .collect();
             (StatusCode::OK, Json(quests)).into_response()
         }
         Err(_) => get_error("Error querying quests".to_string()),
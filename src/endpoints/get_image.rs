@@ -0,0 +1,31 @@
+use crate::common::images::is_image_name;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_auto_routes::route;
+use std::sync::Arc;
+
+// serves the uploaded images, their content never changes as they are named after it
+#[route(get, "/images/*name")]
+pub async fn handler(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response {
+    if !is_image_name(&name) {
+        return (StatusCode::NOT_FOUND, "Image not found").into_response();
+    }
+    match state.images.get(&name).await {
+        Ok(Some(data)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "image/webp"),
+                (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+            ],
+            data,
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Image not found").into_response(),
+        Err(e) => get_error(e),
+    }
+}
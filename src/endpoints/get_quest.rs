@@ -1,5 +1,6 @@
 use crate::{
-    models::{AppState, QuestDocument},
+    common::{nft_claims::get_claimed_quests, quest_prerequisites::get_missing_prerequisites},
+    models::{AppState, QuestDocument, QuestPrerequisite},
     utils::get_error,
 };
 use axum::{
@@ -11,12 +12,28 @@ use axum_auto_routes::route;
 use futures::StreamExt;
 use mongodb::bson::doc;
 use mongodb::bson::from_document;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use starknet::core::types::FieldElement;
 use std::sync::Arc;
 
 #[derive(Deserialize)]
 pub struct GetQuestsQuery {
     id: u32,
+    addr: Option<FieldElement>,
+}
+
+#[derive(Serialize)]
+pub struct QuestResponse {
+    #[serde(flatten)]
+    quest: QuestDocument,
+    // only set when an address is provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locked: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missing_prerequisites: Option<Vec<QuestPrerequisite>>,
+    // whether an NFT of the quest was minted by the address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    claimed: Option<bool>,
 }
 
 #[route(get, "/get_quest")]
@@ -25,7 +42,6 @@ pub async fn handler(
     Query(query): Query<GetQuestsQuery>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestDocument>("quests");
-    let current_time = chrono::Utc::now().timestamp_millis();
 
     let pipeline = [
         doc! {
@@ -36,28 +52,8 @@ pub async fn handler(
         },
         doc! {
             "$addFields": {
-                "expired": {
-                    "$cond": [
-                        {
-                            "$and": [
-                               doc! {
-                                    "$gte": [
-                                        "$expiry",
-                                        0
-                                    ]
-                                },
-                                doc! {
-                                    "$lt": [
-                                        "$expiry",
-                                        current_time
-                                    ]
-                                }
-                            ]
-                        },
-                        true,
-                        false
-                    ]
-                }
+                // set by the quests_expiry job and when admins change the expiry
+                "expired": { "$eq": ["$expired", true] }
             }
         },
     ];
@@ -71,7 +67,46 @@ pub async fn handler(
                             if let Some(expiry) = &quest.expiry {
                                 quest.expiry_timestamp = Some(expiry.to_string());
                             }
-                            return (StatusCode::OK, Json(quest)).into_response();
+                            let missing_prerequisites = match &query.addr {
+                                Some(addr) => match get_missing_prerequisites(
+                                    &state.db,
+                                    addr,
+                                    quest.prerequisites.as_deref().unwrap_or_default(),
+                                )
+                                .await
+                                {
+                                    Ok(missing) => Some(missing),
+                                    Err(e) => {
+                                        return get_error(format!(
+                                            "Error checking prerequisites: {}",
+                                            e
+                                        ))
+                                    }
+                                },
+                                None => None,
+                            };
+                            let claimed = match &query.addr {
+                                Some(addr) => {
+                                    match get_claimed_quests(&state.db, addr, Some(&[quest.id]))
+                                        .await
+                                    {
+                                        Ok(claimed) => Some(!claimed.is_empty()),
+                                        Err(_) => {
+                                            return get_error("Error querying claims".to_string())
+                                        }
+                                    }
+                                }
+                                None => None,
+                            };
+                            let response = QuestResponse {
+                                quest,
+                                locked: missing_prerequisites
+                                    .as_ref()
+                                    .map(|missing| !missing.is_empty()),
+                                missing_prerequisites,
+                                claimed,
+                            };
+                            return (StatusCode::OK, Json(response)).into_response();
                         }
                     }
                     _ => continue,
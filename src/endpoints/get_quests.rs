@@ -36,28 +36,8 @@ pub async fn handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
         },
         doc! {
             "$addFields": {
-                "expired": {
-                    "$cond": [
-                        {
-                            "$and": [
-                                doc! {
-                                    "$gte": [
-                                        "$expiry",
-                                        0
-                                    ]
-                                },
-                                doc! {
-                                    "$lt": [
-                                        "$expiry",
-                                    current_time
-                                    ]
-                                }
-                            ]
-                        },
-                        true,
-                        false
-                    ]
-                }
+                // set by the quests_expiry job and when admins change the expiry
+                "expired": { "$eq": ["$expired", true] }
             }
         },
     ];
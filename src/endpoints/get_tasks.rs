@@ -1,4 +1,10 @@
-use crate::{models::AppState, utils::get_error};
+use crate::{
+    common::{
+        quest_eligibility::EligibilityError, quest_prerequisites::get_quest_missing_prerequisites,
+    },
+    models::AppState,
+    utils::get_error,
+};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -42,6 +48,14 @@ pub async fn handler(
     State(state): State<Arc<AppState>>,
     Query(query): Query<GetTasksQuery>,
 ) -> impl IntoResponse {
+    match get_quest_missing_prerequisites(&state.db, &query.addr, query.quest_id).await {
+        Ok(missing) if !missing.is_empty() => {
+            return EligibilityError::MissingPrerequisites(missing).into_response()
+        }
+        Ok(_) => {}
+        Err(e) => return get_error(format!("Error checking prerequisites: {}", e)),
+    }
+
     let pipeline = vec![
         doc! { "$match": { "quest_id": query.quest_id } },
         doc! {
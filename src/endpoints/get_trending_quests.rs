@@ -44,18 +44,8 @@ pub async fn handler(
         },
         doc! {
             "$addFields": {
-                "expired": {
-                    "$cond": [
-                        {
-                            "$and": [
-                                { "$gte": ["$expiry", 0] },
-                                { "$lt": ["$expiry", current_time] },
-                            ]
-                        },
-                        true,
-                        false
-                    ]
-                }
+                // set by the quests_expiry job and when admins change the expiry
+                "expired": { "$eq": ["$expired", true] }
             }
         },
         doc! {
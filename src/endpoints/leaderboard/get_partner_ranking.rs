@@ -0,0 +1,42 @@
+use crate::common::leaderboard::{get_scoped_leaderboard_source, LeaderboardScope, RankingMetric};
+use crate::endpoints::leaderboard::get_ranking::get_ranking_page;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetPartnerRankingQuery {
+    // issuer of the quests
+    partner: String,
+    addr: String,
+    page_size: i64,
+    shift: i64,
+    // "xp" (default) or "time"
+    sort_by: Option<String>,
+});
+
+#[route(get, "/leaderboard/get_partner_ranking")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GetPartnerRankingQuery>,
+) -> impl IntoResponse {
+    let metric = match RankingMetric::parse(query.sort_by.as_deref().unwrap_or("xp")) {
+        Ok(metric) => metric,
+        Err(e) => return get_error(e),
+    };
+    let (collection, source_pipeline) =
+        get_scoped_leaderboard_source(&state.db, &LeaderboardScope::Issuer(query.partner.clone()));
+    get_ranking_page(
+        &collection,
+        source_pipeline,
+        metric,
+        &query.addr,
+        query.page_size,
+        query.shift,
+    )
+    .await
+}
@@ -40,7 +40,9 @@ use axum::{
 };
 use axum_auto_routes::route;
 
-use crate::utils::get_timestamp_from_days;
+use crate::common::leaderboard::{
+    get_leaderboard_source, get_ranking_stages, parse_leaderboard_window, RankingMetric,
+};
 use axum::http::StatusCode;
 use axum::http::{header, Response};
 use chrono::Utc;
@@ -53,40 +55,12 @@ use std::sync::Arc;
 pub async fn get_user_rank(
     collection: &Collection<Document>,
     address: &String,
-    timestamp: &i64,
+    source_pipeline: &[Document],
+    metric: RankingMetric,
 ) -> Document {
-    let user_rank_pipeline = vec![
-        doc! {
-            "$match": doc! {
-                "timestamp": doc! {
-                    "$gte": timestamp,
-                }
-            }
-        },
-        doc! {
-            "$sort": doc! {
-                "experience": -1,
-                "timestamp": 1,
-                "_id": 1
-            }
-        },
-        doc! {
-            "$addFields": doc! {
-                "tempSortField": 1
-            }
-        },
-        doc! {
-            "$setWindowFields": doc! {
-                "sortBy": doc! {
-                    "tempSortField": -1
-                },
-                "output": doc! {
-                    "rank": doc! {
-                        "$documentNumber": doc! {}
-                    }
-                }
-            }
-        },
+    let mut user_rank_pipeline = source_pipeline.to_vec();
+    user_rank_pipeline.extend(get_ranking_stages(metric));
+    user_rank_pipeline.extend(vec![
         doc! {
             "$facet": doc! {
                 "total_users": [
@@ -131,7 +105,7 @@ pub async fn get_user_rank(
                 "rank": "$rank.rank"
             }
         },
-    ];
+    ]);
 
     // add allow disk use to view options
     let view_options = mongodb::options::AggregateOptions::builder()
@@ -232,7 +206,16 @@ pub struct GetCompletedQuestsQuery {
     */
     shift: i64,
 
+    /*
+    all, week, month, calendar_week, calendar_month or custom
+    */
     duration: String,
+
+    /*
+    bounds (timestamps in ms) of custom ranges
+    */
+    start: Option<i64>,
+    end: Option<i64>,
 }
 
 #[route(get, "/leaderboard/get_ranking")]
@@ -240,25 +223,39 @@ pub async fn handler(
     State(state): State<Arc<AppState>>,
     Query(query): Query<GetCompletedQuestsQuery>,
 ) -> impl IntoResponse {
-    // check value of duration and set time_gap accordingly using match and respective timestamp
-    let time_gap = match query.duration.as_str() {
-        "week" => get_timestamp_from_days(7),
-        "month" => get_timestamp_from_days(30),
-        "all" => 0,
-        _ => {
-            return get_error("Invalid duration".to_string());
-        }
+    // get the time window matching the duration and the collection to rank
+    let window = match parse_leaderboard_window(&query.duration, query.start, query.end, Utc::now())
+    {
+        Ok(window) => window,
+        Err(e) => return get_error(e),
     };
-    // get collection
-    let users_collection = state.db.collection::<Document>("leaderboard_table");
+    let (users_collection, source_pipeline) = get_leaderboard_source(&state.db, &window);
+
+    get_ranking_page(
+        &users_collection,
+        source_pipeline,
+        RankingMetric::Xp,
+        &query.addr,
+        query.page_size,
+        query.shift,
+    )
+    .await
+}
 
-    // get params from query
-    let address = query.addr.to_string();
-    let page_size = query.page_size;
-    let shift = query.shift;
+// Returns the page of the ranking centered on address, moved by shift pages. Shared by every
+// leaderboard built from a source pipeline.
+pub async fn get_ranking_page(
+    users_collection: &Collection<Document>,
+    source_pipeline: Vec<Document>,
+    metric: RankingMetric,
+    address: &str,
+    page_size: i64,
+    shift: i64,
+) -> axum::response::Response {
+    let address = address.to_string();
 
     // get user rank and total users
-    let stats = get_user_rank(&users_collection, &address, &time_gap).await;
+    let stats = get_user_rank(users_collection, &address, &source_pipeline, metric).await;
     let total_users = stats.get("total_users").unwrap().as_i32().unwrap() as i64;
     let user_rank = stats.get("user_rank").unwrap().as_i32().unwrap() as i64;
 
@@ -298,20 +295,13 @@ pub async fn handler(
         }
     }
 
-    let paginated_leaderboard_pipeline = [
-        doc! {
-            "$match": doc!{
-                "timestamp": doc!{
-                    "$gte": time_gap,
-                }
-            }
-        },
+    // scoped rankings often have fewer users than a page
+    let lower_range = lower_range.max(1);
+
+    let mut paginated_leaderboard_pipeline = source_pipeline;
+    paginated_leaderboard_pipeline.extend([
         doc! {
-            "$sort":doc! {
-                "experience":-1,
-                "timestamp":1,
-                "_id":1,
-            }
+            "$sort": metric.sort()
         },
         doc! {
             "$skip": lower_range-1
@@ -332,12 +322,14 @@ pub async fn handler(
                 "_id": 0,
                 "address": "$_id",
                 "xp": "$experience",
+                "quests": 1,
+                "completion_time": "$duration",
                 "achievements": doc!{
                     "$size": "$associatedAchievement"
                 }
             }
         },
-    ];
+    ]);
 
     match users_collection
         .aggregate(paginated_leaderboard_pipeline, None)
@@ -350,10 +342,7 @@ pub async fn handler(
                 ranking.push(result);
             }
             res.insert("ranking".to_string(), ranking);
-            res.insert(
-                "first_elt_position".to_string(),
-                if lower_range == 0 { 1 } else { lower_range },
-            );
+            res.insert("first_elt_position".to_string(), lower_range);
 
             // Set caching response
             let expires = Utc::now() + chrono::Duration::minutes(5);
@@ -0,0 +1,91 @@
+use crate::common::seasons::{
+    get_season_ranking_pipeline, SEASONS_COLLECTION, SEASON_EXP_COLLECTION,
+    SEASON_STANDINGS_COLLECTION,
+};
+use crate::{
+    models::{AppState, SeasonDocument},
+    utils::get_error,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: i64 = 100;
+
+pub_struct!(Deserialize; GetSeasonRankingQuery {
+    season_id: u32,
+    limit: Option<i64>,
+});
+
+#[route(get, "/leaderboard/get_season_ranking")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GetSeasonRankingQuery>,
+) -> impl IntoResponse {
+    let seasons_collection = state.db.collection::<SeasonDocument>(SEASONS_COLLECTION);
+    let season = match seasons_collection
+        .find_one(doc! { "id": query.season_id }, None)
+        .await
+    {
+        Ok(Some(season)) => season,
+        Ok(None) => return get_error("Season does not exist".to_string()),
+        Err(e) => return get_error(format!("Error querying season: {}", e)),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 1000);
+    let frozen = season.frozen_at.is_some();
+
+    // frozen seasons are served from their final standings, running ones are ranked live
+    let (collection, pipeline) = if frozen {
+        (
+            state.db.collection::<Document>(SEASON_STANDINGS_COLLECTION),
+            vec![
+                doc! { "$match": { "season_id": season.id } },
+                doc! { "$sort": { "rank": 1 } },
+                doc! { "$limit": limit },
+            ],
+        )
+    } else {
+        let mut pipeline = get_season_ranking_pipeline(season.id);
+        pipeline.extend(vec![
+            doc! { "$limit": limit },
+            doc! { "$addFields": { "address": "$_id" } },
+        ]);
+        (
+            state.db.collection::<Document>(SEASON_EXP_COLLECTION),
+            pipeline,
+        )
+    };
+    let mut pipeline = pipeline;
+    pipeline.push(doc! {
+        "$project": {
+            "_id": 0,
+            "address": 1,
+            "xp": "$experience",
+            "rank": 1
+        }
+    });
+
+    match collection.aggregate(pipeline, None).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(ranking) => (
+                StatusCode::OK,
+                Json(json!({
+                    "season": season,
+                    "frozen": frozen,
+                    "ranking": ranking,
+                })),
+            )
+                .into_response(),
+            Err(e) => get_error(format!("Error reading ranking: {}", e)),
+        },
+        Err(e) => get_error(format!("Error querying ranking: {}", e)),
+    }
+}
@@ -0,0 +1,30 @@
+use crate::common::seasons::SEASONS_COLLECTION;
+use crate::{
+    models::{AppState, SeasonDocument},
+    utils::get_error,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::options::FindOptions;
+use std::sync::Arc;
+
+#[route(get, "/leaderboard/get_seasons")]
+pub async fn handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let collection = state.db.collection::<SeasonDocument>(SEASONS_COLLECTION);
+    let options = FindOptions::builder()
+        .sort(doc! { "start_time": -1 })
+        .build();
+    match collection.find(None, options).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<SeasonDocument>>().await {
+            Ok(seasons) => (StatusCode::OK, Json(seasons)).into_response(),
+            Err(e) => get_error(format!("Error reading seasons: {}", e)),
+        },
+        Err(e) => get_error(format!("Error querying seasons: {}", e)),
+    }
+}
@@ -1,9 +1,8 @@
 /*
 this endpoint will return static data of leaderboard and position of user address
 Steps to get data over different time intervals :
-1) iterate over one week timestamps and add total points and get top 3 and get user position
-2) iterate over one month timestamps and add total points and get top 3 and get user position
-3) iterate over all timestamps and add total points and get top 3 and get user position
+1) sum the experience earned during the requested window (daily buckets) and get top 3 and get user position
+2) for "all", use the cumulative experience of leaderboard_table and get top 3 and get user position
 */
 
 use crate::{models::AppState, utils::get_error};
@@ -14,13 +13,13 @@ use axum::{
 };
 use axum_auto_routes::route;
 
-use crate::utils::get_timestamp_from_days;
+use crate::common::leaderboard::{get_leaderboard_source, parse_leaderboard_window};
 use axum::http::header;
 use axum::http::StatusCode;
 use axum::response::Response;
 use chrono::Utc;
 use futures::TryStreamExt;
-use mongodb::bson::{doc, Document};
+use mongodb::bson::doc;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -31,7 +30,16 @@ pub struct GetLeaderboardInfoQuery {
     */
     addr: String,
 
+    /*
+    all, week, month, calendar_week, calendar_month or custom
+    */
     duration: String,
+
+    /*
+    bounds (timestamps in ms) of custom ranges
+    */
+    start: Option<i64>,
+    end: Option<i64>,
 }
 
 #[route(get, "/leaderboard/get_static_info")]
@@ -40,19 +48,16 @@ pub async fn handler(
     Query(query): Query<GetLeaderboardInfoQuery>,
 ) -> impl IntoResponse {
     let addr: String = query.addr.to_string();
-    let collection = state.db.collection::<Document>("leaderboard_table");
 
-    // check value of duration and set time_gap accordingly using match and respective timestamp
-    let time_gap = match query.duration.as_str() {
-        "week" => get_timestamp_from_days(7),
-        "month" => get_timestamp_from_days(30),
-        "all" => 0,
-        _ => {
-            return get_error("Invalid duration".to_string());
-        }
+    // get the time window matching the duration and the collection to rank
+    let window = match parse_leaderboard_window(&query.duration, query.start, query.end, Utc::now())
+    {
+        Ok(window) => window,
+        Err(e) => return get_error(e),
     };
+    let (collection, mut leaderboard_pipeline) = get_leaderboard_source(&state.db, &window);
 
-    let leaderboard_pipeline = vec![
+    leaderboard_pipeline.extend(vec![
         doc! {
             "$sort": doc! {
                 "experience": -1,
@@ -60,13 +65,6 @@ pub async fn handler(
                 "_id": 1
             }
         },
-        doc! {
-            "$match": doc! {
-                "timestamp": doc! {
-                    "$gte": time_gap,
-                }
-            }
-        },
         doc! {
             "$facet": doc! {
                 "best_users": [
@@ -149,7 +147,7 @@ pub async fn handler(
                 }
             }
         },
-    ];
+    ]);
 
     return match collection.aggregate(leaderboard_pipeline, None).await {
         Ok(mut cursor) => {
@@ -0,0 +1,59 @@
+use crate::common::seasons::{SEASONS_COLLECTION, SEASON_STANDINGS_COLLECTION};
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetUserSeasonsQuery {
+    addr: FieldElement,
+});
+
+// final standings of a user in every frozen season
+#[route(get, "/leaderboard/get_user_seasons")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GetUserSeasonsQuery>,
+) -> impl IntoResponse {
+    let pipeline = vec![
+        doc! { "$match": { "address": query.addr.to_string() } },
+        doc! {
+            "$lookup": {
+                "from": SEASONS_COLLECTION,
+                "localField": "season_id",
+                "foreignField": "id",
+                "as": "season"
+            }
+        },
+        doc! { "$unwind": "$season" },
+        doc! { "$sort": { "season.end_time": -1 } },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "season_id": 1,
+                "name": "$season.name",
+                "start_time": "$season.start_time",
+                "end_time": "$season.end_time",
+                "rank": 1,
+                "xp": "$experience",
+                "total_users": "$season.total_users"
+            }
+        },
+    ];
+
+    let collection = state.db.collection::<Document>(SEASON_STANDINGS_COLLECTION);
+    match collection.aggregate(pipeline, None).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(seasons) => (StatusCode::OK, Json(seasons)).into_response(),
+            Err(e) => get_error(format!("Error reading seasons: {}", e)),
+        },
+        Err(e) => get_error(format!("Error querying seasons: {}", e)),
+    }
+}
@@ -1,2 +1,8 @@
+pub mod get_category_ranking;
+pub mod get_partner_ranking;
+pub mod get_quest_ranking;
 pub mod get_ranking;
+pub mod get_season_ranking;
+pub mod get_seasons;
 pub mod get_static_info;
+pub mod get_user_seasons;
@@ -1,4 +1,9 @@
-use crate::{models::AppState, utils::get_error};
+use crate::{
+    common::typed_data::{get_boost_claim, get_claim_expiry, get_claim_hash, get_signing},
+    config::SignatureScheme,
+    models::AppState,
+    utils::get_error,
+};
 use axum::{
     extract::{Query, State},
     response::IntoResponse,
@@ -12,7 +17,6 @@ use axum::http::StatusCode;
 use mongodb::bson::{doc, Bson, Document};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use starknet::core::crypto::ecdsa_sign;
 use starknet::core::{crypto::pedersen_hash, types::FieldElement};
 use std::sync::Arc;
 
@@ -59,24 +63,57 @@ pub async fn handler(
         ));
     }
 
-    let hashed = pedersen_hash(
-        &FieldElement::from(boost_id),
-        &pedersen_hash(
-            &FieldElement::from(modified_amount),
-            &pedersen_hash(
-                &FieldElement::from(0 as u32),
+    let token = FieldElement::from_str(token).unwrap();
+    let settings = &state.conf.quest_boost.signature;
+    let (hashed, deadline, nonce) = match settings.scheme {
+        SignatureScheme::Legacy => {
+            let hashed = pedersen_hash(
+                &FieldElement::from(boost_id),
                 &pedersen_hash(
-                    &FieldElement::from_str(token).unwrap(),
-                    &FieldElement::from_str(&*address).unwrap(),
+                    &FieldElement::from(modified_amount),
+                    &pedersen_hash(
+                        &FieldElement::from(0 as u32),
+                        &pedersen_hash(&token, &query.addr),
+                    ),
                 ),
-            ),
-        ),
-    );
+            );
+            (hashed, None, None)
+        }
+        SignatureScheme::Snip12 => {
+            let signing = match get_signing(&state.conf.signing) {
+                Ok(signing) => signing,
+                Err(e) => return get_error(format!("Error while generating signature: {}", e)),
+            };
+            let (deadline, nonce) = get_claim_expiry(signing);
+            // redraws wait for the last issued signature to expire
+            if let Err(e) = collection
+                .update_one(
+                    doc! { "id": boost_id },
+                    doc! { "$max": { "claim_deadline": deadline } },
+                    None,
+                )
+                .await
+            {
+                return get_error(format!("Error while generating signature: {}", e));
+            }
+            let message = get_boost_claim(boost_id, modified_amount, token, deadline, nonce);
+            match get_claim_hash(signing, settings, query.addr, &message) {
+                Ok(hashed) => (hashed, Some(deadline), Some(nonce)),
+                Err(e) => return get_error(format!("Error while generating signature: {}", e)),
+            }
+        }
+    };
 
-    match ecdsa_sign(&state.conf.quest_boost.private_key, &hashed) {
+    match state.boost_signer.sign_hash(&hashed).await {
         Ok(signature) => (
             StatusCode::OK,
-            Json(json!({"address": address, "r": signature.r, "s": signature.s})),
+            Json(json!({
+                "address": address,
+                "r": signature.r,
+                "s": signature.s,
+                "deadline": deadline,
+                "nonce": nonce,
+            })),
         )
             .into_response(),
         Err(e) => get_error(format!("Error while generating signature: {}", e)),
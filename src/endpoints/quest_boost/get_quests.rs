@@ -24,7 +24,6 @@ pub async fn handler(
     Query(query): Query<GetQuestForBoostQuery>,
 ) -> impl IntoResponse {
     let boost_id = query.boost_id;
-    let current_time = chrono::Utc::now().timestamp_millis();
 
     let pipeline = vec![
         doc! {
@@ -84,18 +83,8 @@ pub async fn handler(
                         "as": "item",
                         "in": {
                             "$mergeObjects": ["$$item", doc! {
-                                "expired": {
-                                    "$cond": [
-                                        {
-                                            "$and": [
-                                                { "$gte": ["$$item.expiry", 0] },
-                                                { "$lt": ["$$item.expiry", current_time] },
-                                            ]
-                                        },
-                                        true,
-                                        false
-                                    ]
-                                }
+                                // set by the quests_expiry job and when admins change the expiry
+                                "expired": { "$eq": ["$$item.expired", true] }
                             }],
                         },
                     }
@@ -25,13 +25,16 @@ pub async fn handler(
     let api_url = "https://api.carmine.finance/api/v1/mainnet/price-protect-users";
 
     // Check if the addr is in the "data" field of the API response
-    let response = reqwest::get(api_url)
-        .await
-        .unwrap()
-        .json::<serde_json::Value>()
-        .await
-        .unwrap();
-    let data = response["data"].as_array().unwrap();
+    let response = match state.http.send(state.http.get(api_url)).await {
+        Ok(response) => match response.json::<serde_json::Value>() {
+            Ok(json) => json,
+            Err(e) => return get_error(format!("{}", e)),
+        },
+        Err(e) => return get_error(format!("{}", e)),
+    };
+    let Some(data) = response["data"].as_array() else {
+        return get_error("Invalid response from Carmine API".to_string());
+    };
     let mut found = false;
     let logger = &state.logger;
     for address in data {
@@ -51,7 +54,7 @@ pub async fn handler(
     if found {
         match state.upsert_completed_task(addr, task_id).await {
             Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-            Err(e) => get_error(format!("{}", e)),
+            Err(e) => e.into_response(),
         }
     } else {
         get_error("You didn't open price protect for at least 10$ on Carmine.".to_string())
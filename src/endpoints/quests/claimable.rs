@@ -1,8 +1,17 @@
-use crate::{models::AppState, utils::get_error};
+use crate::{
+    common::{
+        nft_claims::allocate_token_id, nft_rewards::is_level_unlocked,
+        quest_eligibility::check_quest_eligibility,
+    },
+    config::ClaimableAlias,
+    models::{AppState, NFTUri},
+    utils::get_error,
+};
 use axum::{
     extract::{Query, State},
-    response::IntoResponse,
-    Json,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
 };
 
 use crate::models::{Reward, RewardResponse};
@@ -10,10 +19,11 @@ use crate::utils::get_nft;
 use axum::http::StatusCode;
 use axum_auto_routes::route;
 use futures::TryStreamExt;
-use mongodb::bson::{doc, Document};
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::FindOptions;
 use serde::{Deserialize, Serialize};
 use starknet::core::types::FieldElement;
-use starknet::signers::{LocalWallet, SigningKey};
+use std::collections::HashSet;
 use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,162 +32,146 @@ pub struct HasCompletedQuestsQuery {
     quest_id: u32,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ClaimableAliasQuery {
+    addr: FieldElement,
+}
+
 #[route(get, "/quests/claimable")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
     Query(query): Query<HasCompletedQuestsQuery>,
 ) -> impl IntoResponse {
-    let address = query.addr.to_string();
-    let quest_id = query.quest_id;
-    let pipeline = vec![
-        doc! {
-            "$match": doc! {
-                "address": address
-            }
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "tasks",
-                "localField": "task_id",
-                "foreignField": "id",
-                "as": "associatedTask"
-            }
-        },
-        doc! {
-            "$unwind": "$associatedTask"
-        },
-        doc! {
-            "$project": doc! {
-                "_id": 0,
-                "address": 1,
-                "task_id": 1,
-                "quest_id": "$associatedTask.quest_id"
-            }
-        },
-        doc! {
-            "$group": doc! {
-                "_id": "$quest_id",
-                "done": doc! {
-                    "$sum": 1
-                }
-            }
-        },
-        doc! {
-            "$match": doc! {
-                "_id": quest_id
-            }
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "tasks",
-                "localField": "_id",
-                "foreignField": "quest_id",
-                "as": "tasks"
-            }
-        },
-        doc! {
-            "$project": doc! {
-                "_id": 1,
-                "tasks": "$tasks",
-                "result": doc! {
-                    "$cond": doc! {
-                        "if": doc! {
-                            "$eq": [
-                                doc! {
-                                    "$size": "$tasks"
-                                },
-                                "$done"
-                            ]
-                        },
-                        "then": true,
-                        "else": false
-                    }
-                }
-            }
-        },
-        doc! {
-            "$unwind": doc! {
-                "path": "$tasks"
-            }
-        },
-        doc! {
-            "$sort": doc! {
-                "tasks.id": -1
-            }
-        },
-        doc! {
-            "$limit": 1
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "nft_uri",
-                "localField": "_id",
-                "foreignField": "quest_id",
-                "as": "nft_uri"
-            }
-        },
-        doc! {
-            "$project": doc! {
-                "result": 1,
-                "_id": 0,
-                "last_task": "$tasks.id",
-                "nft_level": "$nft_uri.id"
-            }
-        },
-        doc! {
-            "$unwind": doc! {
-                "path": "$nft_level"
-            }
-        },
-    ];
-    let tasks_collection = state.db.collection::<Document>("completed_tasks");
-    match tasks_collection.aggregate(pipeline, None).await {
-        Ok(cursor) => {
-            let mut cursor = cursor;
-            let mut result = false;
-            let mut nft_level = 0;
-            let mut last_task = 0;
-            while let Some(doc) = cursor.try_next().await.unwrap() {
-                result = doc.get("result").unwrap().as_bool().unwrap();
-                nft_level = doc.get("nft_level").unwrap().as_i64().unwrap();
-                last_task = doc.get("last_task").unwrap().as_i32().unwrap();
-            }
+    get_claimable_rewards(&state, &query.addr, query.quest_id).await
+}
 
-            if !result {
-                return get_error("User hasn't completed all tasks".to_string());
-            }
+// Legacy per-quest claimable urls are still referenced by older quest documents, each configured
+// alias answers like /quests/claimable with the quest id of the alias
+pub fn get_alias_router(aliases: &[ClaimableAlias]) -> Router<Arc<AppState>> {
+    aliases.iter().fold(Router::new(), |router, alias| {
+        let quest_id = alias.quest_id;
+        router.route(
+            &alias.path,
+            get(
+                move |State(state): State<Arc<AppState>>,
+                      Query(query): Query<ClaimableAliasQuery>| async move {
+                    get_claimable_rewards(&state, &query.addr, quest_id).await
+                },
+            ),
+        )
+    })
+}
 
-            let signer = LocalWallet::from(SigningKey::from_secret_scalar(
-                state.conf.nft_contract.private_key,
-            ));
+fn as_u32(value: &Bson) -> Option<u32> {
+    match value {
+        Bson::Int32(v) => u32::try_from(*v).ok(),
+        Bson::Int64(v) => u32::try_from(*v).ok(),
+        _ => None,
+    }
+}
 
-            let mut rewards = vec![];
+// Signs every NFT level of the quest unlocked by the tasks completed by addr. A level is an
+// nft_uri document of the quest, signed for its claim task (the last task of the quest when unset).
+pub async fn get_claimable_rewards(
+    state: &AppState,
+    addr: &FieldElement,
+    quest_id: u32,
+) -> Response {
+    if let Err(e) = check_quest_eligibility(state, addr, quest_id).await {
+        return e.into_response();
+    }
 
-            let Ok((token_id, sig)) = get_nft(
-                quest_id,
-                last_task as u32,
-                &query.addr,
-                nft_level as u32,
-                &signer,
-            )
-            .await
-            else {
-                return get_error("Signature failed".into());
-            };
+    let mut quest_tasks: Vec<u32> = match state
+        .db
+        .collection::<Document>("tasks")
+        .distinct("id", doc! { "quest_id": quest_id }, None)
+        .await
+    {
+        Ok(ids) => ids.iter().filter_map(as_u32).collect(),
+        Err(_) => return get_error("Error querying status".to_string()),
+    };
+    quest_tasks.sort_unstable();
+    let Some(last_task) = quest_tasks.last().copied() else {
+        return get_error("User hasn't completed all tasks".to_string());
+    };
 
-            rewards.push(Reward {
-                task_id: last_task as u32,
-                nft_contract: state.conf.nft_contract.address.clone(),
-                token_id: token_id.to_string(),
-                sig: (sig.r, sig.s),
-            });
+    let completed: HashSet<u32> = match state
+        .db
+        .collection::<Document>("completed_tasks")
+        .distinct(
+            "task_id",
+            doc! { "address": addr.to_string(), "task_id": { "$in": &quest_tasks } },
+            None,
+        )
+        .await
+    {
+        Ok(ids) => ids.iter().filter_map(as_u32).collect(),
+        Err(_) => return get_error("Error querying status".to_string()),
+    };
 
-            if rewards.is_empty() {
-                get_error("No rewards found for this user".into())
-            } else {
-                (StatusCode::OK, Json(RewardResponse { rewards })).into_response()
-            }
+    let options = FindOptions::builder().sort(doc! { "id": 1 }).build();
+    let levels: Vec<NFTUri> = match state
+        .db
+        .collection::<NFTUri>("nft_uri")
+        .find(doc! { "quest_id": quest_id }, options)
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect().await {
+            Ok(levels) => levels,
+            Err(_) => return get_error("Error querying rewards".to_string()),
+        },
+        Err(_) => return get_error("Error querying rewards".to_string()),
+    };
+    if levels.is_empty() {
+        return get_error("No NFT found for this quest".to_string());
+    }
+
+    let mut rewards = vec![];
+    let mut claim_tasks = HashSet::new();
+    for level in levels {
+        if !is_level_unlocked(level.unlock.as_ref(), &quest_tasks, &completed) {
+            continue;
+        }
+        let task_id = level.claim_task_id.unwrap_or(last_task);
+        // the contract accepts a single NFT per task
+        if !claim_tasks.insert(task_id) {
+            state.logger.warning(format!(
+                "Quest {} has several NFT levels claimed with task {}",
+                quest_id, task_id
+            ));
+            continue;
         }
-        Err(_) => get_error("Error querying status".to_string()),
+        let token_id =
+            match allocate_token_id(&state.db, addr, quest_id, task_id, level.id as u32).await {
+                Ok(token_id) => token_id,
+                Err(e) => return get_error(e),
+            };
+        let Ok(claim) = get_nft(
+            &state.conf,
+            quest_id,
+            task_id,
+            addr,
+            token_id,
+            state.nft_signer.as_ref(),
+        )
+        .await
+        else {
+            return get_error("Signature failed".into());
+        };
+        rewards.push(Reward {
+            task_id,
+            level: level.id as u32,
+            nft_contract: state.conf.nft_contract.address.clone(),
+            token_id: claim.token_id.to_string(),
+            sig: (claim.sig.r, claim.sig.s),
+            deadline: claim.deadline,
+            nonce: claim.nonce,
+        });
+    }
+
+    if rewards.is_empty() {
+        get_error("User hasn't completed all tasks".to_string())
+    } else {
+        (StatusCode::OK, Json(RewardResponse { rewards })).into_response()
     }
 }
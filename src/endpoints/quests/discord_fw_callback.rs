@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::http_client::HttpClient;
 use crate::models::QuestTaskDocument;
 use crate::utils::CompletedTasksTrait;
 use crate::{
@@ -73,7 +74,7 @@ pub async fn handler(
         ),
         ("grant_type", &"authorization_code".to_string()),
     ];
-    let access_token = match exchange_authorization_code(params).await {
+    let access_token = match exchange_authorization_code(&state.http, params).await {
         Ok(token) => token,
         Err(e) => {
             return get_error_redirect(
@@ -84,15 +85,18 @@ pub async fn handler(
     };
 
     // Get user guild information
-    let client = reqwest::Client::new();
-    let response_result = client
-        .get("https://discord.com/api/users/@me/guilds")
-        .header(AUTHORIZATION, format!("Bearer {}", access_token))
-        .send()
+    let response_result = state
+        .http
+        .send(
+            state
+                .http
+                .get("https://discord.com/api/users/@me/guilds")
+                .header(AUTHORIZATION, format!("Bearer {}", access_token)),
+        )
         .await;
     let response: Vec<Guild> = match response_result {
         Ok(response) => {
-            let json_result = response.json().await;
+            let json_result = response.json();
             match json_result {
                 Ok(json) => json,
                 Err(e) => {
@@ -137,23 +141,22 @@ pub async fn handler(
 }
 
 async fn exchange_authorization_code(
+    http: &HttpClient,
     params: [(&str, &String); 5],
-) -> Result<String, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let res = client
-        .post("https://discord.com/api/oauth2/token")
-        .form(&params)
-        .send()
-        .await?;
-    let json: serde_json::Value = res.json().await?;
+) -> Result<String, String> {
+    let res = http
+        .send(
+            http.post("https://discord.com/api/oauth2/token")
+                .form(&params),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    let json: serde_json::Value = res.json().map_err(|e| e.to_string())?;
     match json["access_token"].as_str() {
         Some(s) => Ok(s.to_string()),
-        None => Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!(
-                "Failed to get 'access_token' from JSON response : {:?}",
-                json
-            ),
-        ))),
+        None => Err(format!(
+            "Failed to get 'access_token' from JSON response : {:?}",
+            json
+        )),
     }
 }
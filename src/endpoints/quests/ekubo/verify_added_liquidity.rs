@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    common::verification_cache::{cache_failure, get_cached_failure},
     models::{AppState, VerifyQuery},
     utils::{get_error, CompletedTasksTrait},
 };
@@ -25,6 +26,9 @@ pub async fn handler(
 ) -> impl IntoResponse {
     let task_id = 38;
     let addr = &query.addr;
+    if let Some(failure) = get_cached_failure(&state, &query.addr, task_id).await {
+        return failure;
+    }
 
     // check if user has provider liquidity
     let call_result = state
@@ -42,11 +46,17 @@ pub async fn handler(
     match call_result {
         Ok(result) => {
             if result[0] == FieldElement::ZERO {
-                get_error("You didn't provided any liquidity on Ekubo.".to_string())
+                cache_failure(
+                    &state,
+                    &query.addr,
+                    task_id,
+                    "You didn't provided any liquidity on Ekubo.",
+                )
+                .await
             } else {
                 match state.upsert_completed_task(query.addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             }
         }
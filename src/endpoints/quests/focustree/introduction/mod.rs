@@ -1,3 +1,2 @@
-pub mod claimable;
 pub mod verify_twitter_fw;
 pub mod verify_twitter_rt;
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     models::{AppState, VerifyQuery},
-    utils::{get_error, CompletedTasksTrait},
+    utils::CompletedTasksTrait,
 };
 use axum::{
     extract::{Query, State},
@@ -21,6 +21,6 @@ pub async fn handler(
     let task_id = 62;
     match state.upsert_completed_task(query.addr, task_id).await {
         Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-        Err(e) => get_error(format!("{}", e)),
+        Err(e) => e.into_response(),
     }
 }
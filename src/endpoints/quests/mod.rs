@@ -6,8 +6,9 @@ pub mod ekubo;
 pub mod focustree;
 pub mod nostra;
 pub mod proscore;
-pub mod starknet;
+pub mod share_card;
 pub mod starknetid;
+pub mod token_uri;
 pub mod uri;
 pub mod verify_balance;
 pub mod verify_contract;
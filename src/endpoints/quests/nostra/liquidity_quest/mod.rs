@@ -1,2 +1 @@
-pub mod claimable;
 pub mod verify_added_liquidity;
@@ -48,7 +48,7 @@ pub async fn handler(
             } else {
                 match state.upsert_completed_task(query.addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             }
         }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    common::verification_cache::{cache_failure, get_cached_failure},
     models::{AppState, VerifyQuery},
     utils::{get_error, CompletedTasksTrait},
 };
@@ -25,6 +26,10 @@ pub async fn handler(
 ) -> impl IntoResponse {
     let task_id = 133;
     let addr = &query.addr;
+    if let Some(failure) = get_cached_failure(&state, &query.addr, task_id).await {
+        return failure;
+    }
+
     let balance_calldata = vec![*addr];
     let balance_result = state
         .provider
@@ -44,7 +49,7 @@ pub async fn handler(
     };
 
     if user_balance == FieldElement::ZERO {
-        return get_error("You didn't stake any STRK.".to_string());
+        return cache_failure(&state, &query.addr, task_id, "You didn't stake any STRK.").await;
     }
 
     let call_result = state
@@ -62,11 +67,17 @@ pub async fn handler(
     match call_result {
         Ok(result) => {
             if result[0] < FieldElement::from_dec_str("10").unwrap() {
-                get_error("You need to stake atleast 10 STRK".to_string())
+                cache_failure(
+                    &state,
+                    &query.addr,
+                    task_id,
+                    "You need to stake atleast 10 STRK",
+                )
+                .await
             } else {
                 match state.upsert_completed_task(query.addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             }
         }
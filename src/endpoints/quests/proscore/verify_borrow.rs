@@ -48,7 +48,7 @@ pub async fn handler(
         Ok(result) => match parse_res(&result) {
             Ok(true) => match state.upsert_completed_task(query.addr, task_id).await {
                 Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                Err(e) => get_error(format!("{}", e)),
+                Err(e) => e.into_response(),
             },
             Ok(false) => get_error("You must borrow 10 EKUBO tokens".to_string()),
             Err(e) => get_error(format!("Error while parsing Braavos signers: {}", e)),
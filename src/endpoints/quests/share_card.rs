@@ -0,0 +1,48 @@
+use crate::common::share_cards::{get_share_card_response, ShareCard};
+use crate::models::{AppState, QuestDocument};
+use crate::utils::{get_error, get_quest_completion_date};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ShareCardQuery {
+    id: u32,
+    addr: FieldElement,
+}
+
+// png preview of a quest completed by addr, meant to be linked as the og:image of the quest page
+#[route(get, "/quests/share_card")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ShareCardQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let quest = match state
+        .db
+        .collection::<QuestDocument>("quests")
+        .find_one(doc! { "id": query.id, "disabled": false }, None)
+        .await
+    {
+        Ok(Some(quest)) => quest,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Quest not found").into_response(),
+        Err(_) => return get_error("Error querying quest".to_string()),
+    };
+    let address = query.addr.to_string();
+    let Some(completed_at) = get_quest_completion_date(&state.db, &address, quest.id as i64).await
+    else {
+        return (StatusCode::NOT_FOUND, "Quest not completed").into_response();
+    };
+    let card = ShareCard::new(&quest.img_card, &quest.title_card, query.addr, completed_at);
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    get_share_card_response(&state, card, if_none_match).await
+}
@@ -53,7 +53,7 @@ pub async fn handler(
             if domain_len > 0 {
                 match state.upsert_completed_task(query.addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             } else {
                 get_error("You don't own a stark domain".to_string())
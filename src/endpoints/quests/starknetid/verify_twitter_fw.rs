@@ -25,6 +25,6 @@ pub async fn handler(
     }
     match state.upsert_completed_task(query.addr, task_id).await {
         Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-        Err(e) => get_error(format!("{}", e)),
+        Err(e) => e.into_response(),
     }
 }
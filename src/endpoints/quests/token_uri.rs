@@ -0,0 +1,23 @@
+use crate::common::nft_claims::get_claimed_level;
+use crate::endpoints::quests::uri::get_metadata_response;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_auto_routes::route;
+use std::sync::Arc;
+
+#[route(get, "/quests/uri/:token_id")]
+pub async fn handler(State(state): State<Arc<AppState>>, Path(token_id): Path<String>) -> Response {
+    let Ok(token_id) = token_id.parse::<u64>() else {
+        return (StatusCode::NOT_FOUND, "Token not found").into_response();
+    };
+    let level = match get_claimed_level(&state.db, token_id).await {
+        Ok(level) => level,
+        Err(e) => return get_error(format!("Error querying claims: {}", e)),
+    };
+    get_metadata_response(&state, level as i64, Some(token_id)).await
+}
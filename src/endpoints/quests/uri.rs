@@ -1,5 +1,6 @@
+use crate::common::nft_claims::NFT_CLAIMS_COLLECTION;
 use crate::models::{AppState, NFTUri};
-use crate::utils::get_error;
+use crate::utils::{get_app_url, get_error, get_quest_completion_date};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -7,23 +8,40 @@ use axum::{
     Json,
 };
 use axum_auto_routes::route;
-use futures::StreamExt;
-use mongodb::bson::{doc, from_document};
+use mongodb::bson::{doc, Document};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::sync::Arc;
 
+// ERC-721 metadata, as read by marketplaces and wallets
 #[derive(Serialize)]
 pub struct TokenURI {
     name: String,
     description: String,
     image: String,
-    attributes: Option<Vec<Attribute>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    animation_url: Option<String>,
+    external_url: String,
+    attributes: Vec<Attribute>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Attribute {
+    // "number" or "date" (a timestamp in seconds), strings are displayed as is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_type: Option<String>,
     trait_type: String,
-    value: u32,
+    value: Value,
+}
+
+impl Attribute {
+    fn new(display_type: Option<&str>, trait_type: &str, value: impl Into<Value>) -> Self {
+        Attribute {
+            display_type: display_type.map(String::from),
+            trait_type: trait_type.to_string(),
+            value: value.into(),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -36,44 +54,87 @@ pub async fn handler(
     State(state): State<Arc<AppState>>,
     Query(level_query): Query<LevelQuery>,
 ) -> Response {
-    let level = level_query
+    let Some(level) = level_query
         .level
-        .and_then(|level_str| level_str.parse::<i64>().ok());
+        .and_then(|level_str| level_str.parse::<i64>().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "Invalid level").into_response();
+    };
+    get_metadata_response(&state, level, None).await
+}
 
-    let uri_collection = state.db.collection::<NFTUri>("nft_uri");
-    let pipeline = vec![doc! {
-        "$match":{
-            "id":&level.unwrap()
-        }
-    }];
+// Metadata of the NFTs of level. Tokens issued through nft_claims also get the completion date of
+// the quest by their owner.
+pub async fn get_metadata_response(
+    state: &AppState,
+    level: i64,
+    token_id: Option<u64>,
+) -> Response {
+    let nft_uri = match state
+        .db
+        .collection::<NFTUri>("nft_uri")
+        .find_one(doc! { "id": level }, None)
+        .await
+    {
+        Ok(Some(nft_uri)) => nft_uri,
+        Ok(None) => return (StatusCode::NOT_FOUND, "NFT URI not found").into_response(),
+        Err(_) => return get_error("Error querying NFT URI".to_string()),
+    };
+    let quest = match state
+        .db
+        .collection::<Document>("quests")
+        .find_one(doc! { "id": nft_uri.quest_id }, None)
+        .await
+    {
+        Ok(Some(quest)) => quest,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Quest not found").into_response(),
+        Err(_) => return get_error("Error querying quest".to_string()),
+    };
 
-    match uri_collection.aggregate(pipeline, None).await {
-        Ok(mut cursor) => {
-            while let Some(result) = cursor.next().await {
-                return match result {
-                    Ok(document) => {
-                        if let Ok(nft_uri) = from_document::<NFTUri>(document) {
-                            return (
-                                StatusCode::OK,
-                                Json(TokenURI {
-                                    name: (&*nft_uri.name).to_string(),
-                                    description: (&*nft_uri.description).to_string(),
-                                    image: format!(
-                                        "{}{}",
-                                        state.conf.variables.app_link, &*nft_uri.image
-                                    ),
-                                    attributes: None,
-                                }),
-                            )
-                                .into_response();
-                        }
-                        get_error("Error querying NFT URI".to_string())
-                    }
-                    _ => get_error("Error querying NFT URI".to_string()),
-                };
+    let mut attributes = vec![
+        Attribute::new(None, "Quest", quest.get_str("name").unwrap_or_default()),
+        Attribute::new(
+            None,
+            "Category",
+            quest.get_str("category").unwrap_or_default(),
+        ),
+        Attribute::new(None, "Issuer", quest.get_str("issuer").unwrap_or_default()),
+        Attribute::new(Some("number"), "Level", nft_uri.id),
+    ];
+    if let Some(token_id) = token_id {
+        let claim = state
+            .db
+            .collection::<Document>(NFT_CLAIMS_COLLECTION)
+            .find_one(doc! { "token_id": token_id.to_string() }, None)
+            .await;
+        let owner = match claim {
+            Ok(claim) => claim.and_then(|c| c.get_str("address").ok().map(String::from)),
+            Err(_) => return get_error("Error querying NFT claim".to_string()),
+        };
+        if let Some(owner) = owner {
+            if let Some(date) = get_quest_completion_date(&state.db, &owner, nft_uri.quest_id).await
+            {
+                attributes.push(Attribute::new(Some("date"), "Completion date", date));
             }
-            get_error("NFT URI not found".to_string())
         }
-        Err(_) => get_error("Error querying NFT URI".to_string()),
     }
+    if let Some(attribute) = nft_uri.attributes {
+        attributes.push(attribute);
+    }
+
+    let app_link = &state.conf.variables.app_link;
+    (
+        StatusCode::OK,
+        Json(TokenURI {
+            name: nft_uri.name,
+            description: nft_uri.description,
+            image: get_app_url(app_link, &nft_uri.image),
+            animation_url: nft_uri
+                .animation_url
+                .map(|animation_url| get_app_url(app_link, &animation_url)),
+            external_url: format!("{}/quest/{}", app_link, nft_uri.quest_id),
+            attributes,
+        }),
+    )
+        .into_response()
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    common::verification_cache::{cache_failure, get_cached_failure},
     models::{AppState, QuestTaskDocument},
     utils::{get_error, CompletedTasksTrait},
 };
@@ -44,6 +45,10 @@ pub async fn handler(
         return get_error("Invalid task type.".to_string());
     }
 
+    if let Some(failure) = get_cached_failure(&state, &query.addr, task_id).await {
+        return failure;
+    }
+
     let addr = &query.addr;
     let utils_contract = state.conf.quests.utils_contract;
 
@@ -70,11 +75,11 @@ pub async fn handler(
         Ok(result) => {
             // if result[0] < FieldElement::from_dec_str("3000000000000000").unwrap() {
             if result[0] < required_amount {
-                get_error("You didn't invest (enough).".to_string())
+                cache_failure(&state, &query.addr, task_id, "You didn't invest (enough).").await
             } else {
                 match state.upsert_completed_task(query.addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             }
         }
@@ -111,7 +111,7 @@ pub async fn handler(
         // All calls succeeded and matched their regexes
         match state.upsert_completed_task(*addr, task_id).await {
             Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-            Err(e) => get_error(format!("Failed to update completed task: {}", e)),
+            Err(e) => e.into_response(),
         }
     } else {
         get_error("No calls specified for this task.".to_string())
@@ -12,7 +12,6 @@ use axum::{
 use axum_auto_routes::route;
 use mongodb::bson::doc;
 use regex::Regex;
-use reqwest::get;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use starknet::core::types::FieldElement;
@@ -58,24 +57,24 @@ pub async fn handler(
     // Call the specified API
     let parsed_api_url = parse_string(api_url, FieldElement::from_str(&query.addr).unwrap());
 
-    let response = get(&parsed_api_url).await;
+    let response = state.http.send(state.http.get(&parsed_api_url)).await;
 
     match response {
         Ok(res) => {
-            let res_text = res.text().await.unwrap();
+            let res_text = res.text();
 
             // Check response against the regex
             let parsed_regex_str =
                 parse_string(regex_str, FieldElement::from_str(&query.addr).unwrap());
             let re = Regex::new(&parsed_regex_str).unwrap();
-            if re.is_match(&res_text) {
+            if re.is_match(res_text) {
                 // Mark the task as completed
                 match state
                     .upsert_completed_task(FieldElement::from_str(&query.addr).unwrap(), task_id)
                     .await
                 {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 }
             } else {
                 get_error("User not eligible.".to_string())
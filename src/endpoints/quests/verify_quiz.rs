@@ -50,7 +50,7 @@ pub async fn handler(
     {
         true => match state.upsert_completed_task(body.addr, task_id).await {
             Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-            Err(e) => get_error(format!("{}", e)),
+            Err(e) => e.into_response(),
         },
         false => get_error("Incorrect answers".to_string()),
     }
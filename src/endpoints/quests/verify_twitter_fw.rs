@@ -37,7 +37,7 @@ pub async fn handler(
             while let Some(_result) = cursor.try_next().await.unwrap() {
                 match state.upsert_completed_task(query.addr, task_id).await {
                     Ok(_) => return (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
+                    Err(e) => e.into_response(),
                 };
             }
             get_error("Error querying task".to_string())
@@ -0,0 +1,461 @@
+use crate::config::Http;
+use chrono::Utc;
+use reqwest::{header::CACHE_CONTROL, Method, Request, RequestBuilder, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// expired responses are pruned once the cache holds that many entries
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+#[derive(Debug)]
+pub enum HttpError {
+    // the host failed too many times in a row, it is not called until its breaker closes
+    CircuitOpen(String),
+    Request(String),
+    Decode(String),
+    // the body is larger than the cap of the call, in bytes
+    TooLarge(usize),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::CircuitOpen(host) => {
+                write!(f, "{} is temporarily unavailable, retry later", host)
+            }
+            HttpError::Request(e) => write!(f, "Failed to send request: {}", e),
+            HttpError::Decode(e) => write!(f, "Failed to decode response: {}", e),
+            HttpError::TooLarge(max_bytes) => write!(f, "Response exceeds {} bytes", max_bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    status: StatusCode,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    // empty when the body isn't utf-8
+    pub fn text(&self) -> &str {
+        std::str::from_utf8(&self.body).unwrap_or_default()
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, HttpError> {
+        serde_json::from_slice(&self.body).map_err(|e| HttpError::Decode(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HostPolicy {
+    timeout: Duration,
+    retries: u32,
+    cache_ttl: u64,
+}
+
+// Counts the consecutive failures of a host. Once open, the breaker rejects every call until
+// the cooldown ends, then the next failure opens it again and a success closes it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Breaker {
+    failures: u32,
+    open_until: i64,
+}
+
+impl Breaker {
+    pub fn is_open(&self, now: i64) -> bool {
+        self.open_until > now
+    }
+
+    // returns true when this failure opened the breaker
+    pub fn record(&mut self, failed: bool, now: i64, threshold: u32, cooldown_ms: i64) -> bool {
+        if !failed {
+            *self = Breaker::default();
+            return false;
+        }
+        self.failures += 1;
+        if threshold > 0 && self.failures >= threshold {
+            self.open_until = now + cooldown_ms;
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UpstreamMetrics {
+    pub requests: u64,
+    pub errors: u64,
+    pub retries: u64,
+    pub cache_hits: u64,
+    // calls rejected while the breaker was open
+    pub rejected: u64,
+    pub breaker_opens: u64,
+    pub breaker_open: bool,
+    pub avg_latency_ms: u64,
+    pub max_latency_ms: u64,
+    #[serde(skip)]
+    total_latency_ms: u64,
+}
+
+struct CachedResponse {
+    response: HttpResponse,
+    expires_at: i64,
+}
+
+// Seconds a response can be cached for. Only hosts configured with a ttl are cached, the
+// Cache-Control header of the upstream then takes precedence, s-maxage over max-age.
+pub fn get_cache_ttl(cache_control: Option<&str>, default_ttl: u64) -> u64 {
+    let Some(cache_control) = cache_control.filter(|_| default_ttl > 0) else {
+        return default_ttl;
+    };
+    let (mut max_age, mut s_maxage) = (None, None);
+    for directive in cache_control.split(',') {
+        let directive = directive.trim().to_ascii_lowercase();
+        match directive.split_once('=') {
+            Some(("max-age", value)) => max_age = value.trim_matches('"').parse().ok(),
+            Some(("s-maxage", value)) => s_maxage = value.trim_matches('"').parse().ok(),
+            None if matches!(directive.as_str(), "no-store" | "no-cache" | "private") => return 0,
+            _ => {}
+        }
+    }
+    s_maxage.or(max_age).unwrap_or(default_ttl)
+}
+
+// Responses are cached per url and request headers so that calls made with different credentials
+// or representations never share an entry
+pub fn get_cache_key(request: &Request) -> String {
+    let mut headers: Vec<String> = request
+        .headers()
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    headers.sort();
+    headers.insert(0, request.url().to_string());
+    headers.join("\n")
+}
+
+// reads the body of response, failing once it exceeds max_bytes when set
+async fn read_body(
+    mut response: reqwest::Response,
+    max_bytes: Option<usize>,
+) -> Result<Vec<u8>, HttpError> {
+    let request_error = |e: reqwest::Error| HttpError::Request(e.to_string());
+    let Some(max_bytes) = max_bytes else {
+        return Ok(response.bytes().await.map_err(request_error)?.to_vec());
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes as u64)
+    {
+        return Err(HttpError::TooLarge(max_bytes));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(HttpError::TooLarge(max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+// exponential backoff, the jitter is added by the caller
+pub fn get_retry_delay(base_ms: u64, attempt: u32) -> Duration {
+    Duration::from_millis(base_ms.saturating_mul(1 << attempt.min(10)))
+}
+
+// Client shared by every call to partner APIs. Each host gets its own timeout, retries, circuit
+// breaker, cache ttl and metrics.
+pub struct HttpClient {
+    client: reqwest::Client,
+    conf: Http,
+    breakers: Mutex<HashMap<String, Breaker>>,
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    metrics: Mutex<HashMap<String, UpstreamMetrics>>,
+}
+
+impl HttpClient {
+    pub fn new(conf: &Http) -> Self {
+        HttpClient {
+            client: reqwest::Client::new(),
+            conf: conf.clone(),
+            breakers: Mutex::new(HashMap::new()),
+            cache: Mutex::new(HashMap::new()),
+            metrics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    fn get_policy(&self, host: &str) -> HostPolicy {
+        let host_conf = self.conf.hosts.iter().find(|conf| conf.host == host);
+        HostPolicy {
+            timeout: Duration::from_millis(
+                host_conf
+                    .and_then(|conf| conf.timeout_ms)
+                    .unwrap_or(self.conf.timeout_ms),
+            ),
+            retries: host_conf
+                .and_then(|conf| conf.retries)
+                .unwrap_or(self.conf.retries),
+            cache_ttl: host_conf
+                .and_then(|conf| conf.cache_ttl)
+                .unwrap_or(self.conf.cache_ttl),
+        }
+    }
+
+    fn update_metrics(&self, host: &str, update: impl FnOnce(&mut UpstreamMetrics)) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            update(metrics.entry(host.to_string()).or_default());
+        }
+    }
+
+    fn is_breaker_open(&self, host: &str, now: i64) -> bool {
+        self.breakers
+            .lock()
+            .map(|breakers| breakers.get(host).is_some_and(|b| b.is_open(now)))
+            .unwrap_or(false)
+    }
+
+    fn record_attempt(&self, host: &str, failed: bool, latency_ms: u64) {
+        let now = Utc::now().timestamp_millis();
+        let opened = match self.breakers.lock() {
+            Ok(mut breakers) => breakers.entry(host.to_string()).or_default().record(
+                failed,
+                now,
+                self.conf.breaker_failures,
+                self.conf.breaker_cooldown as i64 * 1000,
+            ),
+            Err(_) => false,
+        };
+        self.update_metrics(host, |metrics| {
+            metrics.requests += 1;
+            metrics.total_latency_ms += latency_ms;
+            metrics.max_latency_ms = metrics.max_latency_ms.max(latency_ms);
+            if failed {
+                metrics.errors += 1;
+            }
+            if opened {
+                metrics.breaker_opens += 1;
+            }
+        });
+    }
+
+    fn get_cached(&self, key: &str, now: i64) -> Option<HttpResponse> {
+        let cache = self.cache.lock().ok()?;
+        cache
+            .get(key)
+            .filter(|cached| cached.expires_at > now)
+            .map(|cached| cached.response.clone())
+    }
+
+    fn cache_response(&self, key: String, response: &HttpResponse, ttl: u64, now: i64) {
+        if let Ok(mut cache) = self.cache.lock() {
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.retain(|_, cached| cached.expires_at > now);
+            }
+            cache.insert(
+                key,
+                CachedResponse {
+                    response: response.clone(),
+                    expires_at: now + ttl as i64 * 1000,
+                },
+            );
+        }
+    }
+
+    // Sends the request with the policy of its host. Successful GET responses are cached, server
+    // errors, 429 and transport errors are retried and count as failures for the breaker.
+    pub async fn send(&self, builder: RequestBuilder) -> Result<HttpResponse, HttpError> {
+        self.execute(builder, None).await
+    }
+
+    // same as send for untrusted upstreams, bodies larger than max_bytes are rejected
+    pub async fn send_capped(
+        &self,
+        builder: RequestBuilder,
+        max_bytes: usize,
+    ) -> Result<HttpResponse, HttpError> {
+        self.execute(builder, Some(max_bytes)).await
+    }
+
+    async fn execute(
+        &self,
+        builder: RequestBuilder,
+        max_bytes: Option<usize>,
+    ) -> Result<HttpResponse, HttpError> {
+        let mut request = builder
+            .build()
+            .map_err(|e| HttpError::Request(e.to_string()))?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let policy = self.get_policy(&host);
+        let cache_key = (request.method() == Method::GET && policy.cache_ttl > 0)
+            .then(|| get_cache_key(&request));
+
+        if let Some(cached) = cache_key
+            .as_ref()
+            .and_then(|key| self.get_cached(key, Utc::now().timestamp_millis()))
+        {
+            self.update_metrics(&host, |metrics| metrics.cache_hits += 1);
+            return Ok(cached);
+        }
+
+        if request.timeout().is_none() {
+            *request.timeout_mut() = Some(policy.timeout);
+        }
+        // streamed bodies can't be sent twice
+        let retries = match request.try_clone() {
+            Some(_) => policy.retries,
+            None => 0,
+        };
+        let mut request = Some(request);
+        let mut attempt = 0;
+        loop {
+            if self.is_breaker_open(&host, Utc::now().timestamp_millis()) {
+                self.update_metrics(&host, |metrics| metrics.rejected += 1);
+                return Err(HttpError::CircuitOpen(host));
+            }
+            let current: Option<Request> = match attempt < retries {
+                true => request.as_ref().and_then(|request| request.try_clone()),
+                false => request.take(),
+            };
+            let current = current.ok_or_else(|| HttpError::Request("request consumed".into()))?;
+
+            let started_at = Instant::now();
+            let outcome = match self.client.execute(current).await {
+                Ok(response) => {
+                    let status = response.status();
+                    let cache_control = response
+                        .headers()
+                        .get(CACHE_CONTROL)
+                        .and_then(|value| value.to_str().ok())
+                        .map(|value| value.to_string());
+                    read_body(response, max_bytes)
+                        .await
+                        .map(|body| (status, cache_control, body))
+                }
+                Err(e) => Err(HttpError::Request(e.to_string())),
+            };
+            let failed = match &outcome {
+                Ok((status, _, _)) => {
+                    status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+                }
+                // the host answered, retrying would download the same body
+                Err(HttpError::TooLarge(_)) => false,
+                Err(_) => true,
+            };
+            self.record_attempt(&host, failed, started_at.elapsed().as_millis() as u64);
+
+            if failed && attempt < retries {
+                let jitter = rand::random::<u64>() % self.conf.retry_delay_ms.max(1);
+                tokio::time::sleep(
+                    get_retry_delay(self.conf.retry_delay_ms, attempt)
+                        + Duration::from_millis(jitter),
+                )
+                .await;
+                attempt += 1;
+                self.update_metrics(&host, |metrics| metrics.retries += 1);
+                continue;
+            }
+
+            let (status, cache_control, body) = outcome?;
+            let response = HttpResponse { status, body };
+            if let Some(key) = cache_key {
+                let ttl = get_cache_ttl(cache_control.as_deref(), policy.cache_ttl);
+                if status.is_success() && ttl > 0 {
+                    self.cache_response(key, &response, ttl, Utc::now().timestamp_millis());
+                }
+            }
+            return Ok(response);
+        }
+    }
+
+    pub fn get_metrics(&self) -> HashMap<String, UpstreamMetrics> {
+        let now = Utc::now().timestamp_millis();
+        let mut metrics = self
+            .metrics
+            .lock()
+            .map(|metrics| metrics.clone())
+            .unwrap_or_default();
+        for (host, metrics) in metrics.iter_mut() {
+            metrics.avg_latency_ms = metrics
+                .total_latency_ms
+                .checked_div(metrics.requests)
+                .unwrap_or(0);
+            metrics.breaker_open = self.is_breaker_open(host, now);
+        }
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_control_overrides_host_ttl() {
+        assert_eq!(get_cache_ttl(None, 30), 30);
+        assert_eq!(get_cache_ttl(Some("public, max-age=120"), 30), 120);
+        assert_eq!(get_cache_ttl(Some("s-maxage=10, max-age=120"), 30), 10);
+        assert_eq!(get_cache_ttl(Some("no-store"), 30), 0);
+        assert_eq!(get_cache_ttl(Some("private, max-age=60"), 30), 0);
+        assert_eq!(get_cache_ttl(Some("must-revalidate"), 30), 30);
+        // hosts without a ttl are never cached
+        assert_eq!(get_cache_ttl(Some("public, max-age=120"), 0), 0);
+    }
+
+    #[test]
+    fn cache_key_includes_headers() {
+        let client = reqwest::Client::new();
+        let key = |builder: RequestBuilder| get_cache_key(&builder.build().unwrap());
+        let url = "https://api.starkscan.co/api/v0/transfers";
+        let alice = key(client.get(url).header("authorization", "Bearer alice"));
+        let bob = key(client.get(url).header("authorization", "Bearer bob"));
+        assert_ne!(alice, bob);
+        assert_ne!(alice, key(client.get(url)));
+        assert_eq!(
+            key(client.get(url).header("a", "1").header("b", "2")),
+            key(client.get(url).header("b", "2").header("a", "1"))
+        );
+    }
+
+    #[test]
+    fn breaker_opens_after_consecutive_failures() {
+        let mut breaker = Breaker::default();
+        assert!(!breaker.record(true, 0, 3, 1000));
+        assert!(!breaker.record(false, 0, 3, 1000));
+        assert!(!breaker.record(true, 0, 3, 1000));
+        assert!(!breaker.record(true, 0, 3, 1000));
+        assert!(breaker.record(true, 0, 3, 1000));
+        assert!(breaker.is_open(999));
+        assert!(!breaker.is_open(1000));
+        // a failure after the cooldown opens it again right away
+        assert!(breaker.record(true, 1000, 3, 1000));
+        assert!(!breaker.record(false, 1500, 3, 1000));
+        assert!(!breaker.is_open(1500));
+    }
+
+    #[test]
+    fn retry_delay_doubles() {
+        assert_eq!(get_retry_delay(200, 0), Duration::from_millis(200));
+        assert_eq!(get_retry_delay(200, 2), Duration::from_millis(800));
+    }
+}
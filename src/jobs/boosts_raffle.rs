@@ -0,0 +1,307 @@
+use crate::common::boost_lifecycle::update_boosts_lifecycle;
+use crate::common::sybil::SYBIL_SCORES_COLLECTION;
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use crate::utils::to_hex;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    Collection,
+};
+use rand::Rng;
+use starknet::core::types::FieldElement;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// use this variable to add some extra winners so that we have some extra winners incase anyone user repeats
+const EXTRA_WINNERS: i32 = 10;
+
+pub struct BoostsRaffleJob;
+
+#[async_trait]
+impl Job for BoostsRaffleJob {
+    fn name(&self) -> &'static str {
+        "boosts_raffle"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let boost_collection = state.db.collection::<Document>("boosts");
+        let pipeline = vec![doc! {
+            "$match": {
+                "expiry":{
+                    "$lt": Utc::now().timestamp_millis()
+                },
+                "winner": {
+                    "$eq": null,
+                },
+            }
+        }];
+        let mut cursor = boost_collection
+            .aggregate(pipeline, None)
+            .await
+            .map_err(|e| format!("Error querying boosts: {}", e))?;
+
+        while let Some(boost) = cursor
+            .try_next()
+            .await
+            .map_err(|e| format!("Error reading boosts: {}", e))?
+        {
+            match draw_boost_winners(state, &boost_collection, &boost).await {
+                Ok(_) => report.success(),
+                Err(e) => {
+                    let error = format!(
+                        "job={} boost_id={} error={}",
+                        self.name(),
+                        boost.get("id").unwrap_or(&Bson::Null),
+                        e
+                    );
+                    state.logger.warning(error.clone());
+                    report.failure(error);
+                }
+            }
+        }
+
+        update_boosts_lifecycle(&state.db, &state.http, &state.conf, &state.logger).await;
+        Ok(())
+    }
+}
+
+async fn draw_boost_winners(
+    state: &Arc<AppState>,
+    boost_collection: &Collection<Document>,
+    boost: &Document,
+) -> Result<(), String> {
+    let boost_id = boost
+        .get_i32("id")
+        .map_err(|e| format!("invalid id: {}", e))?;
+    let num_of_winners = boost
+        .get_i32("num_of_winners")
+        .map_err(|e| format!("invalid num_of_winners: {}", e))?;
+    let quests = boost
+        .get_array("quests")
+        .map_err(|e| format!("invalid quests: {}", e))?;
+
+    // addresses scored above the risk threshold can't win
+    let max_risk_score = boost
+        .get_f64("max_risk_score")
+        .unwrap_or(state.conf.sybil.boost_threshold);
+
+    let completed_tasks_collection = state.db.collection::<Document>("completed_tasks");
+    let mut address_list: Vec<String> = Vec::new();
+    for quest in quests {
+        let completers = get_quest_completers(
+            &completed_tasks_collection,
+            quest,
+            num_of_winners + EXTRA_WINNERS,
+            max_risk_score,
+        )
+        .await?;
+        address_list.extend(completers);
+    }
+
+    // skip if no user has completed quests
+    if address_list.is_empty() {
+        return Ok(());
+    }
+
+    let (winners, extra_winners) = pick_winners(address_list, num_of_winners.max(0) as usize);
+    let filter = doc! { "id": boost_id, "winner": null };
+    let update = doc! { "$set": {
+        "winner": winners,
+        "extra_winners": extra_winners,
+        "drawn_at": Utc::now().timestamp_millis(),
+    } };
+    boost_collection
+        .update_one(filter, update, None)
+        .await
+        .map_err(|e| format!("unable to save winners: {}", e))?;
+    Ok(())
+}
+
+async fn get_quest_completers(
+    completed_tasks_collection: &Collection<Document>,
+    quest: &Bson,
+    sample_size: i32,
+    max_risk_score: f64,
+) -> Result<Vec<String>, String> {
+    let pipeline = vec![
+        doc! {
+            "$lookup": doc! {
+                "from": "tasks",
+                "localField": "task_id",
+                "foreignField": "id",
+                "as": "associated_tasks"
+            }
+        },
+        doc! {
+            "$match": doc! {
+                "$expr": doc! {
+                    "$eq": [
+                        doc! {
+                            "$first": "$associated_tasks.quest_id"
+                        },
+                        quest
+                    ]
+                }
+            }
+        },
+        doc! {
+            "$group": doc! {
+                "_id": "$address",
+                "tasks_list": doc! {
+                    "$push": doc! {
+                        "$arrayElemAt": [
+                            "$associated_tasks",
+                            0
+                        ]
+                    }
+                }
+            }
+        },
+        doc! {
+            "$unwind": "$tasks_list"
+        },
+        doc! {
+            "$group": doc! {
+                "_id": doc! {
+                    "address": "$_id",
+                    "quest_id": "$tasks_list.quest_id"
+                },
+                "tasks_array": doc! {
+                    "$push": "$tasks_list"
+                }
+            }
+        },
+        doc! {
+            "$project": doc! {
+                "_id": 0,
+                "address": "$_id.address",
+                "quest_id": "$_id.quest_id",
+                "tasks_array": 1
+            }
+        },
+        doc! {
+            "$lookup": doc! {
+                "from": "tasks",
+                "localField": "quest_id",
+                "foreignField": "quest_id",
+                "as": "associatedTasks"
+            }
+        },
+        doc! {
+            "$match": doc! {
+                "$expr": doc! {
+                    "$eq": [
+                        doc! {
+                            "$size": "$tasks_array"
+                        },
+                        doc! {
+                            "$size": "$associatedTasks"
+                        }
+                    ]
+                }
+            }
+        },
+        doc! {
+            "$project": doc! {
+                "address": "$address"
+            }
+        },
+        doc! {
+            "$lookup": doc! {
+                "from": SYBIL_SCORES_COLLECTION,
+                "localField": "address",
+                "foreignField": "address",
+                "as": "sybil"
+            }
+        },
+        // addresses without a score yet are kept
+        doc! {
+            "$match": doc! {
+                "sybil.score": { "$not": { "$gt": max_risk_score } }
+            }
+        },
+        doc! {
+            "$sample":{
+                "size": sample_size
+            }
+        },
+    ];
+
+    let mut cursor = completed_tasks_collection
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|e| format!("unable to query completed tasks: {}", e))?;
+    let mut addresses = Vec::new();
+    while let Some(doc) = cursor
+        .try_next()
+        .await
+        .map_err(|e| format!("unable to read completed tasks: {}", e))?
+    {
+        // invalid addresses are ignored instead of failing the whole boost
+        if let Some(address) = doc
+            .get_str("address")
+            .ok()
+            .and_then(|address| FieldElement::from_str(address).ok())
+        {
+            addresses.push(to_hex(address));
+        }
+    }
+    Ok(addresses)
+}
+
+// returns the winners and the remaining candidates which are kept for re-draws. A candidate listed
+// several times (one per completed quest of the boost) has as many chances to be drawn.
+pub fn pick_winners(
+    mut candidates: Vec<String>,
+    num_of_winners: usize,
+) -> (Vec<String>, Vec<String>) {
+    let mut rng = rand::thread_rng();
+    let mut drawn: Vec<String> = Vec::new();
+    while !candidates.is_empty() {
+        let candidate = candidates[rng.gen_range(0..candidates.len())].clone();
+        // the other entries of the candidate are dropped so that every draw makes progress
+        candidates.retain(|c| c != &candidate);
+        drawn.push(candidate);
+    }
+    let extra_winners = drawn.split_off(num_of_winners.min(drawn.len()));
+    (drawn, extra_winners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_winners_removes_duplicates() {
+        let candidates = vec!["0x1", "0x2", "0x1", "0x3", "0x2"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let (winners, extra_winners) = pick_winners(candidates, 2);
+        assert_eq!(winners.len(), 2);
+        assert_eq!(extra_winners.len(), 1);
+        assert!(!winners.contains(&extra_winners[0]));
+    }
+
+    #[test]
+    fn pick_winners_keeps_every_candidate_once() {
+        let candidates: Vec<String> = vec!["0x1", "0x1", "0x1", "0x2", "0x3", "0x3"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let (winners, extra_winners) = pick_winners(candidates, 1);
+        let mut drawn = [winners, extra_winners].concat();
+        drawn.sort();
+        assert_eq!(drawn, vec!["0x1", "0x2", "0x3"]);
+    }
+
+    #[test]
+    fn pick_winners_with_few_candidates() {
+        let (winners, extra_winners) = pick_winners(vec!["0x1".to_string()], 5);
+        assert_eq!(winners, vec!["0x1".to_string()]);
+        assert!(extra_winners.is_empty());
+    }
+}
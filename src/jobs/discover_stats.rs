@@ -0,0 +1,26 @@
+use crate::common::defi_stats::{refresh_defi_stats, DefiStatsKind};
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+// Stores a snapshot of every discover/defi stats upstream. A failing upstream keeps its previous
+// snapshot, which the endpoints serve as stale.
+pub struct DiscoverStatsJob;
+
+#[async_trait]
+impl Job for DiscoverStatsJob {
+    fn name(&self) -> &'static str {
+        "discover_stats"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        for kind in DefiStatsKind::ALL {
+            match refresh_defi_stats(state, kind).await {
+                Ok(_) => report.success(),
+                Err(e) => report.failure(format!("{}: {}", kind.name(), e)),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::common::xp_ledger::reconcile_leaderboard;
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+// Repairs the totals of leaderboard_table and the daily buckets that drifted from the user_exp
+// ledger, it also builds them on a fresh database
+pub struct LeaderboardReconcileJob;
+
+#[async_trait]
+impl Job for LeaderboardReconcileJob {
+    fn name(&self) -> &'static str {
+        "leaderboard_reconcile"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let result = reconcile_leaderboard(&state.db)
+            .await
+            .map_err(|e| format!("Unable to reconcile leaderboard: {}", e))?;
+        for address in result.repaired {
+            state.logger.warning(format!(
+                "job={} repaired leaderboard drift of {}",
+                self.name(),
+                address
+            ));
+            report.success();
+        }
+        for address in result.removed {
+            state.logger.warning(format!(
+                "job={} removed leaderboard total of {} without ledger entries",
+                self.name(),
+                address
+            ));
+            report.success();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+    Database,
+};
+
+use crate::logger::Logger;
+use crate::utils::is_duplicate_key;
+
+pub const JOB_LEASES_COLLECTION: &str = "job_leases";
+
+// Takes (or renews) the lease of a job. The upsert fails with a duplicate key error when another
+// instance holds a lease that has not expired yet, in which case the job must be skipped.
+pub async fn acquire_lease(
+    db: &Database,
+    logger: &Logger,
+    job_name: &str,
+    owner: &str,
+    duration_ms: i64,
+) -> bool {
+    let leases_collection = db.collection::<Document>(JOB_LEASES_COLLECTION);
+    let now = Utc::now().timestamp_millis();
+    let filter = doc! {
+        "_id": job_name,
+        "$or": [
+            { "expires_at": { "$lt": now } },
+            { "owner": owner },
+        ],
+    };
+    let update = doc! {
+        "$set": {
+            "owner": owner,
+            "acquired_at": now,
+            "expires_at": now + duration_ms,
+        }
+    };
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    match leases_collection.update_one(filter, update, options).await {
+        Ok(_) => true,
+        Err(e) if is_duplicate_key(&e) => false,
+        Err(e) => {
+            logger.warning(format!("job={} unable to acquire lease: {}", job_name, e));
+            false
+        }
+    }
+}
+
+pub async fn release_lease(db: &Database, logger: &Logger, job_name: &str, owner: &str) {
+    let leases_collection = db.collection::<Document>(JOB_LEASES_COLLECTION);
+    let filter = doc! { "_id": job_name, "owner": owner };
+    let update = doc! { "$set": { "expires_at": 0 } };
+    if let Err(e) = leases_collection.update_one(filter, update, None).await {
+        logger.warning(format!("job={} unable to release lease: {}", job_name, e));
+    }
+}
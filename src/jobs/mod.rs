@@ -0,0 +1,293 @@
+pub mod boosts_raffle;
+pub mod discover_stats;
+pub mod leaderboard_reconcile;
+pub mod lease;
+pub mod nft_mints;
+pub mod purge_unique_viewers;
+pub mod quests_expiry;
+pub mod seasons_freeze;
+pub mod sybil_scoring;
+pub mod webhooks_retry;
+
+use crate::jobs::lease::{acquire_lease, release_lease};
+use crate::models::AppState;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+    Database,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time::{sleep, Duration},
+};
+
+pub const JOBS_STATUS_COLLECTION: &str = "jobs_status";
+pub const JOB_RUNS_COLLECTION: &str = "job_runs";
+
+// only the first errors of a run are persisted to keep status documents small
+const MAX_REPORTED_ERRORS: usize = 10;
+
+// how often each job checks its schedule and the manual triggers / pauses set by admins
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+// minimum lease duration, used for manual runs and very frequent schedules
+const MIN_LEASE_DURATION_MS: i64 = 60_000;
+
+#[derive(Default)]
+pub struct JobReport {
+    pub processed: u32,
+    pub failed: u32,
+    pub errors: Vec<String>,
+}
+
+impl JobReport {
+    pub fn success(&mut self) {
+        self.processed += 1;
+    }
+
+    pub fn successes(&mut self, count: u64) {
+        self.processed += count as u32;
+    }
+
+    pub fn failure(&mut self, error: String) {
+        self.processed += 1;
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+#[async_trait]
+pub trait Job: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Errors affecting a single item must be recorded in the report, an Err aborts the whole run
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String>;
+}
+
+pub struct Scheduler {
+    state: Arc<AppState>,
+    instance_id: String,
+    shutdown: watch::Receiver<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    pub fn new(state: Arc<AppState>, shutdown: watch::Receiver<bool>) -> Self {
+        Scheduler {
+            state,
+            instance_id: format!("{:016x}", rand::random::<u64>()),
+            shutdown,
+            handles: Vec::new(),
+        }
+    }
+
+    // Jobs with a disabled schedule in the config can still be triggered manually by admins
+    pub fn register<J: Job + 'static>(&mut self, job: J) {
+        let state = self.state.clone();
+        let instance_id = self.instance_id.clone();
+        let mut shutdown = self.shutdown.clone();
+        let schedule = match state.conf.jobs.get(job.name()) {
+            Some(job_conf) if job_conf.enabled => match Schedule::from_str(&job_conf.schedule) {
+                Ok(schedule) => Some(schedule),
+                Err(e) => {
+                    state.logger.severe(format!(
+                        "job={} invalid schedule \"{}\": {}",
+                        job.name(),
+                        job_conf.schedule,
+                        e
+                    ));
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        self.handles.push(tokio::spawn(async move {
+            let mut next_run: Option<DateTime<Utc>> = schedule
+                .as_ref()
+                .and_then(|schedule| schedule.upcoming(Utc).next());
+            loop {
+                if *shutdown.borrow() {
+                    break;
+                }
+
+                let now = Utc::now();
+                let due = next_run.is_some_and(|next_run| next_run <= now);
+                if due {
+                    next_run = schedule
+                        .as_ref()
+                        .and_then(|schedule| schedule.after(&now).next());
+                }
+                let control = get_job_control(&state.db, job.name()).await;
+
+                if due || control.trigger_requested {
+                    // the lease is kept until the next scheduled run so that other instances skip this one
+                    let lease_duration = next_run
+                        .map(|next_run| (next_run - now).num_milliseconds())
+                        .unwrap_or(0)
+                        .max(MIN_LEASE_DURATION_MS);
+                    if acquire_lease(
+                        &state.db,
+                        &state.logger,
+                        job.name(),
+                        &instance_id,
+                        lease_duration,
+                    )
+                    .await
+                    {
+                        if control.trigger_requested
+                            && take_trigger_request(&state.db, job.name()).await
+                        {
+                            run_job(&state, &job, &instance_id, "manual").await;
+                        } else if due && !control.paused {
+                            run_job(&state, &job, &instance_id, "schedule").await;
+                        }
+                    }
+                }
+
+                tokio::select! {
+                    _ = sleep(POLL_INTERVAL) => {},
+                    res = shutdown.changed() => {
+                        if res.is_err() {
+                            break;
+                        }
+                    },
+                }
+            }
+            release_lease(&state.db, &state.logger, job.name(), &instance_id).await;
+            state.logger.info(format!("job={} stopped", job.name()));
+        }));
+    }
+
+    // waits for every job to finish its current run after shutdown was requested
+    pub async fn join(self) {
+        for handle in self.handles {
+            let _ = handle.await;
+        }
+    }
+}
+
+#[derive(Default)]
+struct JobControl {
+    paused: bool,
+    trigger_requested: bool,
+}
+
+async fn get_job_control(db: &Database, job_name: &str) -> JobControl {
+    let status_collection = db.collection::<Document>(JOBS_STATUS_COLLECTION);
+    match status_collection
+        .find_one(doc! { "_id": job_name }, None)
+        .await
+    {
+        Ok(Some(status)) => JobControl {
+            paused: status.get_bool("paused").unwrap_or(false),
+            trigger_requested: status.get_bool("trigger_requested").unwrap_or(false),
+        },
+        _ => JobControl::default(),
+    }
+}
+
+// atomically clears the manual trigger so that a single instance runs it
+async fn take_trigger_request(db: &Database, job_name: &str) -> bool {
+    let status_collection = db.collection::<Document>(JOBS_STATUS_COLLECTION);
+    matches!(
+        status_collection
+            .find_one_and_update(
+                doc! { "_id": job_name, "trigger_requested": true },
+                doc! { "$set": { "trigger_requested": false } },
+                None,
+            )
+            .await,
+        Ok(Some(_))
+    )
+}
+
+async fn run_job<J: Job>(state: &Arc<AppState>, job: &J, instance_id: &str, trigger: &str) {
+    let logger = &state.logger;
+    let started_at = Utc::now().timestamp_millis();
+    let mut report = JobReport::default();
+    let result = job.run(state, &mut report).await;
+    let finished_at = Utc::now().timestamp_millis();
+
+    let status = match &result {
+        Err(e) => {
+            logger.severe(format!("job={} failed: {}", job.name(), e));
+            "failed"
+        }
+        Ok(_) if report.failed > 0 => {
+            logger.warning(format!(
+                "job={} completed with {} failed items out of {}",
+                job.name(),
+                report.failed,
+                report.processed
+            ));
+            "partial"
+        }
+        Ok(_) => "success",
+    };
+
+    let run = doc! {
+        "instance": instance_id,
+        "trigger": trigger,
+        "status": status,
+        "started_at": started_at,
+        "finished_at": finished_at,
+        "duration_ms": finished_at - started_at,
+        "processed": report.processed,
+        "failed": report.failed,
+        "errors": report.errors,
+        "error": result.err(),
+    };
+
+    let runs_collection = state.db.collection::<Document>(JOB_RUNS_COLLECTION);
+    let mut history = run.clone();
+    history.insert("job", job.name());
+    if let Err(e) = runs_collection.insert_one(history, None).await {
+        logger.warning(format!("job={} unable to save run: {}", job.name(), e));
+    }
+
+    let status_collection = state.db.collection::<Document>(JOBS_STATUS_COLLECTION);
+    let options = UpdateOptions::builder().upsert(true).build();
+    if let Err(e) = status_collection
+        .update_one(
+            doc! { "_id": job.name() },
+            doc! { "$set": { "last_run": run } },
+            options,
+        )
+        .await
+    {
+        logger.warning(format!("job={} unable to save status: {}", job.name(), e));
+    }
+}
+
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,105 @@
+use crate::common::nft_claims::{mark_nft_minted, parse_mint};
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use async_trait::async_trait;
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+};
+use starknet::core::{
+    types::{BlockId, EventFilter, FieldElement},
+    utils::get_selector_from_name,
+};
+use starknet::providers::Provider;
+use std::sync::Arc;
+
+// last block indexed by each indexing job
+pub const INDEXER_CURSORS_COLLECTION: &str = "indexer_cursors";
+
+// a run stops there, the next one continues from the last indexed block
+const MAX_BLOCKS_PER_RUN: u64 = 10_000;
+const EVENTS_CHUNK_SIZE: u64 = 1_000;
+
+// Indexes the mints of the NFT contract and marks the matching nft_claims as minted. The cursor
+// only moves once every mint of the block range is saved, a failing run is retried entirely.
+pub struct NftMintsJob;
+
+#[async_trait]
+impl Job for NftMintsJob {
+    fn name(&self) -> &'static str {
+        "nft_mints"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let cursors = state.db.collection::<Document>(INDEXER_CURSORS_COLLECTION);
+        let last_block = cursors
+            .find_one(doc! { "_id": self.name() }, None)
+            .await
+            .map_err(|e| format!("Error reading cursor: {}", e))?
+            .and_then(|cursor| cursor.get_i64("block_number").ok());
+        let from_block = match last_block {
+            Some(block) => block as u64 + 1,
+            None => state.conf.nft_contract.deploy_block,
+        };
+        let latest_block = state
+            .provider
+            .block_number()
+            .await
+            .map_err(|e| format!("Error reading latest block: {}", e))?;
+        if from_block > latest_block {
+            return Ok(());
+        }
+        let to_block = latest_block.min(from_block + MAX_BLOCKS_PER_RUN - 1);
+
+        let address = FieldElement::from_hex_be(&state.conf.nft_contract.address)
+            .map_err(|e| format!("Invalid NFT contract address: {}", e))?;
+        let transfer = get_selector_from_name("Transfer").unwrap();
+        let filter = EventFilter {
+            from_block: Some(BlockId::Number(from_block)),
+            to_block: Some(BlockId::Number(to_block)),
+            address: Some(address),
+            keys: Some(vec![vec![transfer]]),
+        };
+        let mut continuation_token = None;
+        loop {
+            let page = state
+                .provider
+                .get_events(filter.clone(), continuation_token, EVENTS_CHUNK_SIZE)
+                .await
+                .map_err(|e| format!("Error reading events: {}", e))?;
+            for event in page.events {
+                let Some((to, token_id)) = parse_mint(&event.keys, &event.data) else {
+                    continue;
+                };
+                match mark_nft_minted(
+                    &state.db,
+                    &to,
+                    token_id,
+                    event.block_number,
+                    &event.transaction_hash,
+                )
+                .await
+                {
+                    Ok(true) => report.success(),
+                    Ok(false) => {}
+                    Err(e) => return Err(format!("Error saving mint of {}: {}", token_id, e)),
+                }
+            }
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        let options = UpdateOptions::builder().upsert(true).build();
+        cursors
+            .update_one(
+                doc! { "_id": self.name() },
+                doc! { "$set": { "block_number": to_block as i64 } },
+                options,
+            )
+            .await
+            .map_err(|e| format!("Error saving cursor: {}", e))?;
+        Ok(())
+    }
+}
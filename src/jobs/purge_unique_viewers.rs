@@ -0,0 +1,29 @@
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use crate::utils::get_timestamp_from_days;
+use async_trait::async_trait;
+use mongodb::bson::{doc, Document};
+use std::sync::Arc;
+
+// page visits older than this are no longer used by analytics
+const RETENTION_DAYS: i64 = 90;
+
+pub struct PurgeUniqueViewersJob;
+
+#[async_trait]
+impl Job for PurgeUniqueViewersJob {
+    fn name(&self) -> &'static str {
+        "purge_unique_viewers"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let collection = state.db.collection::<Document>("unique_viewers");
+        let filter = doc! { "timestamp": { "$lt": get_timestamp_from_days(RETENTION_DAYS) } };
+        let result = collection
+            .delete_many(filter, None)
+            .await
+            .map_err(|e| format!("Unable to purge unique_viewers: {}", e))?;
+        report.successes(result.deleted_count);
+        Ok(())
+    }
+}
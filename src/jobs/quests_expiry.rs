@@ -0,0 +1,65 @@
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use async_trait::async_trait;
+use chrono::Utc;
+use mongodb::bson::{doc, Document};
+use std::sync::Arc;
+
+// quests without expiry have a null or negative one
+pub fn is_quest_expired(expiry: Option<i64>, now: i64) -> bool {
+    expiry.is_some_and(|expiry| expiry >= 0 && expiry < now)
+}
+
+// Maintains the expired flag read by the quest listings. Admins set it when they change the
+// expiry, the job catches the quests expiring in between and the flags left over after an
+// expiry was pushed back.
+pub struct QuestsExpiryJob;
+
+#[async_trait]
+impl Job for QuestsExpiryJob {
+    fn name(&self) -> &'static str {
+        "quests_expiry"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let collection = state.db.collection::<Document>("quests");
+        let now = Utc::now().timestamp_millis();
+        let expired = collection
+            .update_many(
+                doc! {
+                    "expiry": { "$gte": 0, "$lt": now },
+                    "expired": { "$ne": true },
+                },
+                doc! { "$set": { "expired": true } },
+                None,
+            )
+            .await
+            .map_err(|e| format!("Unable to expire quests: {}", e))?;
+        let extended = collection
+            .update_many(
+                doc! {
+                    "expired": { "$ne": false },
+                    "$nor": [{ "expiry": { "$gte": 0, "$lt": now } }],
+                },
+                doc! { "$set": { "expired": false } },
+                None,
+            )
+            .await
+            .map_err(|e| format!("Unable to unexpire quests: {}", e))?;
+        report.successes(expired.modified_count + extended.modified_count);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_past_expiries_only() {
+        assert!(is_quest_expired(Some(999), 1000));
+        assert!(!is_quest_expired(Some(1000), 1000));
+        assert!(!is_quest_expired(Some(-1), 1000));
+        assert!(!is_quest_expired(None, 1000));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::common::seasons::{freeze_season, SEASONS_COLLECTION};
+use crate::jobs::{Job, JobReport};
+use crate::models::{AppState, SeasonDocument};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use std::sync::Arc;
+
+pub struct SeasonsFreezeJob;
+
+#[async_trait]
+impl Job for SeasonsFreezeJob {
+    fn name(&self) -> &'static str {
+        "seasons_freeze"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let collection = state.db.collection::<SeasonDocument>(SEASONS_COLLECTION);
+        let filter = doc! {
+            "end_time": { "$lte": Utc::now().timestamp_millis() },
+            "frozen_at": null,
+        };
+        let mut cursor = collection
+            .find(filter, None)
+            .await
+            .map_err(|e| format!("Error querying seasons: {}", e))?;
+
+        while let Some(season) = cursor
+            .try_next()
+            .await
+            .map_err(|e| format!("Error reading seasons: {}", e))?
+        {
+            match freeze_season(&state.db, season.id).await {
+                Ok(total_users) => {
+                    state.logger.info(format!(
+                        "job={} season_id={} frozen with {} users",
+                        self.name(),
+                        season.id,
+                        total_users
+                    ));
+                    report.success();
+                }
+                Err(e) => report.failure(format!("season_id={} error={}", season.id, e)),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::common::sybil::{update_sybil_score, SYBIL_SCORES_COLLECTION};
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use starknet::core::types::FieldElement;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// scores are refreshed at most once a day per address
+const SCORE_TTL_MS: i64 = 86_400_000;
+// bounds the Starkscan calls made by a single run
+const MAX_ADDRESSES_PER_RUN: i64 = 200;
+
+// Scores the addresses which completed tasks recently and don't have a fresh score
+pub struct SybilScoringJob;
+
+#[async_trait]
+impl Job for SybilScoringJob {
+    fn name(&self) -> &'static str {
+        "sybil_scoring"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let since = Utc::now().timestamp_millis() - SCORE_TTL_MS;
+        let pipeline = vec![
+            doc! { "$match": { "timestamp": { "$gte": since } } },
+            doc! { "$group": { "_id": "$address" } },
+            doc! {
+                "$lookup": {
+                    "from": SYBIL_SCORES_COLLECTION,
+                    "localField": "_id",
+                    "foreignField": "address",
+                    "as": "score"
+                }
+            },
+            doc! {
+                "$match": {
+                    "$or": [
+                        { "score": { "$size": 0 } },
+                        { "score.updated_at": { "$lt": since } },
+                    ]
+                }
+            },
+            doc! { "$limit": MAX_ADDRESSES_PER_RUN },
+        ];
+        let mut cursor = state
+            .db
+            .collection::<Document>("completed_tasks")
+            .aggregate(pipeline, None)
+            .await
+            .map_err(|e| format!("Error querying completions: {}", e))?;
+
+        while let Some(doc) = cursor
+            .try_next()
+            .await
+            .map_err(|e| format!("Error reading completions: {}", e))?
+        {
+            let Some(addr) = doc
+                .get_str("_id")
+                .ok()
+                .and_then(|address| FieldElement::from_str(address).ok())
+            else {
+                continue;
+            };
+            match update_sybil_score(state, &addr).await {
+                Ok(_) => report.success(),
+                Err(e) => report.failure(format!("address={} error={}", addr, e)),
+            }
+        }
+        Ok(())
+    }
+}
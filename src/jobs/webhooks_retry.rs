@@ -0,0 +1,78 @@
+use crate::common::webhooks::{deliver_webhook, WEBHOOK_DELIVERIES_COLLECTION};
+use crate::jobs::{Job, JobReport};
+use crate::models::AppState;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document};
+use std::sync::Arc;
+
+// deliveries are abandoned after this number of attempts
+const MAX_ATTEMPTS: i32 = 5;
+
+pub struct WebhooksRetryJob;
+
+#[async_trait]
+impl Job for WebhooksRetryJob {
+    fn name(&self) -> &'static str {
+        "webhooks_retry"
+    }
+
+    async fn run(&self, state: &Arc<AppState>, report: &mut JobReport) -> Result<(), String> {
+        let collection = state
+            .db
+            .collection::<Document>(WEBHOOK_DELIVERIES_COLLECTION);
+        let filter = doc! { "status": "failed", "attempts": { "$lt": MAX_ATTEMPTS } };
+        let mut cursor = collection
+            .find(filter, None)
+            .await
+            .map_err(|e| format!("Error querying webhook deliveries: {}", e))?;
+
+        while let Some(delivery) = cursor
+            .try_next()
+            .await
+            .map_err(|e| format!("Error reading webhook deliveries: {}", e))?
+        {
+            let (Ok(id), Ok(endpoint), Some(body)) = (
+                delivery.get_object_id("_id"),
+                delivery.get_str("endpoint"),
+                delivery
+                    .get("body")
+                    .cloned()
+                    .map(Bson::into_relaxed_extjson),
+            ) else {
+                report.failure(format!("Malformed webhook delivery: {}", delivery));
+                continue;
+            };
+            let attempts = delivery.get_i32("attempts").unwrap_or(0) + 1;
+
+            let result = deliver_webhook(&state.http, &state.conf.webhooks, endpoint, &body).await;
+            let status = match &result {
+                Ok(_) => "delivered",
+                Err(_) if attempts >= MAX_ATTEMPTS => "abandoned",
+                Err(_) => "failed",
+            };
+            let update = doc! {
+                "$set": {
+                    "status": status,
+                    "attempts": attempts,
+                    "last_error": result.as_ref().err().cloned(),
+                    "updated_at": Utc::now().timestamp_millis(),
+                }
+            };
+            if let Err(e) = collection
+                .update_one(doc! { "_id": id }, update, None)
+                .await
+            {
+                report.failure(format!("Unable to update webhook delivery {}: {}", id, e));
+                continue;
+            }
+
+            match result {
+                Ok(_) => report.success(),
+                Err(e) => report.failure(format!("Webhook delivery {} failed: {}", id, e)),
+            }
+        }
+        Ok(())
+    }
+}
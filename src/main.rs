@@ -3,11 +3,30 @@ mod utils;
 mod common;
 mod config;
 mod endpoints;
+mod http_client;
+mod image_store;
+mod jobs;
 mod logger;
 mod middleware;
 mod models;
+mod reward_providers;
+mod reward_signer;
 
-use crate::utils::{add_leaderboard_table, run_boosts_raffle};
+use crate::common::defi_stats::{ensure_defi_stats_indexes, STALE_HEADER, UPDATED_AT_HEADER};
+use crate::common::nft_claims::ensure_nft_claims_indexes;
+use crate::common::share_cards::{ensure_share_cards_indexes, load_font};
+use crate::common::verification_cache::ensure_verification_cache_indexes;
+use crate::common::xp_ledger::ensure_xp_ledger_indexes;
+use crate::jobs::{
+    boosts_raffle::BoostsRaffleJob, discover_stats::DiscoverStatsJob,
+    leaderboard_reconcile::LeaderboardReconcileJob, nft_mints::NftMintsJob,
+    purge_unique_viewers::PurgeUniqueViewersJob, quests_expiry::QuestsExpiryJob,
+    seasons_freeze::SeasonsFreezeJob, shutdown_signal, sybil_scoring::SybilScoringJob,
+    webhooks_retry::WebhooksRetryJob, Scheduler,
+};
+use crate::middleware::address_ip::{ensure_address_ips_indexes, track_address_ip};
+use crate::middleware::rate_limit::{rate_limit_middleware, RateLimiter};
+use crate::reward_providers::cache::ensure_rewards_cache_indexes;
 use axum::{http::StatusCode, Router};
 use axum_auto_routes::route;
 use mongodb::{bson::doc, options::ClientOptions, Client};
@@ -16,7 +35,7 @@ use starknet::providers::{jsonrpc::HttpTransport, JsonRpcClient};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::Mutex;
-use tokio::sync;
+use tokio::sync::{self, watch};
 use tower_http::cors::{Any, CorsLayer};
 use utils::WithState;
 
@@ -41,6 +60,37 @@ async fn main() {
         .await
         .unwrap();
 
+    let (nft_signer, boost_signer) = match (
+        reward_signer::get_reward_signer(&conf.nft_contract.signer).await,
+        reward_signer::get_reward_signer(&conf.quest_boost.signer).await,
+    ) {
+        (Ok(nft_signer), Ok(boost_signer)) => (nft_signer, boost_signer),
+        (Err(e), _) | (_, Err(e)) => {
+            logger
+                .async_severe(format!("Unable to load signer: {}", e))
+                .await;
+            return;
+        }
+    };
+
+    let images = match image_store::get_image_store(&conf.images.store) {
+        Ok(images) => images,
+        Err(e) => {
+            logger
+                .async_severe(format!("Unable to load image store: {}", e))
+                .await;
+            return;
+        }
+    };
+
+    let share_card_font = match load_font(&conf.share_cards.font) {
+        Ok(font) => font,
+        Err(e) => {
+            logger.async_severe(e).await;
+            return;
+        }
+    };
+
     let shared_state = Arc::new(models::AppState {
         last_task_id: sync::Mutex::new(0),
         last_question_id: sync::Mutex::new(0),
@@ -52,6 +102,12 @@ async fn main() {
         db: Client::with_options(client_options)
             .unwrap()
             .database(&conf.database.name),
+        http: http_client::HttpClient::new(&conf.http),
+        nft_signer,
+        boost_signer,
+        images,
+        reward_providers: reward_providers::get_reward_providers(&conf),
+        share_card_font,
     });
     if shared_state
         .db
@@ -65,15 +121,57 @@ async fn main() {
         logger.info("Connected to database");
     }
 
-    let db_instance = shared_state.db.clone();
-    run_boosts_raffle(
-        &db_instance,
-        conf.quest_boost.update_interval,
-        logger.clone(),
-    );
-    add_leaderboard_table(&shared_state.db).await;
+    let rate_limiter =
+        match RateLimiter::new(&conf.rate_limit, &shared_state.db, logger.clone()).await {
+            Ok(rate_limiter) => Arc::new(rate_limiter),
+            Err(e) => {
+                logger.async_severe(e).await;
+                return;
+            }
+        };
 
-    let cors = CorsLayer::new().allow_headers(Any).allow_origin(Any);
+    let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+    let mut scheduler = Scheduler::new(shared_state.clone(), shutdown_receiver);
+    scheduler.register(BoostsRaffleJob);
+    scheduler.register(LeaderboardReconcileJob);
+    scheduler.register(QuestsExpiryJob);
+    scheduler.register(PurgeUniqueViewersJob);
+    scheduler.register(WebhooksRetryJob);
+    scheduler.register(SeasonsFreezeJob);
+    scheduler.register(SybilScoringJob);
+    scheduler.register(DiscoverStatsJob);
+    scheduler.register(NftMintsJob);
+    if let Err(e) = ensure_xp_ledger_indexes(&shared_state.db).await {
+        logger.severe(format!("Unable to create xp ledger indexes: {}", e));
+    }
+    if let Err(e) = ensure_verification_cache_indexes(&shared_state.db).await {
+        logger.severe(format!(
+            "Unable to create verification cache indexes: {}",
+            e
+        ));
+    }
+    if let Err(e) = ensure_defi_stats_indexes(&shared_state.db, conf.discover.history_days).await {
+        logger.severe(format!("Unable to create defi stats indexes: {}", e));
+    }
+    if let Err(e) = ensure_nft_claims_indexes(&shared_state.db).await {
+        logger.severe(format!("Unable to create nft claims indexes: {}", e));
+    }
+    if let Err(e) = ensure_share_cards_indexes(&shared_state.db, conf.share_cards.cache_ttl).await {
+        logger.severe(format!("Unable to create share cards indexes: {}", e));
+    }
+    if let Err(e) = ensure_address_ips_indexes(&shared_state.db).await {
+        logger.severe(format!("Unable to create address ips indexes: {}", e));
+    }
+    if let Err(e) = ensure_rewards_cache_indexes(&shared_state.db, conf.rewards.cache_max_age).await
+    {
+        logger.severe(format!("Unable to create rewards cache indexes: {}", e));
+    }
+
+    // the frontend is served from another origin, it reads the age of the defi stats
+    let cors = CorsLayer::new()
+        .allow_headers(Any)
+        .allow_origin(Any)
+        .expose_headers([UPDATED_AT_HEADER, STALE_HEADER]);
     let app = ROUTE_REGISTRY
         .lock()
         .unwrap()
@@ -82,6 +180,20 @@ async fn main() {
         .fold(Router::new().with_state(shared_state.clone()), |acc, r| {
             acc.merge(r.to_router(shared_state.clone()))
         })
+        .merge(
+            endpoints::quests::claimable::get_alias_router(&conf.claimable_aliases)
+                .with_state(shared_state.clone()),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            shared_state.clone(),
+            track_address_ip,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit_middleware,
+        ))
+        // the middlewares above read the client ip from the configured source
+        .layer(conf.server.ip_source.clone().into_extension())
         .layer(cors);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], conf.server.port));
@@ -91,8 +203,15 @@ async fn main() {
     ));
     axum::Server::bind(&addr)
         .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
+
+    logger
+        .async_info("server: shutting down, waiting for running jobs")
+        .await;
+    let _ = shutdown_sender.send(true);
+    scheduler.join().await;
 }
 
 #[route(get, "/")]
@@ -0,0 +1,114 @@
+use crate::models::AppState;
+use axum::{
+    extract::{Query, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use axum_client_ip::SecureClientIp;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::{Database, IndexModel};
+use starknet::core::types::FieldElement;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ip addresses each wallet used, one document per (address, ip) pair
+pub const ADDRESS_IPS_COLLECTION: &str = "address_ips";
+// pairs not seen for this long are removed
+const ADDRESS_IPS_RETENTION: Duration = Duration::from_secs(90 * 86_400);
+// last_seen is only rewritten when older than this, most requests don't write anything
+const LAST_SEEN_REFRESH_MS: i64 = 10 * 60 * 1000;
+
+pub async fn ensure_address_ips_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(ADDRESS_IPS_COLLECTION);
+    let expiry = IndexModel::builder()
+        .keys(doc! { "last_seen": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(ADDRESS_IPS_RETENTION)
+                .build(),
+        )
+        .build();
+    collection.create_index(expiry, None).await?;
+    let pair = IndexModel::builder()
+        .keys(doc! { "address": 1, "ip": 1 })
+        .build();
+    collection.create_index(pair, None).await?;
+    let ip = IndexModel::builder().keys(doc! { "ip": 1 }).build();
+    collection.create_index(ip, None).await?;
+    Ok(())
+}
+
+// verify flows only succeed for addresses which did the task themselves
+fn is_verify_path(path: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .starts_with("verify")
+}
+
+// Records the client ip of successful verify requests made for an address (addr query parameter)
+// so that addresses sharing ips can be detected. Other endpoints accept any address, recording
+// them would let anyone attach an address to their ip.
+pub async fn track_address_ip<B>(
+    State(state): State<Arc<AppState>>,
+    client_ip: SecureClientIp,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let addr = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(params)| params.get("addr").cloned())
+        .and_then(|addr| FieldElement::from_str(&addr).ok())
+        .filter(|addr| *addr != FieldElement::ZERO)
+        .filter(|_| is_verify_path(req.uri().path()));
+
+    let response = next.run(req).await;
+    if let (Some(addr), true) = (addr, response.status().is_success()) {
+        let ip = client_ip.0.to_string();
+        tokio::spawn(async move {
+            let collection = state.db.collection::<Document>(ADDRESS_IPS_COLLECTION);
+            let now = DateTime::now();
+            let refresh_before =
+                DateTime::from_millis(now.timestamp_millis() - LAST_SEEN_REFRESH_MS);
+            let filter = doc! { "address": addr.to_string(), "ip": &ip };
+            // a document left unchanged is not written, numeric dates of older documents sort
+            // before any date and are converted on their next refresh
+            let update = vec![doc! {
+                "$set": {
+                    "first_seen": { "$ifNull": ["$first_seen", now] },
+                    "last_seen": {
+                        "$cond": [
+                            { "$lt": [{ "$ifNull": ["$last_seen", 0] }, refresh_before] },
+                            now,
+                            "$last_seen"
+                        ]
+                    }
+                }
+            }];
+            let options = UpdateOptions::builder().upsert(true).build();
+            if let Err(e) = collection.update_one(filter, update, options).await {
+                state
+                    .logger
+                    .warning(format!("Unable to record address ip: {}", e));
+            }
+        });
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_verify_paths_only() {
+        assert!(is_verify_path("/quests/verify_quiz"));
+        assert!(is_verify_path("/achievements/verify_default"));
+        assert!(!is_verify_path("/get_completed_quests"));
+        assert!(!is_verify_path("/quests/claimable"));
+    }
+}
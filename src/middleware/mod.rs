@@ -1 +1,3 @@
+pub mod address_ip;
 pub mod auth;
+pub mod rate_limit;
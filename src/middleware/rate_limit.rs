@@ -0,0 +1,348 @@
+use crate::config::{RateLimit, RateLimitGroup};
+use crate::logger::Logger;
+use async_trait::async_trait;
+use axum::{
+    extract::{Query, State},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use axum_client_ip::SecureClientIp;
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, Database, IndexModel,
+};
+use serde_json::json;
+use starknet::core::types::FieldElement;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const RATE_LIMITS_COLLECTION: &str = "rate_limits";
+
+// the in-memory store drops idle buckets once it holds that many
+const MAX_MEMORY_BUCKETS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub capacity: f64,
+    pub refill_per_second: f64,
+}
+
+impl Bucket {
+    fn new(capacity: u32, refill_per_minute: u32) -> Option<Self> {
+        match capacity {
+            0 => None,
+            _ => Some(Bucket {
+                capacity: capacity as f64,
+                refill_per_second: refill_per_minute as f64 / 60.0,
+            }),
+        }
+    }
+
+    // time after which the bucket is full again, idle buckets can be dropped after it
+    fn refill_ms(&self) -> i64 {
+        match self.refill_per_second > 0.0 {
+            true => (self.capacity / self.refill_per_second * 1000.0).ceil() as i64,
+            false => i64::MAX / 2,
+        }
+    }
+}
+
+// Takes a token from a bucket holding tokens at updated_at (full when unknown). Returns the
+// remaining tokens, or the milliseconds to wait before a token is available.
+pub fn take_token(
+    bucket: &Bucket,
+    tokens: Option<f64>,
+    updated_at: i64,
+    now: i64,
+) -> Result<f64, i64> {
+    let elapsed = (now - updated_at).max(0) as f64 / 1000.0;
+    let tokens = tokens
+        .map(|tokens| (tokens + elapsed * bucket.refill_per_second).min(bucket.capacity))
+        .unwrap_or(bucket.capacity);
+    if tokens >= 1.0 {
+        return Ok(tokens - 1.0);
+    }
+    match bucket.refill_per_second > 0.0 {
+        true => Err(((1.0 - tokens) / bucket.refill_per_second * 1000.0).ceil() as i64),
+        false => Err(i64::MAX / 2),
+    }
+}
+
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    // Ok(None) when the request is allowed, Ok(Some(retry_after_ms)) when the bucket is empty
+    async fn take(&self, key: &str, bucket: &Bucket, now: i64) -> Result<Option<i64>, String>;
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    // key -> (tokens, updated_at, expires_at)
+    buckets: Mutex<HashMap<String, (f64, i64, i64)>>,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(&self, key: &str, bucket: &Bucket, now: i64) -> Result<Option<i64>, String> {
+        let mut buckets = self.buckets.lock().map_err(|e| e.to_string())?;
+        if buckets.len() >= MAX_MEMORY_BUCKETS {
+            buckets.retain(|_, (_, _, expires_at)| *expires_at > now);
+        }
+        let (tokens, updated_at) = match buckets.get(key) {
+            Some((tokens, updated_at, _)) => (Some(*tokens), *updated_at),
+            None => (None, now),
+        };
+        match take_token(bucket, tokens, updated_at, now) {
+            Ok(tokens) => {
+                buckets.insert(key.to_string(), (tokens, now, now + bucket.refill_ms()));
+                Ok(None)
+            }
+            Err(retry_after) => Ok(Some(retry_after)),
+        }
+    }
+}
+
+// Shares the buckets between instances, each take is a single atomic update
+pub struct MongoStore {
+    collection: Collection<Document>,
+}
+
+impl MongoStore {
+    pub async fn new(db: &Database) -> Result<Self, mongodb::error::Error> {
+        let collection = db.collection::<Document>(RATE_LIMITS_COLLECTION);
+        let expiry = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+        collection.create_index(expiry, None).await?;
+        Ok(MongoStore { collection })
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MongoStore {
+    async fn take(&self, key: &str, bucket: &Bucket, now: i64) -> Result<Option<i64>, String> {
+        // same computation as take_token, written as an update pipeline
+        let refilled = doc! {
+            "$min": [
+                bucket.capacity,
+                {
+                    "$add": [
+                        { "$ifNull": ["$tokens", bucket.capacity] },
+                        {
+                            "$multiply": [
+                                { "$divide": [{ "$subtract": [now, { "$ifNull": ["$updated_at", now] }] }, 1000] },
+                                bucket.refill_per_second
+                            ]
+                        }
+                    ]
+                }
+            ]
+        };
+        let pipeline = vec![
+            doc! { "$set": { "tokens": refilled } },
+            doc! {
+                "$set": {
+                    "allowed": { "$gte": ["$tokens", 1] },
+                    "tokens": {
+                        "$cond": [{ "$gte": ["$tokens", 1] }, { "$subtract": ["$tokens", 1] }, "$tokens"]
+                    },
+                    "updated_at": now,
+                    "expires_at": mongodb::bson::DateTime::from_millis(now.saturating_add(bucket.refill_ms())),
+                }
+            },
+        ];
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let result = self
+            .collection
+            .find_one_and_update(doc! { "_id": key }, pipeline, options)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "bucket not returned".to_string())?;
+        if result.get_bool("allowed").unwrap_or(true) {
+            return Ok(None);
+        }
+        let tokens = result.get_f64("tokens").unwrap_or(0.0);
+        match take_token(bucket, Some(tokens), now, now) {
+            Ok(_) => Ok(None),
+            Err(retry_after) => Ok(Some(retry_after)),
+        }
+    }
+}
+
+struct RouteGroup {
+    name: String,
+    paths: Vec<String>,
+    per_ip: Option<Bucket>,
+    per_address: Option<Bucket>,
+}
+
+pub struct RateLimiter {
+    groups: Vec<RouteGroup>,
+    store: Box<dyn RateLimitStore>,
+    logger: Logger,
+}
+
+// matches a path against a pattern where * matches any characters, including /
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        if index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+impl RateLimiter {
+    pub async fn new(conf: &RateLimit, db: &Database, logger: Logger) -> Result<Self, String> {
+        let store: Box<dyn RateLimitStore> = match conf.store.as_str() {
+            "memory" => Box::<MemoryStore>::default(),
+            "mongo" => Box::new(
+                MongoStore::new(db)
+                    .await
+                    .map_err(|e| format!("Unable to create rate limit store: {}", e))?,
+            ),
+            store => return Err(format!("Unknown rate limit store {}", store)),
+        };
+        let groups = conf
+            .groups
+            .iter()
+            .map(|group: &RateLimitGroup| RouteGroup {
+                name: group.name.clone(),
+                paths: group.paths.clone(),
+                per_ip: Bucket::new(group.ip_capacity, group.ip_refill_per_minute),
+                per_address: Bucket::new(group.address_capacity, group.address_refill_per_minute),
+            })
+            .collect();
+        Ok(RateLimiter {
+            groups,
+            store,
+            logger,
+        })
+    }
+
+    // returns the milliseconds to wait when one of the buckets of the request is empty
+    async fn check(&self, path: &str, ip: &str, addr: Option<FieldElement>) -> Option<i64> {
+        let group = self.groups.iter().find(|group| {
+            group
+                .paths
+                .iter()
+                .any(|pattern| path_matches(pattern, path))
+        })?;
+        let now = Utc::now().timestamp_millis();
+        let mut keys = Vec::new();
+        if let Some(bucket) = group.per_ip {
+            keys.push((format!("{}:ip:{}", group.name, ip), bucket));
+        }
+        if let (Some(bucket), Some(addr)) = (group.per_address, addr) {
+            keys.push((format!("{}:addr:{}", group.name, addr), bucket));
+        }
+        for (key, bucket) in keys {
+            match self.store.take(&key, &bucket, now).await {
+                Ok(Some(retry_after)) => return Some(retry_after),
+                Ok(None) => {}
+                // the limiter must not take the api down with it
+                Err(e) => self
+                    .logger
+                    .warning(format!("Rate limit store error for {}: {}", key, e)),
+            }
+        }
+        None
+    }
+}
+
+pub async fn rate_limit_middleware<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    client_ip: SecureClientIp,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let addr = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(params)| params.get("addr").cloned())
+        .and_then(|addr| FieldElement::from_str(&addr).ok());
+    let ip = client_ip.0.to_string();
+    match limiter.check(req.uri().path(), &ip, addr).await {
+        Some(retry_after_ms) => {
+            let retry_after = (retry_after_ms as f64 / 1000.0).ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(json!({
+                    "error": "Too many requests, please retry later",
+                    "retry_after": retry_after,
+                })),
+            )
+                .into_response()
+        }
+        None => next.run(req).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUCKET: Bucket = Bucket {
+        capacity: 2.0,
+        refill_per_second: 0.5,
+    };
+
+    #[test]
+    fn bucket_empties_and_refills() {
+        let tokens = take_token(&BUCKET, None, 0, 0).unwrap();
+        let tokens = take_token(&BUCKET, Some(tokens), 0, 0).unwrap();
+        assert_eq!(tokens, 0.0);
+        assert_eq!(take_token(&BUCKET, Some(tokens), 0, 0), Err(2000));
+        assert_eq!(take_token(&BUCKET, Some(tokens), 0, 1000), Err(1000));
+        assert_eq!(take_token(&BUCKET, Some(tokens), 0, 2000), Ok(0.0));
+        // refills never exceed the capacity
+        assert_eq!(take_token(&BUCKET, Some(0.0), 0, 60_000), Ok(1.0));
+    }
+
+    #[test]
+    fn matches_route_patterns() {
+        assert!(path_matches(
+            "/quests/*verify*",
+            "/quests/zklend/verify_borrow"
+        ));
+        assert!(path_matches("/quests/*verify*", "/quests/verify_quiz"));
+        assert!(!path_matches("/quests/*verify*", "/quests/claimable"));
+        assert!(path_matches(
+            "/achievements/verify_*",
+            "/achievements/verify_briq"
+        ));
+        assert!(path_matches("/get_quests", "/get_quests"));
+        assert!(!path_matches("/get_quests", "/get_quests_old"));
+    }
+
+    #[tokio::test]
+    async fn memory_store_limits_each_key() {
+        let store = MemoryStore::default();
+        assert_eq!(store.take("a", &BUCKET, 0).await, Ok(None));
+        assert_eq!(store.take("a", &BUCKET, 0).await, Ok(None));
+        assert_eq!(store.take("a", &BUCKET, 0).await, Ok(Some(2000)));
+        assert_eq!(store.take("b", &BUCKET, 0).await, Ok(None));
+    }
+}
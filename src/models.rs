@@ -7,7 +7,11 @@ use starknet::{
 };
 
 use crate::endpoints::quests::uri::Attribute;
-use crate::{config::Config, logger::Logger};
+use crate::{
+    config::Config, http_client::HttpClient, image_store::ImageStore, logger::Logger,
+    reward_providers::RewardProviders, reward_signer::RewardSigner,
+};
+use std::sync::Arc;
 use tokio::sync::Mutex;
 
 pub_struct!(;AppState {
@@ -17,6 +21,13 @@ pub_struct!(;AppState {
     provider: JsonRpcClient<HttpTransport>,
     db: Database,
     logger: Logger,
+    http: HttpClient,
+    nft_signer: Arc<dyn RewardSigner>,
+    boost_signer: Arc<dyn RewardSigner>,
+    images: Arc<dyn ImageStore>,
+    reward_providers: RewardProviders,
+    // font of the share cards, checked at startup
+    share_card_font: Arc<Vec<u8>>,
 });
 
 pub_struct!(Debug, Serialize, Deserialize; NFTItem {
@@ -47,9 +58,28 @@ pub_struct!(Debug, Serialize, Deserialize; QuestDocument {
     expired: Option<bool>,
     experience: i64,
     start_time: i64,
-    banner: Option<Banner>
+    banner: Option<Banner>,
+    prerequisites: Option<Vec<QuestPrerequisite>>,
 });
 
+// A requirement that must be completed before a quest unlocks. Quest prerequisites can point to
+// quests of previous seasons, which is how quest chains are built.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestPrerequisite {
+    Quest { id: u32 },
+    Achievement { id: u32 },
+}
+
+impl std::fmt::Display for QuestPrerequisite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuestPrerequisite::Quest { id } => write!(f, "quest {}", id),
+            QuestPrerequisite::Achievement { id } => write!(f, "achievement {}", id),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Banner {
     pub tag: String,
@@ -80,6 +110,7 @@ pub_struct!(Debug, Serialize, Deserialize; QuestInsertDocument {
     mandatory_domain: Option<String>,
     experience: i32,
     start_time: i64,
+    prerequisites: Option<Vec<QuestPrerequisite>>,
 });
 
 pub_struct!(Debug, Serialize, Deserialize;  QuizInsertDocument {
@@ -99,13 +130,28 @@ pub_struct!(Debug, Serialize, Deserialize; QuizQuestionDocument {
     quiz_id: i64,
 });
 
+// Tasks a user must complete to claim an NFT level. Both conditions apply when both are set, a
+// level without conditions requires every task of the quest.
+pub_struct!(Debug, Clone, Serialize, Deserialize, PartialEq; NftUnlock {
+    // at least min_tasks of among, or of every task of the quest when among is not set
+    min_tasks: Option<u32>,
+    among: Option<Vec<u32>>,
+    // tasks which must all be completed, e.g. optional bonus tasks
+    required_tasks: Option<Vec<u32>>,
+});
+
 pub_struct!(Serialize, Deserialize; NFTUri {
     id: i64,
     name: String,
     description:String,
     image: String,
+    animation_url: Option<String>,
     quest_id: i64,
-    attributes: Option<Attribute>
+    attributes: Option<Attribute>,
+    unlock: Option<NftUnlock>,
+    // task the level is signed for, levels of the same quest need distinct tasks. Defaults to the
+    // last task of the quest.
+    claim_task_id: Option<u32>,
 });
 
 pub_struct!(Deserialize; CompletedTasks {
@@ -170,9 +216,13 @@ pub struct QuestTaskDocument {
 
 pub_struct!(Serialize; Reward {
     task_id: u32,
+    level: u32,
     nft_contract: String,
     token_id: String,
     sig: (FieldElement, FieldElement),
+    // set when the contract verifies SNIP-12 claims
+    deadline: Option<i64>,
+    nonce: Option<FieldElement>,
 });
 
 pub_struct!(Serialize; RewardResponse {
@@ -276,15 +326,15 @@ pub_struct!(Debug, Serialize, Deserialize; UserAchievement {
     img_url: String,
 });
 
-pub_struct!(Debug, Serialize, Deserialize; UserExperience {
-    address: String,
-    experience:i64,
-    timestamp:i64,
-});
-
-pub_struct!(Debug, Serialize, Deserialize; LeaderboardTable {
-    experience:i64,
-    timestamp:f64,
+pub_struct!(Debug, Serialize, Deserialize; SeasonDocument {
+    id: u32,
+    name: String,
+    start_time: i64,
+    end_time: i64,
+    // quest categories earning season experience, all categories when empty
+    categories: Vec<String>,
+    frozen_at: Option<i64>,
+    total_users: Option<i64>,
 });
 
 pub_struct!(Debug, Serialize, Deserialize; BoostTable {
@@ -299,6 +349,9 @@ pub_struct!(Debug, Serialize, Deserialize; BoostTable {
     hidden: bool,
     num_of_winners: i32,
     token_decimals: i32,
+    status: Option<String>,
+    // overrides the sybil boost_threshold of the config
+    max_risk_score: Option<f64>,
 });
 
 pub_struct!(Debug, Serialize, Deserialize; NftBalance {
@@ -505,16 +558,7 @@ pub struct Claim {
     pub claimee: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub enum RewardSource {
-    ZkLend,
-    Nostra,
-    Nimbora,
-    Ekubo,
-    Vesu,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommonReward {
     pub amount: FieldElement,
     pub displayed_amount: FieldElement,
@@ -522,7 +566,8 @@ pub struct CommonReward {
     pub reward_id: Option<u64>,
     pub claim_contract: FieldElement,
     pub token_symbol: String,
-    pub reward_source: RewardSource,
+    // name of the provider it comes from
+    pub reward_source: String,
     pub claimed: bool,
     pub start_date: Option<String>,
     pub end_date: Option<String>,
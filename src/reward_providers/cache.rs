@@ -0,0 +1,83 @@
+use crate::models::CommonReward;
+use mongodb::{
+    bson::{doc, from_bson, to_bson, DateTime, Document},
+    options::{IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+use std::time::Duration;
+
+// last rewards successfully fetched for each (address, provider), served while the provider
+// is unavailable
+pub const REWARDS_CACHE_COLLECTION: &str = "defi_rewards_cache";
+
+pub async fn ensure_rewards_cache_indexes(
+    db: &Database,
+    max_age: u64,
+) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(REWARDS_CACHE_COLLECTION);
+    let key = IndexModel::builder()
+        .keys(doc! { "address": 1, "provider": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    collection.create_index(key, None).await?;
+    let expiry = IndexModel::builder()
+        .keys(doc! { "updated_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(max_age))
+                .build(),
+        )
+        .build();
+    collection.create_index(expiry, None).await?;
+    Ok(())
+}
+
+pub async fn save_rewards(
+    db: &Database,
+    address: &str,
+    provider: &str,
+    rewards: &[CommonReward],
+) -> Result<(), String> {
+    let rewards = to_bson(rewards).map_err(|e| e.to_string())?;
+    let options = UpdateOptions::builder().upsert(true).build();
+    db.collection::<Document>(REWARDS_CACHE_COLLECTION)
+        .update_one(
+            doc! { "address": address, "provider": provider },
+            doc! { "$set": { "rewards": rewards, "updated_at": DateTime::now() } },
+            options,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// rewards saved less than max_age seconds ago and when they were saved, in ms
+pub async fn get_cached_rewards(
+    db: &Database,
+    address: &str,
+    provider: &str,
+    max_age: u64,
+) -> Result<Option<(Vec<CommonReward>, i64)>, String> {
+    // expired documents can outlive their ttl until mongodb purges them
+    let oldest = DateTime::from_millis(DateTime::now().timestamp_millis() - max_age as i64 * 1000);
+    let cached = db
+        .collection::<Document>(REWARDS_CACHE_COLLECTION)
+        .find_one(
+            doc! { "address": address, "provider": provider, "updated_at": { "$gte": oldest } },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(cached) = cached else {
+        return Ok(None);
+    };
+    let rewards = cached
+        .get("rewards")
+        .ok_or("missing rewards")
+        .and_then(|rewards| from_bson(rewards.clone()).map_err(|_| "invalid rewards"))?;
+    let updated_at = cached
+        .get_datetime("updated_at")
+        .map_err(|e| e.to_string())?
+        .timestamp_millis();
+    Ok(Some((rewards, updated_at)))
+}
@@ -0,0 +1,163 @@
+use crate::config::{Config, Token};
+use crate::models::{AppState, CommonReward, ContractCall, EkuboRewards};
+use crate::reward_providers::{
+    decode, get_headers, get_id_claim_call, get_json, RewardError, RewardProvider,
+};
+use crate::utils::{read_contract, to_hex};
+use async_trait::async_trait;
+use serde_json::Value;
+use starknet::{core::types::FieldElement, macros::selector};
+
+pub struct EkuboProvider {
+    token: Token,
+}
+
+impl EkuboProvider {
+    pub fn new(conf: &Config) -> Self {
+        EkuboProvider {
+            token: conf.tokens.strk.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl RewardProvider for EkuboProvider {
+    fn name(&self) -> &'static str {
+        "ekubo"
+    }
+
+    async fn fetch(&self, state: &AppState, addr: &str) -> Result<Value, RewardError> {
+        let url = format!(
+            "https://mainnet-api.ekubo.org/airdrops/{}?token={}",
+            addr,
+            to_hex(self.token.contract)
+        );
+        let response = state
+            .http
+            .send(state.http.get(&url).headers(get_headers()))
+            .await?;
+        get_json(&response)
+    }
+
+    fn normalize(&self, _addr: &str, payload: Value) -> Result<Vec<CommonReward>, RewardError> {
+        let rewards: Vec<EkuboRewards> = decode(payload)?;
+        Ok(rewards
+            .into_iter()
+            .map(|reward| CommonReward {
+                amount: reward.claim.amount,
+                displayed_amount: reward.claim.amount,
+                proof: reward.proof,
+                reward_id: Some(reward.claim.id),
+                claim_contract: reward.contract_address,
+                token_symbol: self.token.symbol.clone(),
+                reward_source: self.name().to_string(),
+                claimed: false,
+                start_date: Some(reward.start_date),
+                end_date: Some(reward.end_date),
+            })
+            .collect())
+    }
+
+    async fn is_claimed(
+        &self,
+        state: &AppState,
+        _addr: &str,
+        reward: &CommonReward,
+    ) -> Result<bool, RewardError> {
+        let claimed = read_contract(
+            state,
+            reward.claim_contract,
+            selector!("is_claimed"),
+            vec![FieldElement::from(reward.reward_id.unwrap_or_default())],
+        )
+        .await
+        .map_err(|e| RewardError::Contract(e.to_string()))?;
+        Ok(claimed.first() != Some(&FieldElement::ZERO))
+    }
+
+    // an older airdrop would be taken for the latest one
+    fn selects_from_all(&self) -> bool {
+        true
+    }
+
+    // Nothing is left to claim once the latest airdrop is claimed. Otherwise only the latest
+    // airdrop of each period is kept.
+    fn select(&self, rewards: Vec<CommonReward>) -> Vec<CommonReward> {
+        if !matches!(rewards.last(), Some(latest) if !latest.claimed) {
+            return vec![];
+        }
+        rewards
+            .into_iter()
+            .rev()
+            .filter(|reward| !reward.claimed)
+            .fold(Vec::<CommonReward>::new(), |mut acc, reward| {
+                if !acc
+                    .iter()
+                    .any(|r| r.start_date == reward.start_date && r.end_date == reward.end_date)
+                {
+                    acc.push(reward);
+                }
+                acc
+            })
+    }
+
+    fn get_claim_call(&self, addr: &str, reward: &CommonReward) -> ContractCall {
+        get_id_claim_call(addr, reward)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_reward(id: u64, period: &str, claimed: bool) -> CommonReward {
+        CommonReward {
+            amount: FieldElement::from(id),
+            displayed_amount: FieldElement::from(id),
+            proof: vec![],
+            reward_id: Some(id),
+            claim_contract: FieldElement::ONE,
+            token_symbol: "STRK".to_string(),
+            reward_source: "ekubo".to_string(),
+            claimed,
+            start_date: Some(period.to_string()),
+            end_date: Some(period.to_string()),
+        }
+    }
+
+    fn get_provider() -> EkuboProvider {
+        EkuboProvider {
+            token: Token {
+                contract: FieldElement::ONE,
+                symbol: "STRK".to_string(),
+                decimals: 18,
+            },
+        }
+    }
+
+    #[test]
+    fn keeps_latest_airdrop_of_each_period() {
+        let rewards = vec![
+            get_reward(1, "2024-03", false),
+            get_reward(2, "2024-04", true),
+            get_reward(3, "2024-04", false),
+            get_reward(4, "2024-04", false),
+        ];
+        let ids: Vec<Option<u64>> = get_provider()
+            .select(rewards)
+            .iter()
+            .map(|reward| reward.reward_id)
+            .collect();
+        assert_eq!(ids, vec![Some(4), Some(1)]);
+    }
+
+    #[test]
+    fn drops_everything_once_latest_is_claimed() {
+        let rewards = vec![
+            get_reward(1, "2024-03", false),
+            get_reward(2, "2024-04", true),
+        ];
+        assert!(get_provider().select(rewards).is_empty());
+        assert!(get_provider().select(vec![]).is_empty());
+    }
+}
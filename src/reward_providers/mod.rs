@@ -0,0 +1,392 @@
+pub mod cache;
+pub mod ekubo;
+pub mod nimbora;
+pub mod nostra;
+pub mod vesu;
+pub mod zklend;
+
+use crate::config::Config;
+use crate::http_client::{HttpError, HttpResponse};
+use crate::models::{AppState, CommonReward, ContractCall};
+use crate::reward_providers::cache::{get_cached_rewards, save_rewards};
+use crate::utils::{to_hex, to_hex_trimmed};
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+use serde::Serialize;
+use serde_json::Value;
+use starknet::core::types::FieldElement;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum RewardError {
+    Timeout(Duration),
+    Request(String),
+    Decode(String),
+    Contract(String),
+}
+
+impl fmt::Display for RewardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewardError::Timeout(timeout) => {
+                write!(f, "No response after {}ms", timeout.as_millis())
+            }
+            RewardError::Request(e) => write!(f, "Failed to send request: {}", e),
+            RewardError::Decode(e) => write!(f, "Failed to decode response: {}", e),
+            RewardError::Contract(e) => write!(f, "Failed to read contract: {}", e),
+        }
+    }
+}
+
+impl RewardError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RewardError::Timeout(_) => "timeout",
+            RewardError::Request(_) => "request",
+            RewardError::Decode(_) => "decode",
+            RewardError::Contract(_) => "contract",
+        }
+    }
+}
+
+impl From<HttpError> for RewardError {
+    fn from(e: HttpError) -> Self {
+        match e {
+            HttpError::Decode(e) => RewardError::Decode(e),
+            e => RewardError::Request(e.to_string()),
+        }
+    }
+}
+
+// body of a response, an error status of the upstream is a failed request
+pub fn get_json(response: &HttpResponse) -> Result<Value, RewardError> {
+    if !response.status().is_success() {
+        return Err(RewardError::Request(format!(
+            "Upstream returned {}",
+            response.status()
+        )));
+    }
+    Ok(response.json()?)
+}
+
+pub fn decode<T: serde::de::DeserializeOwned>(payload: Value) -> Result<T, RewardError> {
+    serde_json::from_value(payload).map_err(|e| RewardError::Decode(e.to_string()))
+}
+
+// A protocol distributing rewards. Rewards are fetched from its api, normalized, checked
+// against the claim contracts and turned into the calls claiming them.
+#[async_trait]
+pub trait RewardProvider: Send + Sync {
+    // key of the provider in the responses
+    fn name(&self) -> &'static str;
+
+    // raw data of the protocol about addr
+    async fn fetch(&self, state: &AppState, addr: &str) -> Result<Value, RewardError>;
+
+    fn normalize(&self, addr: &str, payload: Value) -> Result<Vec<CommonReward>, RewardError>;
+
+    // the claimed flag set by normalize is kept for protocols whose api already tracks claims
+    async fn is_claimed(
+        &self,
+        _state: &AppState,
+        _addr: &str,
+        reward: &CommonReward,
+    ) -> Result<bool, RewardError> {
+        Ok(reward.claimed)
+    }
+
+    // whether select depends on the whole list, the provider then fails when a claim status is
+    // unknown instead of leaving the reward out
+    fn selects_from_all(&self) -> bool {
+        false
+    }
+
+    // rewards worth claiming once their claimed flag is set
+    fn select(&self, rewards: Vec<CommonReward>) -> Vec<CommonReward> {
+        rewards
+            .into_iter()
+            .filter(|reward| !reward.claimed)
+            .collect()
+    }
+
+    fn get_claim_call(&self, addr: &str, reward: &CommonReward) -> ContractCall;
+}
+
+// claim(amount, proof)
+pub fn get_amount_claim_call(reward: &CommonReward) -> ContractCall {
+    let mut calldata = vec![
+        to_hex_trimmed(reward.amount),
+        to_hex_trimmed(FieldElement::from(reward.proof.len())),
+    ];
+    calldata.extend(reward.proof.clone());
+    ContractCall {
+        contractaddress: to_hex(reward.claim_contract),
+        calldata,
+        entrypoint: "claim".to_string(),
+    }
+}
+
+// claim(id, claimee, amount, proof)
+pub fn get_id_claim_call(addr: &str, reward: &CommonReward) -> ContractCall {
+    let mut calldata = vec![
+        to_hex_trimmed(FieldElement::from(reward.reward_id.unwrap_or_default())),
+        addr.to_string(),
+        to_hex_trimmed(reward.amount),
+        to_hex_trimmed(FieldElement::from(reward.proof.len())),
+    ];
+    calldata.extend(reward.proof.clone());
+    ContractCall {
+        contractaddress: to_hex(reward.claim_contract),
+        calldata,
+        entrypoint: "claim".to_string(),
+    }
+}
+
+pub fn get_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_static(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:89.0) Gecko/20100101 Firefox/89.0",
+        ),
+    );
+    headers
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceStatus {
+    Ok,
+    // the provider failed, its last known rewards are served instead
+    Stale,
+    // the provider failed and no rewards of addr were cached
+    Unavailable,
+}
+
+// how the rewards of a provider were obtained
+#[derive(Debug, Serialize)]
+pub struct RewardSource {
+    pub status: SourceStatus,
+    pub latency_ms: u64,
+    pub error: Option<&'static str>,
+    pub message: Option<String>,
+    // seconds since the rewards were fetched, null when there are none
+    pub data_age: Option<i64>,
+}
+
+pub struct ProviderRewards {
+    pub provider: Arc<dyn RewardProvider>,
+    pub rewards: Vec<CommonReward>,
+    pub source: RewardSource,
+}
+
+// rewards to serve when a provider failed, from its cached rewards saved at updated_at (in ms)
+pub fn get_fallback(
+    error: &RewardError,
+    latency_ms: u64,
+    cached: Option<(Vec<CommonReward>, i64)>,
+    now: i64,
+) -> (Vec<CommonReward>, RewardSource) {
+    let (status, rewards, data_age) = match cached {
+        Some((rewards, updated_at)) => (
+            SourceStatus::Stale,
+            rewards,
+            Some((now - updated_at).max(0) / 1000),
+        ),
+        None => (SourceStatus::Unavailable, vec![], None),
+    };
+    let source = RewardSource {
+        status,
+        latency_ms,
+        error: Some(error.kind()),
+        message: Some(error.to_string()),
+        data_age,
+    };
+    (rewards, source)
+}
+
+#[derive(Default)]
+pub struct RewardProviders {
+    providers: Vec<(Arc<dyn RewardProvider>, Duration)>,
+}
+
+impl RewardProviders {
+    // Providers disabled in the config are skipped, the others are given up on after their
+    // timeout
+    pub fn register<P: RewardProvider + 'static>(&mut self, conf: &Config, provider: P) {
+        let timeout_ms = match conf.rewards.providers.get(provider.name()) {
+            Some(provider_conf) if !provider_conf.enabled => return,
+            Some(provider_conf) => provider_conf.timeout_ms,
+            None => conf.rewards.timeout_ms,
+        };
+        self.providers
+            .push((Arc::new(provider), Duration::from_millis(timeout_ms)));
+    }
+
+    // Claimable rewards of addr for every provider, in registration order. The rewards of a
+    // provider are cached when it succeeds and served from the cache when it fails.
+    pub async fn get_rewards(&self, state: &AppState, addr: &str) -> Vec<ProviderRewards> {
+        join_all(self.providers.iter().map(|(provider, timeout)| async move {
+            let start = Instant::now();
+            let result = match tokio::time::timeout(
+                *timeout,
+                get_provider_rewards(&**provider, state, addr),
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(_) => Err(RewardError::Timeout(*timeout)),
+            };
+            let latency_ms = start.elapsed().as_millis() as u64;
+            let (rewards, source) = match result {
+                Ok(rewards) => {
+                    // the response doesn't wait for the cache
+                    let (db, logger) = (state.db.clone(), state.logger.clone());
+                    let (address, name, saved) =
+                        (addr.to_string(), provider.name(), rewards.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = save_rewards(&db, &address, name, &saved).await {
+                            logger.warning(format!("Unable to cache rewards of {}: {}", name, e));
+                        }
+                    });
+                    let source = RewardSource {
+                        status: SourceStatus::Ok,
+                        latency_ms,
+                        error: None,
+                        message: None,
+                        data_age: Some(0),
+                    };
+                    (rewards, source)
+                }
+                Err(e) => {
+                    state.logger.warning(format!(
+                        "reward provider {} failed: {}",
+                        provider.name(),
+                        e
+                    ));
+                    let cached = get_cached_rewards(
+                        &state.db,
+                        addr,
+                        provider.name(),
+                        state.conf.rewards.cache_max_age,
+                    )
+                    .await
+                    .unwrap_or_else(|e| {
+                        state.logger.warning(format!(
+                            "Unable to read cached rewards of {}: {}",
+                            provider.name(),
+                            e
+                        ));
+                        None
+                    });
+                    get_fallback(
+                        &e,
+                        latency_ms,
+                        cached,
+                        chrono::Utc::now().timestamp_millis(),
+                    )
+                }
+            };
+            ProviderRewards {
+                provider: provider.clone(),
+                rewards,
+                source,
+            }
+        }))
+        .await
+    }
+}
+
+async fn get_provider_rewards(
+    provider: &dyn RewardProvider,
+    state: &AppState,
+    addr: &str,
+) -> Result<Vec<CommonReward>, RewardError> {
+    let payload = provider.fetch(state, addr).await?;
+    let rewards = provider.normalize(addr, payload)?;
+    let claimed = join_all(
+        rewards
+            .iter()
+            .map(|reward| provider.is_claimed(state, addr, reward)),
+    )
+    .await;
+    // a reward whose claim status is unknown is left out, the others are still returned
+    let mut checked = Vec::with_capacity(rewards.len());
+    for (mut reward, claimed) in rewards.into_iter().zip(claimed) {
+        match claimed {
+            Ok(claimed) => {
+                reward.claimed = claimed;
+                checked.push(reward);
+            }
+            Err(e) if provider.selects_from_all() => return Err(e),
+            Err(e) => state.logger.warning(format!(
+                "Unable to check a {} reward of {}: {}",
+                provider.name(),
+                addr,
+                e
+            )),
+        }
+    }
+    Ok(provider.select(checked))
+}
+
+pub fn get_reward_providers(conf: &Config) -> RewardProviders {
+    let mut providers = RewardProviders::default();
+    providers.register(conf, zklend::ZkLendProvider);
+    providers.register(conf, nostra::NostraProvider::new(conf));
+    providers.register(conf, nimbora::NimboraProvider::new(conf));
+    providers.register(conf, ekubo::EkuboProvider::new(conf));
+    providers.register(conf, vesu::VesuProvider::new(conf));
+    providers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_reward() -> CommonReward {
+        CommonReward {
+            amount: FieldElement::ONE,
+            displayed_amount: FieldElement::ONE,
+            proof: vec![],
+            reward_id: None,
+            claim_contract: FieldElement::ONE,
+            token_symbol: "STRK".to_string(),
+            reward_source: "nostra".to_string(),
+            claimed: false,
+            start_date: None,
+            end_date: None,
+        }
+    }
+
+    #[test]
+    fn serves_cached_rewards_as_stale() {
+        let error = RewardError::Timeout(Duration::from_millis(5000));
+        let (rewards, source) = get_fallback(
+            &error,
+            5001,
+            Some((vec![get_reward()], 1_000_000)),
+            1_090_500,
+        );
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(source.status, SourceStatus::Stale);
+        assert_eq!(source.error, Some("timeout"));
+        assert_eq!(source.message.as_deref(), Some("No response after 5000ms"));
+        assert_eq!(source.data_age, Some(90));
+    }
+
+    #[test]
+    fn reports_unavailable_without_cache() {
+        let error = RewardError::Request("connection refused".to_string());
+        let (rewards, source) = get_fallback(&error, 12, None, 0);
+        assert!(rewards.is_empty());
+        assert_eq!(source.status, SourceStatus::Unavailable);
+        assert_eq!(source.error, Some("request"));
+        assert_eq!(source.latency_ms, 12);
+        assert_eq!(source.data_age, None);
+    }
+}
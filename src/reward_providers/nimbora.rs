@@ -0,0 +1,81 @@
+use crate::config::Config;
+use crate::models::{AppState, CommonReward, ContractCall, NimboraRewards};
+use crate::reward_providers::{
+    decode, get_amount_claim_call, get_headers, get_json, RewardError, RewardProvider,
+};
+use crate::utils::{read_contract, to_hex};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use starknet::{core::types::FieldElement, macros::selector};
+
+pub struct NimboraProvider {
+    contract: FieldElement,
+    token_symbol: String,
+}
+
+impl NimboraProvider {
+    pub fn new(conf: &Config) -> Self {
+        NimboraProvider {
+            contract: conf.rewards.nimbora.contract,
+            token_symbol: conf.tokens.strk.symbol.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl RewardProvider for NimboraProvider {
+    fn name(&self) -> &'static str {
+        "nimbora"
+    }
+
+    // the total allocated to the account and what it already claimed from it
+    async fn fetch(&self, state: &AppState, addr: &str) -> Result<Value, RewardError> {
+        let url = format!(
+            "https://strk-dist-backend.nimbora.io/get_calldata?address={}",
+            addr
+        );
+        let response = state
+            .http
+            .send(state.http.get(&url).headers(get_headers()))
+            .await?;
+        let addr =
+            FieldElement::from_hex_be(addr).map_err(|e| RewardError::Contract(e.to_string()))?;
+        let claimed = read_contract(
+            state,
+            self.contract,
+            selector!("amount_already_claimed"),
+            vec![addr],
+        )
+        .await
+        .map_err(|e| RewardError::Contract(e.to_string()))?;
+        let claimed_amount = claimed.first().copied().unwrap_or_default();
+        Ok(json!({
+            "reward": get_json(&response)?,
+            "claimed_amount": to_hex(claimed_amount),
+        }))
+    }
+
+    fn normalize(&self, _addr: &str, mut payload: Value) -> Result<Vec<CommonReward>, RewardError> {
+        let reward: NimboraRewards = decode(payload["reward"].take())?;
+        let claimed_amount: FieldElement = decode(payload["claimed_amount"].take())?;
+        if claimed_amount == reward.amount {
+            return Ok(vec![]);
+        }
+        Ok(vec![CommonReward {
+            amount: reward.amount,
+            displayed_amount: reward.amount - claimed_amount,
+            proof: reward.proof,
+            reward_id: None,
+            claim_contract: self.contract,
+            token_symbol: self.token_symbol.clone(),
+            reward_source: self.name().to_string(),
+            claimed: false,
+            start_date: None,
+            end_date: None,
+        }])
+    }
+
+    fn get_claim_call(&self, _addr: &str, reward: &CommonReward) -> ContractCall {
+        get_amount_claim_call(reward)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::config::Config;
+use crate::models::{AppState, CommonReward, ContractCall, NostraPeriodsResponse, NostraResponse};
+use crate::reward_providers::{
+    decode, get_amount_claim_call, get_headers, get_json, RewardError, RewardProvider,
+};
+use crate::utils::read_contract;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use starknet::{core::types::FieldElement, macros::selector};
+
+const NOSTRA_API_URL: &str =
+    "https://us-east-2.aws.data.mongodb-api.com/app/data-yqlpb/endpoint/data/v1/action/find";
+
+pub struct NostraProvider {
+    token_symbol: String,
+}
+
+impl NostraProvider {
+    pub fn new(conf: &Config) -> Self {
+        NostraProvider {
+            token_symbol: conf.tokens.strk.symbol.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl RewardProvider for NostraProvider {
+    fn name(&self) -> &'static str {
+        "nostra"
+    }
+
+    // proofs of the account and the reward periods they belong to
+    async fn fetch(&self, state: &AppState, addr: &str) -> Result<Value, RewardError> {
+        let client = &state.http;
+        let proofs_body = json!({
+            "dataSource": "nostra-production",
+            "database": "prod-a-nostra-db",
+            "collection": "rewardProofs",
+            "filter": { "account": addr }
+        });
+        let periods_body = json!({
+            "dataSource": "nostra-production",
+            "database": "prod-a-nostra-db",
+            "collection": "rewardPeriods"
+        });
+        let (periods, proofs) = tokio::try_join!(
+            client.send(
+                client
+                    .post(NOSTRA_API_URL)
+                    .headers(get_headers())
+                    .json(&periods_body)
+            ),
+            client.send(
+                client
+                    .post(NOSTRA_API_URL)
+                    .headers(get_headers())
+                    .json(&proofs_body)
+            )
+        )?;
+        Ok(json!({
+            "periods": get_json(&periods)?,
+            "proofs": get_json(&proofs)?,
+        }))
+    }
+
+    // only the proofs of defi spring periods with a distributor can be claimed
+    fn normalize(&self, _addr: &str, mut payload: Value) -> Result<Vec<CommonReward>, RewardError> {
+        let periods: NostraPeriodsResponse = decode(payload["periods"].take())?;
+        let proofs: NostraResponse = decode(payload["proofs"].take())?;
+        Ok(proofs
+            .documents
+            .into_iter()
+            .rev()
+            .filter_map(|doc| {
+                let distributor = periods
+                    .documents
+                    .iter()
+                    .find(|period| period.id == doc.reward_id && period.defi_spring_rewards)
+                    .and_then(|period| period.defi_spring_rewards_distributor)?;
+                Some(CommonReward {
+                    amount: doc.reward,
+                    displayed_amount: doc.reward,
+                    proof: doc.proofs,
+                    reward_id: None,
+                    claim_contract: distributor,
+                    token_symbol: self.token_symbol.clone(),
+                    reward_source: self.name().to_string(),
+                    claimed: false,
+                    start_date: None,
+                    end_date: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn is_claimed(
+        &self,
+        state: &AppState,
+        addr: &str,
+        reward: &CommonReward,
+    ) -> Result<bool, RewardError> {
+        let addr =
+            FieldElement::from_hex_be(addr).map_err(|e| RewardError::Contract(e.to_string()))?;
+        let claimed = read_contract(
+            state,
+            reward.claim_contract,
+            selector!("amount_already_claimed"),
+            vec![addr],
+        )
+        .await
+        .map_err(|e| RewardError::Contract(e.to_string()))?;
+        Ok(claimed.first() != Some(&FieldElement::ZERO))
+    }
+
+    fn get_claim_call(&self, _addr: &str, reward: &CommonReward) -> ContractCall {
+        get_amount_claim_call(reward)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::config::Config;
+use crate::models::{AppState, CommonReward, ContractCall, VesuRewards};
+use crate::reward_providers::{
+    decode, get_amount_claim_call, get_headers, get_json, RewardError, RewardProvider,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use starknet::core::types::FieldElement;
+
+pub struct VesuProvider {
+    contract: FieldElement,
+    token_symbol: String,
+}
+
+impl VesuProvider {
+    pub fn new(conf: &Config) -> Self {
+        VesuProvider {
+            contract: conf.rewards.vesu.contract,
+            token_symbol: conf.tokens.strk.symbol.clone(),
+        }
+    }
+}
+
+fn parse_amount(amount: &str) -> Result<FieldElement, RewardError> {
+    amount
+        .parse()
+        .map_err(|e| RewardError::Decode(format!("invalid amount {}: {}", amount, e)))
+}
+
+#[async_trait]
+impl RewardProvider for VesuProvider {
+    fn name(&self) -> &'static str {
+        "vesu"
+    }
+
+    async fn fetch(&self, state: &AppState, addr: &str) -> Result<Value, RewardError> {
+        let url = format!("https://api.vesu.xyz/users/{}/strk-rewards", addr);
+        let response = state
+            .http
+            .send(state.http.get(&url).headers(get_headers()))
+            .await?;
+        get_json(&response)
+    }
+
+    // the api tracks the claimed amount, accounts without call data have nothing to claim
+    fn normalize(&self, _addr: &str, payload: Value) -> Result<Vec<CommonReward>, RewardError> {
+        let rewards: VesuRewards = decode(payload)?;
+        let distributor_data = rewards.data.distributor_data;
+        let Some(call_data) = distributor_data.call_data else {
+            return Ok(vec![]);
+        };
+        let distributed_amount = parse_amount(&distributor_data.distributed_amount)?;
+        let claimed_amount = parse_amount(&distributor_data.claimed_amount)?;
+        let amount = distributed_amount - claimed_amount;
+        if amount == FieldElement::ZERO {
+            return Ok(vec![]);
+        }
+        Ok(vec![CommonReward {
+            amount: distributed_amount,
+            displayed_amount: amount,
+            proof: call_data.proof,
+            reward_id: None,
+            claim_contract: self.contract,
+            token_symbol: self.token_symbol.clone(),
+            reward_source: self.name().to_string(),
+            claimed: false,
+            start_date: None,
+            end_date: None,
+        }])
+    }
+
+    fn get_claim_call(&self, _addr: &str, reward: &CommonReward) -> ContractCall {
+        get_amount_claim_call(reward)
+    }
+}
@@ -0,0 +1,47 @@
+use crate::models::{AppState, CommonReward, ContractCall, ZkLendReward};
+use crate::reward_providers::{
+    decode, get_headers, get_id_claim_call, get_json, RewardError, RewardProvider,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+pub struct ZkLendProvider;
+
+#[async_trait]
+impl RewardProvider for ZkLendProvider {
+    fn name(&self) -> &'static str {
+        "zklend"
+    }
+
+    async fn fetch(&self, state: &AppState, addr: &str) -> Result<Value, RewardError> {
+        let url = format!("https://app.zklend.com/api/reward/all/{}", addr);
+        let response = state
+            .http
+            .send(state.http.get(&url).headers(get_headers()))
+            .await?;
+        get_json(&response)
+    }
+
+    fn normalize(&self, _addr: &str, payload: Value) -> Result<Vec<CommonReward>, RewardError> {
+        let rewards: Vec<ZkLendReward> = decode(payload)?;
+        Ok(rewards
+            .into_iter()
+            .map(|reward| CommonReward {
+                amount: reward.amount.value,
+                displayed_amount: reward.amount.value,
+                proof: reward.proof,
+                reward_id: Some(reward.claim_id),
+                claim_contract: reward.claim_contract,
+                token_symbol: reward.token.symbol,
+                reward_source: self.name().to_string(),
+                claimed: reward.claimed,
+                start_date: None,
+                end_date: None,
+            })
+            .collect())
+    }
+
+    fn get_claim_call(&self, addr: &str, reward: &CommonReward) -> ContractCall {
+        get_id_claim_call(addr, reward)
+    }
+}
@@ -0,0 +1,209 @@
+use crate::config::SignerConfig;
+use crate::utils::get_secret;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use starknet::core::{crypto::Signature, types::FieldElement};
+use starknet::signers::SigningKey;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+
+// Signs the hashes of reward claims. The key can stay out of the API process with a remote
+// signer, which is called with
+//   POST <url>  Authorization: Bearer <token>  {"key_id": "nft", "hash": "0x..."}
+// and must answer 200 {"r": "0x...", "s": "0x..."} with the signature of hash.
+#[async_trait]
+pub trait RewardSigner: Send + Sync {
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<Signature, String>;
+}
+
+pub struct LocalSigner {
+    key: SigningKey,
+}
+
+#[async_trait]
+impl RewardSigner for LocalSigner {
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<Signature, String> {
+        self.key.sign(hash).map_err(|e| e.to_string())
+    }
+}
+
+// Key file encrypted with a passphrase (starkli keystore format). The file is decrypted again
+// when it is modified, so the key can be rotated by replacing it.
+pub struct KeystoreSigner {
+    path: PathBuf,
+    passphrase: String,
+    key: RwLock<(Option<SystemTime>, SigningKey)>,
+}
+
+impl KeystoreSigner {
+    pub async fn new(path: PathBuf, passphrase: String) -> Result<Self, String> {
+        let modified = get_modified(&path);
+        let key = decrypt_keystore(path.clone(), passphrase.clone()).await?;
+        Ok(KeystoreSigner {
+            path,
+            passphrase,
+            key: RwLock::new((modified, key)),
+        })
+    }
+
+    async fn get_key(&self) -> Result<SigningKey, String> {
+        let modified = get_modified(&self.path);
+        {
+            let key = self.key.read().await;
+            if key.0 == modified {
+                return Ok(key.1.clone());
+            }
+        }
+        let mut key = self.key.write().await;
+        if key.0 != modified {
+            *key = (
+                modified,
+                decrypt_keystore(self.path.clone(), self.passphrase.clone()).await?,
+            );
+        }
+        Ok(key.1.clone())
+    }
+}
+
+fn get_modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// decryption is deliberately slow, it mustn't block the runtime
+async fn decrypt_keystore(path: PathBuf, passphrase: String) -> Result<SigningKey, String> {
+    tokio::task::spawn_blocking(move || {
+        SigningKey::from_keystore(&path, &passphrase)
+            .map_err(|e| format!("Unable to decrypt {}: {}", path.display(), e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[async_trait]
+impl RewardSigner for KeystoreSigner {
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<Signature, String> {
+        self.get_key().await?.sign(hash).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest<'a> {
+    key_id: &'a str,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    r: FieldElement,
+    s: FieldElement,
+}
+
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    key_id: String,
+}
+
+#[async_trait]
+impl RewardSigner for RemoteSigner {
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<Signature, String> {
+        let mut request = self.client.post(&self.url).json(&RemoteSignRequest {
+            key_id: &self.key_id,
+            hash: format!("{:#x}", hash),
+        });
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Remote signer unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Remote signer returned {}", response.status()));
+        }
+        let signature: RemoteSignResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid remote signer response: {}", e))?;
+        Ok(Signature {
+            r: signature.r,
+            s: signature.s,
+        })
+    }
+}
+
+pub async fn get_reward_signer(conf: &SignerConfig) -> Result<Arc<dyn RewardSigner>, String> {
+    Ok(match conf {
+        SignerConfig::Local { private_key } => Arc::new(LocalSigner {
+            key: SigningKey::from_secret_scalar(*private_key),
+        }),
+        SignerConfig::Keystore {
+            path,
+            passphrase_env,
+        } => Arc::new(KeystoreSigner::new(PathBuf::from(path), get_secret(passphrase_env)?).await?),
+        SignerConfig::Remote {
+            url,
+            key_id,
+            token_env,
+            timeout_ms,
+        } => Arc::new(RemoteSigner {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(*timeout_ms))
+                .build()
+                .map_err(|e| e.to_string())?,
+            url: url.clone(),
+            token: token_env.as_deref().map(get_secret).transpose()?,
+            key_id: key_id.clone(),
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn calls_remote_signer() {
+        let app = Router::new().route(
+            "/sign",
+            post(|Json(body): Json<Value>| async move {
+                assert_eq!(body, json!({ "key_id": "nft", "hash": "0x2a" }));
+                Json(json!({ "r": "0x1", "s": "0x2" }))
+            }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let signer = get_reward_signer(&SignerConfig::Remote {
+            url: format!("http://{}/sign", addr),
+            key_id: "nft".to_string(),
+            token_env: None,
+            timeout_ms: 1000,
+        })
+        .await
+        .unwrap();
+        let signature = signer.sign_hash(&FieldElement::from(42u32)).await.unwrap();
+        assert_eq!(signature.r, FieldElement::ONE);
+        assert_eq!(signature.s, FieldElement::TWO);
+
+        let missing = get_reward_signer(&SignerConfig::Remote {
+            url: format!("http://{}/missing", addr),
+            key_id: "nft".to_string(),
+            token_env: None,
+            timeout_ms: 1000,
+        })
+        .await
+        .unwrap();
+        assert!(missing.sign_hash(&FieldElement::ONE).await.is_err());
+    }
+}
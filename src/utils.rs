@@ -1,8 +1,14 @@
-use crate::logger::Logger;
+use crate::common::quest_eligibility::{check_task_eligibility, EligibilityError};
+use crate::common::seasons::credit_season_experience;
+use crate::common::typed_data::{get_claim_expiry, get_claim_hash, get_nft_claim, get_signing};
+use crate::common::xp_ledger::{grant_experience, XpSource};
+use crate::config::{Config, SignatureScheme};
+use crate::http_client::HttpClient;
 use crate::models::{
-    AchievementDocument, AppState, BoostTable, CompletedTasks, LeaderboardTable, QuestDocument,
-    QuestTaskDocument, QuizQuestionDocument, RewardSource, UserExperience,
+    AchievementDocument, AppState, CompletedTasks, QuestDocument, QuestTaskDocument,
+    QuizQuestionDocument,
 };
+use crate::reward_signer::RewardSigner;
 use async_trait::async_trait;
 use axum::{
     body::Body,
@@ -14,25 +20,25 @@ use chrono::{Duration as dur, Utc};
 use futures::TryStreamExt;
 use mongodb::options::FindOneOptions;
 use mongodb::{
-    bson::doc, options::UpdateOptions, results::UpdateResult, Collection, Cursor, Database,
-    IndexModel,
+    bson::{doc, Bson, Document},
+    error::{ErrorKind, WriteFailure},
+    options::UpdateOptions,
+    results::UpdateResult,
+    Collection, Database,
 };
-use rand::distributions::{Distribution, Uniform};
-use starknet::signers::Signer;
 use starknet::{
     core::{
         crypto::{pedersen_hash, Signature},
         types::{BlockId, BlockTag, FieldElement, FunctionCall},
     },
     providers::{Provider, ProviderError},
-    signers::LocalWallet,
 };
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use std::result::Result;
 use std::str::FromStr;
 use std::{fmt::Write, sync::Arc};
-use tokio::time::{sleep, Duration};
 
 use regex::Regex;
 
@@ -46,31 +52,52 @@ macro_rules! pub_struct {
     }
 }
 
+// signed NFT claim, deadline and nonce are only set for SNIP-12 claims
+pub struct NftClaim {
+    pub token_id: u64,
+    pub sig: Signature,
+    pub deadline: Option<i64>,
+    pub nonce: Option<FieldElement>,
+}
+
 pub async fn get_nft(
+    conf: &Config,
     quest_id: u32,
     task_id: u32,
     addr: &FieldElement,
-    nft_level: u32,
-    signer: &LocalWallet,
-) -> Result<(u64, Signature), Box<dyn std::error::Error + Send + Sync>> {
-    let token_id = match nft_level < 100 {
-        true => nft_level as u64 + 100 * (rand::random::<u64>() % (2u64.pow(32))),
-        false => {
-            (rand::random::<u64>() / 100 / 0x2000000 * 0x2000000 + nft_level as u64) * 100 + 99
+    token_id: u64,
+    signer: &dyn RewardSigner,
+) -> Result<NftClaim, Box<dyn std::error::Error + Send + Sync>> {
+    let settings = &conf.nft_contract.signature;
+    let (hashed, deadline, nonce) = match settings.scheme {
+        SignatureScheme::Legacy => {
+            let hashed = pedersen_hash(
+                &pedersen_hash(
+                    &pedersen_hash(
+                        &pedersen_hash(&FieldElement::from(token_id), &FieldElement::ZERO),
+                        &FieldElement::from(quest_id),
+                    ),
+                    &FieldElement::from(task_id),
+                ),
+                addr,
+            );
+            (hashed, None, None)
+        }
+        SignatureScheme::Snip12 => {
+            let signing = get_signing(&conf.signing)?;
+            let (deadline, nonce) = get_claim_expiry(signing);
+            let message = get_nft_claim(token_id, quest_id, task_id, deadline, nonce);
+            let hashed = get_claim_hash(signing, settings, *addr, &message)?;
+            (hashed, Some(deadline), Some(nonce))
         }
     };
-    let hashed = pedersen_hash(
-        &pedersen_hash(
-            &pedersen_hash(
-                &pedersen_hash(&FieldElement::from(token_id), &FieldElement::ZERO),
-                &FieldElement::from(quest_id),
-            ),
-            &FieldElement::from(task_id),
-        ),
-        addr,
-    );
     let sig = signer.sign_hash(&hashed).await?;
-    Ok((token_id, sig))
+    Ok(NftClaim {
+        token_id,
+        sig,
+        deadline,
+        nonce,
+    })
 }
 
 pub fn calculate_hash(t: &String) -> u64 {
@@ -79,6 +106,11 @@ pub fn calculate_hash(t: &String) -> u64 {
     hasher.finish()
 }
 
+// secrets are never read from the config file, only from the environment
+pub fn get_secret(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("Environment variable {} is not set", name))
+}
+
 pub fn get_error(error: String) -> Response {
     (StatusCode::INTERNAL_SERVER_ERROR, error).into_response()
 }
@@ -123,13 +155,43 @@ pub fn success_redirect(redirect_uri: String) -> Response {
     response.into_response()
 }
 
+#[derive(Debug)]
+pub enum CompletedTaskError {
+    Ineligible(EligibilityError),
+    Database(mongodb::error::Error),
+}
+
+impl std::fmt::Display for CompletedTaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompletedTaskError::Ineligible(e) => write!(f, "{}", e),
+            CompletedTaskError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl IntoResponse for CompletedTaskError {
+    fn into_response(self) -> Response {
+        match self {
+            CompletedTaskError::Ineligible(e) => e.into_response(),
+            CompletedTaskError::Database(e) => get_error(format!("{}", e)),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for CompletedTaskError {
+    fn from(e: mongodb::error::Error) -> Self {
+        CompletedTaskError::Database(e)
+    }
+}
+
 #[async_trait]
 pub trait CompletedTasksTrait {
     async fn upsert_completed_task(
         &self,
         addr: FieldElement,
         task_id: u32,
-    ) -> Result<UpdateResult, mongodb::error::Error>;
+    ) -> Result<UpdateResult, CompletedTaskError>;
 }
 
 #[async_trait]
@@ -138,7 +200,11 @@ impl CompletedTasksTrait for AppState {
         &self,
         addr: FieldElement,
         task_id: u32,
-    ) -> Result<UpdateResult, mongodb::error::Error> {
+    ) -> Result<UpdateResult, CompletedTaskError> {
+        check_task_eligibility(self, &addr, task_id)
+            .await
+            .map_err(CompletedTaskError::Ineligible)?;
+
         let completed_tasks_collection: Collection<CompletedTasks> =
             self.db.collection("completed_tasks");
         let created_at = Utc::now().timestamp_millis();
@@ -228,15 +294,25 @@ impl CompletedTasksTrait for AppState {
                     doc! {
                         "$project": doc! {
                             "_id": 0,
+                            "quest_id": "$associatedQuests.id",
                             "experience": "$associatedQuests.experience",
+                            "category": "$associatedQuests.category",
                         }
                     },
                 ];
                 match completed_tasks_collection.aggregate(pipeline, None).await {
                     Ok(mut cursor) => {
+                        let mut quest_id = 0;
                         let mut experience = 0;
+                        let mut category = String::new();
                         while let Some(response) = cursor.try_next().await.unwrap() {
+                            quest_id = match response.get("quest_id") {
+                                Some(Bson::Int64(id)) => *id as u32,
+                                Some(Bson::Int32(id)) => *id as u32,
+                                _ => 0,
+                            };
                             experience = response.get("experience").unwrap().as_i32().unwrap();
+                            category = response.get_str("category").unwrap_or_default().to_string();
                         }
 
                         // return result if experience is 0 (quest is not completed)
@@ -244,21 +320,25 @@ impl CompletedTasksTrait for AppState {
                             return Ok(result);
                         }
 
-                        // save the user_exp document in the collection
-                        let user_exp_collection = self.db.collection("user_exp");
-                        // add doc with address ,experience and timestamp
-                        let timestamp: f64 = Utc::now().timestamp_millis() as f64;
-                        let document = doc! { "address": addr.to_string(), "experience":experience, "timestamp":timestamp};
-                        user_exp_collection.insert_one(document, None).await?;
-                        let view_collection: Collection<LeaderboardTable> =
-                            self.db.collection("leaderboard_table");
-                        update_leaderboard(
-                            view_collection,
-                            addr.to_string(),
+                        // concurrent completions of the last tasks of a quest grant it only once
+                        if let Some(timestamp) = grant_experience(
+                            &self.db,
+                            &addr.to_string(),
+                            &XpSource::Quest(quest_id),
                             experience.into(),
-                            timestamp,
                         )
-                        .await;
+                        .await?
+                        {
+                            credit_season_experience(
+                                &self.db,
+                                &self.logger,
+                                &addr.to_string(),
+                                Some(&category),
+                                experience.into(),
+                                timestamp,
+                            )
+                            .await;
+                        }
                     }
                     Err(_e) => {
                         get_error("Error querying quests".to_string());
@@ -347,20 +427,24 @@ impl AchievementsTrait for AppState {
                     experience = doc.experience as i32;
                 }
 
-                let user_exp_collection = self.db.collection("user_exp");
-                // add doc with address ,experience and timestamp
-                let timestamp: f64 = Utc::now().timestamp_millis() as f64;
-                let document = doc! { "address": addr.to_string(), "experience":experience, "timestamp":timestamp};
-                user_exp_collection.insert_one(document, None).await?;
-                let view_collection: Collection<LeaderboardTable> =
-                    self.db.collection("leaderboard_table");
-                update_leaderboard(
-                    view_collection,
-                    addr.to_string(),
+                if let Some(timestamp) = grant_experience(
+                    &self.db,
+                    &addr.to_string(),
+                    &XpSource::Achievement(achievement_id),
                     experience.into(),
-                    timestamp,
                 )
-                .await;
+                .await?
+                {
+                    credit_season_experience(
+                        &self.db,
+                        &self.logger,
+                        &addr.to_string(),
+                        None,
+                        experience.into(),
+                        timestamp,
+                    )
+                    .await;
+                }
             }
             None => {}
         }
@@ -437,293 +521,70 @@ impl DeployedTimesTrait for AppState {
     }
 }
 
-pub async fn fetch_json_from_url(url: String) -> Result<serde_json::Value, String> {
-    let client = reqwest::Client::new();
-    match client.get(url).send().await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
+pub async fn fetch_json_from_url(
+    http: &HttpClient,
+    url: String,
+) -> Result<serde_json::Value, String> {
+    match http.send(http.get(&url)).await {
+        Ok(response) => match response.json::<serde_json::Value>() {
             Ok(json) => Ok(json),
             Err(e) => Err(format!("Failed to get JSON response: {}", e)),
         },
-        Err(e) => Err(format!("Failed to send request: {}", e)),
+        Err(e) => Err(format!("{}", e)),
     }
 }
 
-pub async fn update_leaderboard(
-    view_collection: Collection<LeaderboardTable>,
-    address: String,
-    experience: i64,
-    timestamp: f64,
-) {
-    // get current experience and new experience to it
-    let mut old_experience = 0;
-    let filter = doc! { "_id": &*address };
-    let mut cursor: Cursor<LeaderboardTable> = view_collection.find(filter, None).await.unwrap();
-    while let Some(doc) = cursor.try_next().await.unwrap() {
-        old_experience = doc.experience;
+// paths are relative to the app unless they are full urls
+pub fn get_app_url(app_link: &str, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        path.to_string()
+    } else {
+        format!("{}{}", app_link, path)
     }
-
-    // update the view collection
-    let filter = doc! { "_id": &*address };
-    let update =
-        doc! { "$set": { "experience": old_experience + experience, "timestamp": timestamp } };
-    let options = UpdateOptions::builder().upsert(true).build();
-    view_collection
-        .update_one(filter, update, options)
-        .await
-        .unwrap();
 }
 
-pub async fn add_leaderboard_table(db: &Database) {
-    let view_collection_name = "leaderboard_table";
-
-    let pipeline = vec![
-        doc! {
-            "$group": doc!{
-                "_id": "$address",
-                "experience": doc!{
-                    "$sum": "$experience"
-                },
-                "timestamp": doc! {
-                    "$last": "$timestamp"
-                }
-            }
-        },
-        doc! { "$merge" : doc! { "into":  view_collection_name , "on": "_id",  "whenMatched": "replace", "whenNotMatched": "insert" } },
-    ];
-
-    let view_collection: Collection<LeaderboardTable> =
-        db.collection::<LeaderboardTable>(view_collection_name);
-    let source_collection = db.collection::<UserExperience>("user_exp");
-
-    // create materialised view
-    source_collection.aggregate(pipeline, None).await.unwrap();
-
-    //create multiple indexes to speed it up
-    let timestamp_only = IndexModel::builder().keys(doc! { "timestamp":1}).build();
-    view_collection
-        .create_index(timestamp_only, None)
+// timestamp in seconds at which address completed the last task of quest_id, None while tasks
+// of the quest are left
+pub async fn get_quest_completion_date(db: &Database, address: &str, quest_id: i64) -> Option<i64> {
+    let task_ids = db
+        .collection::<Document>("tasks")
+        .distinct("id", doc! { "quest_id": quest_id }, None)
         .await
-        .unwrap();
-    let addrs_only = IndexModel::builder().keys(doc! { "_id":1}).build();
-    view_collection
-        .create_index(addrs_only, None)
+        .ok()?;
+    if task_ids.is_empty() {
+        return None;
+    }
+    let completed: Vec<Document> = db
+        .collection::<Document>("completed_tasks")
+        .find(
+            doc! { "address": address, "task_id": { "$in": &task_ids } },
+            None,
+        )
         .await
-        .unwrap();
-    let compound_index = IndexModel::builder()
-        .keys(doc! { "experience": -1,"timestamp":1,"_id":1})
-        .build();
-    view_collection
-        .create_index(compound_index, None)
+        .ok()?
+        .try_collect()
         .await
-        .unwrap();
-}
-
-pub async fn fetch_and_update_boosts_winner(
-    boost_collection: Collection<BoostTable>,
-    completed_tasks_collection: Collection<CompletedTasks>,
-    interval: u64,
-    logger: Logger,
-) {
-    loop {
-        let pipeline = vec![doc! {
-            "$match": {
-                "expiry":{
-                    "$lt": Utc::now().timestamp_millis()
-                },
-                "winner": {
-                    "$eq": null,
-                },
-            }
-        }];
-        match boost_collection.aggregate(pipeline, None).await {
-            Ok(mut cursor) => {
-                while let Some(doc) = cursor.try_next().await.unwrap() {
-                    let mut num_of_winners = doc.get("num_of_winners").unwrap().as_i32().unwrap();
-                    // use this variable to add some extra winners so that we have some extra winners incase anyone user repeats
-                    let extra_winners = 10;
-                    match doc.get("quests") {
-                        Some(quests_res) => {
-                            let quests = quests_res.as_array().unwrap();
-                            let mut address_list: Vec<FieldElement> = Vec::new();
-                            for quest in quests {
-                                let get_users_per_quest_pipeline = vec![
-                                    doc! {
-                                        "$lookup": doc! {
-                                            "from": "tasks",
-                                            "localField": "task_id",
-                                            "foreignField": "id",
-                                            "as": "associated_tasks"
-                                        }
-                                    },
-                                    doc! {
-                                        "$match": doc! {
-                                            "$expr": doc! {
-                                                "$eq": [
-                                                    doc! {
-                                                        "$first": "$associated_tasks.quest_id"
-                                                    },
-                                                    quest
-                                                ]
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$group": doc! {
-                                            "_id": "$address",
-                                            "tasks_list": doc! {
-                                                "$push": doc! {
-                                                    "$arrayElemAt": [
-                                                        "$associated_tasks",
-                                                        0
-                                                    ]
-                                                }
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$unwind": "$tasks_list"
-                                    },
-                                    doc! {
-                                        "$group": doc! {
-                                            "_id": doc! {
-                                                "address": "$_id",
-                                                "quest_id": "$tasks_list.quest_id"
-                                            },
-                                            "tasks_array": doc! {
-                                                "$push": "$tasks_list"
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$project": doc! {
-                                            "_id": 0,
-                                            "address": "$_id.address",
-                                            "quest_id": "$_id.quest_id",
-                                            "tasks_array": 1
-                                        }
-                                    },
-                                    doc! {
-                                        "$lookup": doc! {
-                                            "from": "tasks",
-                                            "localField": "quest_id",
-                                            "foreignField": "quest_id",
-                                            "as": "associatedTasks"
-                                        }
-                                    },
-                                    doc! {
-                                        "$match": doc! {
-                                            "$expr": doc! {
-                                                "$eq": [
-                                                    doc! {
-                                                        "$size": "$tasks_array"
-                                                    },
-                                                    doc! {
-                                                        "$size": "$associatedTasks"
-                                                    }
-                                                ]
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$project": doc! {
-                                            "address": "$address"
-                                        }
-                                    },
-                                    doc! {
-                                        "$sample":{
-                                            "size":num_of_winners+extra_winners
-                                        }
-                                    },
-                                ];
-                                match completed_tasks_collection
-                                    .aggregate(get_users_per_quest_pipeline, None)
-                                    .await
-                                {
-                                    Ok(mut cursor) => {
-                                        while let Some(doc) = cursor.try_next().await.unwrap() {
-                                            let address =
-                                                doc.get("address").unwrap().as_str().unwrap();
-                                            let formatted_address =
-                                                FieldElement::from_str(address).unwrap();
-                                            address_list.push(formatted_address);
-                                        }
-                                    }
-                                    Err(_err) => {}
-                                }
-                            }
-
-                            // skip if no user has completed quests
-                            if address_list.len() == 0 {
-                                continue;
-                            }
-                            let mut random_index;
-                            let mut winner_array: Vec<String> = Vec::new();
-
-                            // if length of address list is 1 then select the only user
-                            if address_list.len() == 1 {
-                                let winner = &address_list[0].to_string();
-                                let formatted_winner = FieldElement::from_str(winner).unwrap();
-                                winner_array.push(to_hex(formatted_winner));
-                            }
-                            // else select random users
-                            else {
-                                let mut current_winner_index = 0;
-                                // handle case when number of winners is greater than number of users then assign all users as winners
-                                if address_list.len() < num_of_winners as usize {
-                                    num_of_winners = address_list.len() as i32;
-                                }
-                                let mut iter_index = 0;
-                                loop {
-                                    let mut rng = rand::thread_rng();
-
-                                    let die = Uniform::new(0, address_list.len());
-                                    random_index = die.sample(&mut rng);
-
-                                    let winner = &address_list[random_index].to_string();
-                                    let formatted_winner = FieldElement::from_str(winner).unwrap();
-                                    if !winner_array.contains(&to_hex(formatted_winner)) {
-                                        winner_array.push(to_hex(formatted_winner));
-                                        current_winner_index += 1;
-                                    }
-                                    iter_index += 1;
-                                    if current_winner_index == (num_of_winners) as usize
-                                        || iter_index == address_list.len()
-                                    {
-                                        break;
-                                    }
-                                }
-                            }
-
-                            let filter = doc! { "id": doc.get("id").unwrap().as_i32().unwrap() };
-                            let update = doc! { "$set": { "winner": winner_array  } };
-                            let options = UpdateOptions::builder().upsert(true).build();
-                            boost_collection
-                                .update_one(filter, update, options)
-                                .await
-                                .unwrap();
-                        }
-                        None => {
-                            logger.info("No winners found");
-                        }
-                    }
-                }
-            }
-            Err(_err) => logger.info(_err.to_string()),
-        };
-
-        sleep(Duration::from_secs(interval)).await;
+        .ok()?;
+    // a quest with tasks left is not completed, whatever was done
+    let done: HashSet<String> = completed
+        .iter()
+        .filter_map(|task| task.get("task_id").map(|id| id.to_string()))
+        .collect();
+    if done.len() < task_ids.len() {
+        return None;
     }
+    let completed_at = completed
+        .iter()
+        .filter_map(|task| task.get_i64("timestamp").ok())
+        .max()?;
+    Some(completed_at / 1000)
 }
 
-pub fn run_boosts_raffle(db: &Database, interval: u64, logger: Logger) {
-    let boost_collection = db.collection::<BoostTable>("boosts");
-    let completed_tasks_collection = db.collection::<CompletedTasks>("completed_tasks");
-    tokio::spawn(fetch_and_update_boosts_winner(
-        boost_collection,
-        completed_tasks_collection,
-        interval,
-        logger,
-    ));
+pub fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == 11000
+    )
 }
 
 pub async fn verify_task_auth(
@@ -898,25 +759,3 @@ pub async fn read_contract(
         )
         .await
 }
-
-pub async fn check_if_unclaimed(
-    state: &AppState,
-    contract: FieldElement,
-    selector: FieldElement,
-    calldata: Vec<FieldElement>,
-    source: RewardSource,
-) -> bool {
-    let logger = &state.logger;
-    match read_contract(state, contract, selector, calldata).await {
-        Ok(result) => result.get(0) == Some(&FieldElement::ZERO),
-        Err(err) => {
-            logger.severe(format!(
-                "Error checking {:?} claim status: {:?} in {}",
-                source,
-                err,
-                to_hex(contract)
-            ));
-            false
-        }
-    }
-}